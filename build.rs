@@ -0,0 +1,132 @@
+//! Embeds build provenance and the application's identity from
+//! `[package.metadata.app]` in `Cargo.toml` into the binary as
+//! `FIXME_BUILD_*` environment variables, read by `src/version.rs` and
+//! `src/identity.rs`, and with the `grpc` feature generates the daemon's
+//! gRPC interface from `proto/`.
+
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+
+    let commit = git(&["rev-parse", "--short=12", "HEAD"]);
+    let tag = git(&["describe", "--tags", "--exact-match", "HEAD"]);
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+        .map(|status| (!status.is_empty()).to_string());
+    // Honor SOURCE_DATE_EPOCH so reproducible builds embed a fixed time.
+    let built = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+        .unwrap_or_else(SystemTime::now);
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc = output(Command::new(rustc).arg("--version"));
+
+    set("COMMIT", commit);
+    set("TAG", tag);
+    set("DIRTY", dirty);
+    set(
+        "TIMESTAMP",
+        Some(humantime::format_rfc3339_seconds(built).to_string()),
+    );
+    set("RUSTC", rustc);
+    set("TARGET", std::env::var("TARGET").ok());
+    set("FEATURES", Some(features()));
+    identity();
+
+    #[cfg(feature = "grpc")]
+    compile_protos();
+}
+
+/// Generates the gRPC control interface, with the vendored `protoc` unless
+/// `$PROTOC` names another.
+#[cfg(feature = "grpc")]
+fn compile_protos() {
+    println!("cargo:rerun-if-changed=proto/control.proto");
+    if std::env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("protoc is vendored");
+        std::env::set_var("PROTOC", protoc);
+    }
+    tonic_prost_build::compile_protos("proto/control.proto").expect("proto/control.proto compiles");
+}
+
+/// Exposes the `name`, `display-name`, `qualifier` and `organization` of
+/// `[package.metadata.app]`, and the prefix of environment variables derived
+/// from the name. Without them the name and organization default to the
+/// package's name and the qualifier to `com`.
+fn identity() {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let path = std::path::Path::new(&dir).join("Cargo.toml");
+    let manifest = std::fs::read_to_string(path).expect("Cargo.toml is readable");
+    let manifest: toml::Value = manifest.parse().expect("Cargo.toml is valid");
+    let app = manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("app"));
+    let field = |key: &str| {
+        app.and_then(|app| app.get(key))
+            .and_then(toml::Value::as_str)
+            .map(str::to_string)
+    };
+    let name = field("name").unwrap_or_else(|| std::env::var("CARGO_PKG_NAME").unwrap());
+    let ident: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect();
+
+    set(
+        "APP_DISPLAY_NAME",
+        field("display-name").or(Some(name.clone())),
+    );
+    set(
+        "APP_QUALIFIER",
+        field("qualifier").or(Some("com".to_string())),
+    );
+    set(
+        "APP_ORGANIZATION",
+        field("organization").or(Some(name.clone())),
+    );
+    set("APP_ENV_PREFIX", Some(format!("{}_", ident.to_uppercase())));
+    set("APP_IDENT", Some(ident));
+    set("APP_NAME", Some(name));
+}
+
+/// Returns the enabled Cargo features besides `default`, sorted and
+/// comma-separated.
+fn features() -> String {
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(name, _)| {
+            let feature = name.strip_prefix("CARGO_FEATURE_")?;
+            (feature != "DEFAULT").then(|| feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    features.join(",")
+}
+
+/// Exposes `value` to the crate as `FIXME_BUILD_<name>`, empty if unknown.
+fn set(name: &str, value: Option<String>) {
+    println!(
+        "cargo:rustc-env=FIXME_BUILD_{}={}",
+        name,
+        value.unwrap_or_default()
+    );
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    output(Command::new("git").args(args))
+}
+
+/// Returns the trimmed stdout of `command`, or `None` if it failed.
+fn output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok().filter(|o| o.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
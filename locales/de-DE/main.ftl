@@ -0,0 +1,42 @@
+# Messages shown by the application, in German.
+
+app-about = { $description }
+app-exit-status =
+    Exit-Status:
+      0    Erfolg
+      1    der Unterbefehl ist fehlgeschlagen
+      64   Aufruffehler, auch ein fehlender oder unbekannter Unterbefehl
+      78   Konfigurationsfehler
+      124  Zeitlimit überschritten
+      130  abgebrochen
+      141  die Ausgabe wurde geschlossen, z. B. von head
+
+arg-verbose = Legt fest, wie ausführlich protokolliert wird.
+arg-output = Format des Ergebnisses [Standard: human im Terminal, sonst json].
+arg-columns = Kommagetrennte Spalten, die in Tabellen und Berichten angezeigt werden, in dieser Reihenfolge.
+arg-format = Gibt jeden Ergebnisdatensatz über eine Vorlage aus, z. B. '{"{{"} name {"}}"}: {"{{"} value {"}}"}'.
+arg-ascii = Nur einfaches ASCII ausgeben, ohne Unicode-Symbole oder Rahmenzeichen.
+arg-yes = Alle Rückfragen mit Ja beantworten und Standardwerte ohne Nachfrage übernehmen.
+arg-no-input = Mit einem Fehler abbrechen, statt nach Eingaben zu fragen.
+arg-timeout = Den Unterbefehl abbrechen, wenn er länger läuft, z. B. 30s oder 5m.
+arg-retries = Den Unterbefehl bei vorübergehenden Fehlern bis zu so oft erneut ausführen.
+arg-retry-delay = Wartezeit vor dem ersten erneuten Versuch, danach jeweils verdoppelt, z. B. 500ms.
+arg-batch = Den Unterbefehl für jede Zeile dieser Datei (oder - für stdin) einmal ausführen und die Wörter der Zeile anhängen.
+arg-jobs = Anzahl der Stapeleinträge, die gleichzeitig ausgeführt werden.
+arg-batch-order = Reihenfolge, in der die Ausgaben gleichzeitig ausgeführter Stapeleinträge geschrieben werden.
+arg-watch = Den Unterbefehl erneut ausführen, sobald sich eine dieser Dateien oder eines dieser Verzeichnisse ändert.
+arg-dry-run = Zeigen, was sich ändern würde, ohne etwas zu ändern.
+arg-report = Wie der Lauf seinen Fortschritt, Warnungen und Ergebnisse meldet.
+arg-copy = Den wichtigsten Wert des Ergebnisses zusätzlich in die Zwischenablage kopieren.
+arg-width = Hilfe, Tabellen und Text nach so vielen Spalten umbrechen, oder bei 0 gar nicht [Standard: $COLUMNS, sonst die Breite des Terminals].
+arg-stats = Nach dem Lauf Laufzeit, maximalen Speicherbedarf und CPU-Zeit auf stderr ausgeben.
+arg-metrics-out = Die Metriken des Laufs im Textformat von Prometheus in diese Datei schreiben.
+arg-profile-out = Eine Aufzeichnung, wofür der Lauf seine Zeit brauchte, für Perfetto oder speedscope in diese Datei schreiben.
+arg-locale = Zahlen und Datumsangaben in der menschenlesbaren Ausgabe wie in diesem Gebietsschema üblich schreiben, z. B. de-DE.
+arg-seed = Zufallszahlen mit diesem Wert initialisieren, um einen Lauf zu wiederholen [Standard: bei jedem Lauf ein neuer].
+arg-help-all = Hilfe ausgeben, einschließlich der Optionen für Entwickler der Anwendung.
+arg-negated = Schaltet --{ $flag } aus, auch wenn die Einstellung es aktiviert.
+
+error-label = Fehler
+warning-label = Warnung
+error-hint = Weitere Informationen zu diesem Fehler erhalten Sie mit `{ $binary } explain { $code }`.
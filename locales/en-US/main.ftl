@@ -0,0 +1,45 @@
+# Messages shown by the application, in American English.
+#
+# This catalog is the reference every other locale falls back to: add new
+# messages here first, then translate them in the other catalogs.
+
+app-about = { $description }
+app-exit-status =
+    Exit status:
+      0    success
+      1    the subcommand failed
+      64   usage error, including a missing or unknown subcommand
+      78   configuration error
+      124  timed out
+      130  interrupted
+      141  the output was closed, e.g. by head
+
+arg-verbose = Set the logging verbosity level.
+arg-output = Format of the subcommand's result [default: human on a terminal, json otherwise].
+arg-columns = Comma-separated columns to show in tabular output and reports, in order.
+arg-format = Render each result record through a template, e.g. '{"{{"} name {"}}"}: {"{{"} value {"}}"}'.
+arg-ascii = Draw only plain ASCII, without Unicode symbols or box-drawing characters.
+arg-yes = Answer yes to every confirmation and accept defaults without asking.
+arg-no-input = Fail instead of prompting when input would be needed.
+arg-timeout = Cancel the subcommand if it runs longer than this, e.g. 30s or 5m.
+arg-retries = Run the subcommand again up to this many times when it fails transiently.
+arg-retry-delay = Delay before the first retry, doubling for each one after, e.g. 500ms.
+arg-batch = Run the subcommand once per line of this file, or - for stdin, appending the line's words.
+arg-jobs = Number of batch items to run at the same time.
+arg-batch-order = Order in which the output of concurrent batch items is written.
+arg-watch = Run the subcommand again whenever one of these files or directories changes.
+arg-dry-run = Show what would change without changing anything.
+arg-report = How the run reports its progress, warnings and results.
+arg-copy = Also copy the primary value of the result to the clipboard.
+arg-width = Wrap help, tables and text at this many columns, or 0 not to [default: $COLUMNS, else the terminal's width].
+arg-stats = Print the run's wall time, peak memory and CPU time to stderr once it finishes.
+arg-metrics-out = Write the run's metrics to this file in Prometheus' text format.
+arg-profile-out = Write a trace of where the run spent its time to this file, for Perfetto or speedscope.
+arg-locale = Write numbers and dates in human output as usual in this locale, e.g. de-DE.
+arg-seed = Seed random numbers with this, to reproduce a run [default: a fresh one each run].
+arg-help-all = Print help, including the options for developers of the application.
+arg-negated = Turn off --{ $flag }, even where the setting enables it.
+
+error-label = Error
+warning-label = Warning
+error-hint = For more information about this error, try `{ $binary } explain { $code }`.
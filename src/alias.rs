@@ -0,0 +1,92 @@
+use std::ffi::OsString;
+
+use cor_args::Handler;
+
+use crate::{Error, Result};
+
+/// Returns the expansion of the alias `name`, configured as `alias.<name>`.
+pub fn lookup(settings: &dyn Handler, name: &str) -> Option<String> {
+    settings.handle_request(&format!("alias.{}", name))
+}
+
+/// Expands the alias at `args[position]` into the words it stands for, like
+/// git aliases, then any alias those words start with in turn.
+///
+/// Names for which `is_command` holds are never expanded, so aliases cannot
+/// shadow built-in subcommands. Returns `None` if `args[position]` is not an
+/// alias.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if an alias is empty, has unbalanced quotes,
+/// or expands to itself through other aliases.
+pub fn expand<F>(
+    args: &[OsString],
+    position: usize,
+    settings: &dyn Handler,
+    is_command: F,
+) -> Result<Option<Vec<OsString>>>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut args = args.to_vec();
+    let mut chain: Vec<String> = Vec::new();
+    while let Some(name) = args
+        .get(position)
+        .and_then(|a| a.to_str())
+        .map(str::to_string)
+    {
+        if is_command(&name) {
+            break;
+        }
+        let Some(expansion) = lookup(settings, &name) else {
+            break;
+        };
+        if chain.contains(&name) {
+            chain.push(name);
+            return Err(Error::Subcommand(format!(
+                "alias loop: {}",
+                chain.join(" -> ")
+            )));
+        }
+        let words = shell_words::split(&expansion)
+            .map_err(|e| Error::Subcommand(format!("invalid alias '{}': {}", name, e)))?;
+        if words.is_empty() {
+            return Err(Error::Subcommand(format!("alias '{}' is empty", name)));
+        }
+        args.splice(position..=position, words.into_iter().map(OsString::from));
+        chain.push(name);
+    }
+    Ok((!chain.is_empty()).then_some(args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::JsonFileHandler;
+
+    #[test]
+    fn test_expand_follows_aliases_and_detects_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"alias": {"f": "g --x", "g": "fixme1 'a b'", "loop": "again", "again": "loop"}}"#,
+        )
+        .unwrap();
+        let settings = JsonFileHandler::new(&path);
+        let args = |words: &[&str]| words.iter().map(OsString::from).collect::<Vec<_>>();
+        let is_command = |name: &str| name == "fixme1";
+
+        assert_eq!(
+            Some(args(&["fixme", "fixme1", "a b", "--x", "y"])),
+            expand(&args(&["fixme", "f", "y"]), 1, &settings, is_command).unwrap()
+        );
+        assert_eq!(
+            None,
+            expand(&args(&["fixme", "fixme1"]), 1, &settings, is_command).unwrap()
+        );
+        let err = expand(&args(&["fixme", "loop"]), 1, &settings, is_command).unwrap_err();
+        assert_eq!("alias loop: loop -> again -> loop", err.to_string());
+    }
+}
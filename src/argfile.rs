@@ -0,0 +1,85 @@
+use std::ffi::OsString;
+use std::path::Path;
+
+use crate::{Error, Result};
+
+/// Marks an argument naming a response file, e.g. `@args.txt`.
+pub const PREFIX: char = '@';
+
+/// Replaces every `@path` argument with the arguments read from `path`.
+///
+/// A response file holds arguments separated by whitespace or line breaks,
+/// quoted as in a POSIX shell where they contain spaces; lines starting with
+/// `#` are comments. Arguments read from a file are not expanded again, and
+/// nothing after a `--` argument is expanded. The first argument, the program
+/// name, is kept as is.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if a response file cannot be read or has unbalanced quotes.
+pub fn expand<I, T>(args: I) -> Result<Vec<OsString>>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut expanded = Vec::new();
+    let mut literal = false;
+    for (i, arg) in args.into_iter().map(Into::into).enumerate() {
+        if i == 0 || literal {
+            expanded.push(arg);
+            continue;
+        }
+        match arg.to_str() {
+            Some("--") => {
+                literal = true;
+                expanded.push(arg);
+            }
+            Some(s) if s.len() > 1 && s.starts_with(PREFIX) => {
+                expanded.extend(read(Path::new(&s[1..]))?.into_iter().map(OsString::from));
+            }
+            _ => expanded.push(arg),
+        }
+    }
+    Ok(expanded)
+}
+
+/// Reads the arguments of the response file at `path`.
+fn read(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path).map_err(Error::io(path))?;
+    let uncommented: String = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    shell_words::split(&uncommented)
+        .map_err(|e| Error::io(path)(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_reads_response_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("args.txt");
+        std::fs::write(&path, "# generated\n-v debug\nfixme2 'two words'\n").unwrap();
+        let argfile = format!("@{}", path.display());
+
+        let args = expand(["fixme", argfile.as_str(), "--", argfile.as_str()]).unwrap();
+
+        assert_eq!(
+            vec![
+                "fixme",
+                "-v",
+                "debug",
+                "fixme2",
+                "two words",
+                "--",
+                &argfile
+            ],
+            args.iter().map(|a| a.to_str().unwrap()).collect::<Vec<_>>()
+        );
+        assert!(expand(["fixme", "@missing.txt"]).is_err());
+    }
+}
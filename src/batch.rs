@@ -0,0 +1,92 @@
+use std::ffi::OsString;
+use std::io::Write;
+
+use crate::{i18n, Error, Result};
+
+/// The order in which the output of concurrently run batch items is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Order {
+    /// In the order of the items in the input.
+    Input,
+    /// As soon as each item completes.
+    Completion,
+}
+
+/// A batch item that ran on a worker thread, with the output it produced.
+pub struct Completed {
+    /// Position of the item in the batch.
+    pub index: usize,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub result: Result<()>,
+}
+
+/// Returns whether `args` contain a `--batch` option before any `--`.
+pub fn is_batch(args: &[OsString]) -> bool {
+    args.iter()
+        .map(|arg| arg.to_str().unwrap_or_default())
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--batch" || arg.starts_with("--batch="))
+}
+
+/// Makes the arguments of every subcommand of `command` optional, so that a
+/// command line missing the ones each batch item provides still parses.
+pub fn relax(command: clap::Command) -> clap::Command {
+    command.mut_subcommands(|sub| {
+        let sub = sub.subcommand_required(false);
+        let ids: Vec<_> = sub.get_arguments().map(|a| a.get_id().clone()).collect();
+        ids.into_iter()
+            .fold(sub, |sub, id| sub.mut_arg(id, |a| a.required(false)))
+    })
+}
+
+/// Writes the error of a failed batch `item` to `stderr`, prefixed with the item.
+pub fn report_failure(stderr: &mut dyn Write, item: &str, error: &Error) -> Result<()> {
+    writeln!(
+        stderr,
+        "{}: {}[{}]: {}",
+        item,
+        i18n::t("error-label"),
+        error.code(),
+        error
+    )?;
+    Ok(())
+}
+
+/// Returns the command line for one `--batch` item: `args` with the words of
+/// `item`, split as by a POSIX shell, inserted before any `--`.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if `item` has unbalanced quotes.
+pub fn item_args(args: &[OsString], item: &str) -> Result<Vec<OsString>> {
+    let words = shell_words::split(item)
+        .map_err(|e| Error::Subcommand(format!("invalid batch item: {}", e)))?;
+    let separator = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    let mut item_args = args.to_vec();
+    item_args.splice(separator..separator, words.into_iter().map(OsString::from));
+    Ok(item_args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_args_appends_words_before_separator() {
+        let args: Vec<OsString> = ["fixme", "--batch", "-", "fixme1", "--", "-x"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+
+        let item = item_args(&args, "'two words'").unwrap();
+
+        assert_eq!(
+            vec!["fixme", "--batch", "-", "fixme1", "two words", "--", "-x"],
+            item
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::path::PathBuf;
+
+use clap::CommandFactory;
+
+use crate::commands::{self, Command};
+use crate::identity::IDENTITY;
+use crate::{context, examples, flags, localize, schema, settings, theme, version, App, Cli};
+
+/// Configures an [`App`]: its identity, subcommands and where settings are read from.
+///
+/// Everything starts from the package's Cargo metadata and the built-in
+/// subcommands, so a project made from the template customizes itself here
+/// rather than throughout the code:
+///
+/// ```no_run
+/// # use template_rust_cli::App;
+/// App::builder()
+///     .about("Keeps the widgets in order")
+///     .env_prefix("WIDGETS_")
+///     .build()
+///     .run()
+///     .unwrap();
+/// ```
+pub struct AppBuilder {
+    name: String,
+    version: String,
+    long_version: String,
+    author: String,
+    about: String,
+    commands: Vec<Box<dyn Command>>,
+    layout: settings::Layout,
+}
+
+impl AppBuilder {
+    /// Starts from the binary's name, the authors and the description of
+    /// the [identity](IDENTITY), the [build](version::BUILD) version, the
+    /// built-in subcommands and the default [`settings::Layout`].
+    pub fn new() -> Self {
+        AppBuilder {
+            name: IDENTITY.binary.to_string(),
+            version: version::BUILD.short(),
+            long_version: version::BUILD.long(),
+            author: IDENTITY.authors.replace(':', ", "),
+            about: IDENTITY.description.to_string(),
+            commands: commands::registry(),
+            layout: settings::Layout::default(),
+        }
+    }
+
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the version shown by `--version`, both short and long.
+    pub fn version<S: Into<String>>(mut self, version: S) -> Self {
+        self.version = version.into();
+        self.long_version = self.version.clone();
+        self
+    }
+
+    pub fn author<S: Into<String>>(mut self, author: S) -> Self {
+        self.author = author.into();
+        self
+    }
+
+    /// Sets the description shown at the top of `--help`.
+    pub fn about<S: Into<String>>(mut self, about: S) -> Self {
+        self.about = about.into();
+        self
+    }
+
+    /// Registers a subcommand after those already registered.
+    pub fn command<C: Command + 'static>(mut self, command: C) -> Self {
+        self.commands.push(Box::new(command));
+        self
+    }
+
+    /// Replaces the configuration files settings are read from, in order of
+    /// precedence; settings are written to the first.
+    pub fn config_paths<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.layout.config_paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the prefix of environment variables settings are read from, e.g. `FIXME_`.
+    pub fn env_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.layout.env_prefix = prefix.into();
+        self
+    }
+
+    pub fn build(self) -> App {
+        let created = std::time::Instant::now();
+        let settings = self.layout.chain(None);
+        let commands = self.commands;
+        #[cfg(feature = "scripting")]
+        let commands = commands::script::register(commands);
+        let program = self.name.clone();
+        let mut examples = examples::Registry::new();
+        let cli = flags::add_relations(flags::add_negatable(Cli::command()), schema::RELATIONS);
+        let mut args = localize(cli)
+            .name(self.name)
+            .version(self.version)
+            .long_version(self.long_version)
+            .author(self.author)
+            .about(self.about)
+            .styles(theme::styles(&*settings));
+        for command in &commands {
+            let mut cmd = flags::add_relations(command.clap(), schema::RELATIONS);
+            if command.passthrough() {
+                cmd = cmd.arg(
+                    clap::Arg::new(context::PASSTHROUGH)
+                        .help("Arguments passed through untouched")
+                        .value_name("ARGS")
+                        .num_args(0..)
+                        .last(true)
+                        .allow_hyphen_values(true)
+                        .value_parser(clap::value_parser!(std::ffi::OsString)),
+                );
+            }
+            let named = command.examples().into_iter().map(|e| e.named(&program));
+            examples.register(cmd.get_name(), named.collect());
+            args = args.subcommand(cmd);
+        }
+        let args = flags::annotate_defaults(args, &self.layout);
+        App {
+            args: examples.apply(args.allow_external_subcommands(true)),
+            examples,
+            commands,
+            layout: self.layout,
+            created,
+            check_updates: false,
+        }
+    }
+}
+
+impl Default for AppBuilder {
+    fn default() -> Self {
+        AppBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_applies_identity_and_layout() {
+        let app = App::builder()
+            .name("widgets")
+            .version("2.0.0")
+            .about("Keeps the widgets in order")
+            .env_prefix("WIDGETS_")
+            .config_paths(["/etc/widgets.json"])
+            .build();
+
+        assert_eq!("widgets", app.command().get_name());
+        assert_eq!(Some("2.0.0"), app.command().get_version());
+        assert_eq!(
+            Some("Keeps the widgets in order"),
+            app.command()
+                .get_about()
+                .map(|about| about.to_string())
+                .as_deref()
+        );
+        assert_eq!("WIDGETS_", app.layout().env_prefix);
+        assert_eq!(
+            PathBuf::from("/etc/widgets.json"),
+            app.layout().config_path()
+        );
+        assert!(app.command().find_subcommand("config").is_some());
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{Error, Result};
+
+/// Places `text` on the system clipboard.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if there is no clipboard to use, e.g. in a
+/// session without a display server, or in a build without the `clipboard`
+/// feature.
+#[cfg(feature = "clipboard")]
+pub fn copy(text: &str) -> Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| Error::Subcommand(format!("failed to copy to the clipboard: {}", e)))
+}
+
+/// Always fails: there is no clipboard without the `clipboard` feature.
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: &str) -> Result<()> {
+    Err(Error::Subcommand(
+        "failed to copy to the clipboard: built without the clipboard feature".to_string(),
+    ))
+}
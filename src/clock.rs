@@ -0,0 +1,66 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// The source of the current time, so that time-dependent behaviour such as
+/// retry delays and cache expiry can be tested deterministically.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+
+    /// Waits for `duration` to pass.
+    fn sleep(&self, duration: Duration);
+}
+
+/// The operating system's clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// A clock that stands still until slept on, which advances it at once.
+#[derive(Debug)]
+pub struct FrozenClock {
+    now: Mutex<SystemTime>,
+}
+
+impl FrozenClock {
+    pub fn new(at: SystemTime) -> Self {
+        FrozenClock {
+            now: Mutex::new(at),
+        }
+    }
+}
+
+impl Clock for FrozenClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().expect("clock lock is not poisoned")
+    }
+
+    fn sleep(&self, duration: Duration) {
+        *self.now.lock().expect("clock lock is not poisoned") += duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frozen_clock_advances_only_when_slept() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = FrozenClock::new(start);
+        assert_eq!(start, clock.now());
+
+        clock.sleep(Duration::from_secs(90));
+
+        assert_eq!(start + Duration::from_secs(90), clock.now());
+    }
+}
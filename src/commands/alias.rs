@@ -0,0 +1,104 @@
+use std::ffi::OsString;
+
+use clap::{Args, Subcommand};
+use serde::Serialize;
+
+use crate::examples::Example;
+use crate::settings::JsonFileHandler;
+use crate::{alias, Context, Output, Result};
+
+/// Inspects command aliases defined in the config file
+#[derive(Debug, Args)]
+struct AliasArgs {
+    #[command(subcommand)]
+    command: AliasCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum AliasCommand {
+    /// Lists the aliases and what they expand to
+    List,
+}
+
+/// An alias, as listed by `alias list`.
+#[derive(Debug, Serialize)]
+struct Listed {
+    name: String,
+    expansion: String,
+    /// The command line after expanding aliases of aliases.
+    resolved: Option<String>,
+    /// Why the alias cannot be used, if it cannot.
+    problem: Option<String>,
+}
+
+pub struct Alias;
+
+impl super::Command for Alias {
+    fn clap(&self) -> clap::Command {
+        AliasArgs::augment_args(clap::Command::new("alias"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example::new(
+            "FIXME config set alias.f1 'fixme1 --dry-run'",
+            "Makes `FIXME f1 foo` run `FIXME fixme1 --dry-run foo`",
+        )]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: AliasArgs = ctx.args()?;
+        let AliasCommand::List = args.command;
+        let root = JsonFileHandler::new(ctx.app().layout().config_path()).load()?;
+        let aliases = root
+            .as_ref()
+            .and_then(|root| root.get("alias"))
+            .and_then(|aliases| aliases.as_object())
+            .cloned()
+            .unwrap_or_default();
+        let is_command = |name: &str| ctx.app().command().find_subcommand(name).is_some();
+
+        let mut listed = Vec::new();
+        for (name, expansion) in aliases {
+            let expansion = match expansion {
+                serde_json::Value::String(expansion) => expansion,
+                other => other.to_string(),
+            };
+            let (resolved, problem) = if is_command(&name) {
+                (
+                    None,
+                    Some(format!("shadowed by the built-in subcommand '{}'", name)),
+                )
+            } else {
+                match alias::expand(&[OsString::from(&name)], 0, ctx.settings(), is_command) {
+                    Ok(resolved) => (
+                        resolved.map(|words| {
+                            let words: Vec<_> = words.iter().map(|w| w.to_string_lossy()).collect();
+                            shell_words::join(words)
+                        }),
+                        None,
+                    ),
+                    Err(e) => (None, Some(e.to_string())),
+                }
+            };
+            listed.push(Listed {
+                name,
+                expansion,
+                resolved,
+                problem,
+            });
+        }
+
+        let human = listed
+            .iter()
+            .map(|l| match (&l.resolved, &l.problem) {
+                (_, Some(problem)) => format!("{} = {}  ({})", l.name, l.expansion, problem),
+                (Some(resolved), None) if *resolved != l.expansion => {
+                    format!("{} = {}  (runs {})", l.name, l.expansion, resolved)
+                }
+                _ => format!("{} = {}", l.name, l.expansion),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(Some(Output::new(&listed).human(human)))
+    }
+}
@@ -0,0 +1,233 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use clap::{Args, Subcommand};
+use serde::Serialize;
+
+use crate::examples::Example;
+use crate::render::locale::Locale;
+use crate::{paths, Context, Error, Output, Result};
+
+/// Shows or clears the application's cache of downloads and remote lookups
+#[derive(Debug, Args)]
+struct CacheArgs {
+    #[command(subcommand)]
+    command: CacheCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum CacheCommand {
+    /// Prints the cache directory
+    Dir,
+    /// Shows how many files the cache holds and their total size
+    Size,
+    /// Deletes cached files: all of them, or only those matching every filter
+    Clear {
+        /// Only files last modified longer ago than this, e.g. 7d
+        #[arg(long, value_name = "DURATION", value_parser = crate::values::duration)]
+        older_than: Option<Duration>,
+        /// Only files whose path within the cache matches this glob, e.g. 'downloads/*'
+        #[arg(long, value_name = "GLOB")]
+        pattern: Option<glob::Pattern>,
+    },
+}
+
+/// What `cache size` and `cache clear` report.
+#[derive(Debug, Serialize)]
+struct Usage {
+    path: String,
+    files: usize,
+    bytes: u64,
+}
+
+/// A file in the cache.
+#[derive(Debug)]
+struct Entry {
+    /// The path within the cache directory.
+    relative: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+pub struct Cache;
+
+impl super::Command for Cache {
+    fn clap(&self) -> clap::Command {
+        CacheArgs::augment_args(clap::Command::new("cache"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new("FIXME cache size", "Shows how much space the cache takes"),
+            Example::new(
+                "FIXME cache clear --older-than 30d",
+                "Deletes cached files not used for a month",
+            ),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: CacheArgs = ctx.args()?;
+        let dir = paths::cache_dir();
+        let usage = |entries: &[Entry]| Usage {
+            path: dir.display().to_string(),
+            files: entries.len(),
+            bytes: entries.iter().map(|entry| entry.bytes).sum(),
+        };
+        match args.command {
+            CacheCommand::Dir => {
+                writeln!(ctx.out(), "{}", dir.display()).map_err(Error::Output)?;
+                Ok(None)
+            }
+            CacheCommand::Size => {
+                let usage = usage(&entries(&dir)?);
+                let human = format!(
+                    "{} file(s), {} in {}",
+                    ctx.numbers().integer(usage.files as u64),
+                    size(usage.bytes, ctx.numbers()),
+                    usage.path
+                );
+                Ok(Some(Output::new(&usage).human(human)))
+            }
+            CacheCommand::Clear {
+                older_than,
+                pattern,
+            } => {
+                let now = ctx.time().now();
+                let selected: Vec<Entry> = entries(&dir)?
+                    .into_iter()
+                    .filter(|entry| matches(entry, now, older_than, pattern.as_ref()))
+                    .collect();
+                let usage = usage(&selected);
+                let description = format!(
+                    "delete {} file(s), {}, from {}",
+                    ctx.numbers().integer(usage.files as u64),
+                    size(usage.bytes, ctx.numbers()),
+                    usage.path
+                );
+                let cleared = ctx.effect(description, || {
+                    for entry in &selected {
+                        let path = dir.join(&entry.relative);
+                        std::fs::remove_file(&path).map_err(Error::io(&path))?;
+                    }
+                    Ok(())
+                })?;
+                Ok(cleared.map(|()| {
+                    let human = format!(
+                        "Deleted {} file(s), {}",
+                        ctx.numbers().integer(usage.files as u64),
+                        size(usage.bytes, ctx.numbers())
+                    );
+                    Output::new(&usage).human(human)
+                }))
+            }
+        }
+    }
+}
+
+/// Lists the files under `dir`, which need not exist.
+fn entries(dir: &Path) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e)
+                if e.io_error().map(std::io::Error::kind) == Some(std::io::ErrorKind::NotFound) =>
+            {
+                continue
+            }
+            Err(e) => {
+                let path = e.path().unwrap_or(dir).to_path_buf();
+                return Err(Error::io(&path)(e.into()));
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let metadata = entry
+            .metadata()
+            .map_err(|e| Error::io(entry.path())(e.into()))?;
+        entries.push(Entry {
+            relative: entry
+                .path()
+                .strip_prefix(dir)
+                .unwrap_or(entry.path())
+                .to_path_buf(),
+            bytes: metadata.len(),
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        });
+    }
+    Ok(entries)
+}
+
+/// Whether `entry` passes the filters of `cache clear`.
+fn matches(
+    entry: &Entry,
+    now: SystemTime,
+    older_than: Option<Duration>,
+    pattern: Option<&glob::Pattern>,
+) -> bool {
+    let old_enough = older_than
+        .map(|age| now.duration_since(entry.modified).unwrap_or_default() > age)
+        .unwrap_or(true);
+    let matching = pattern
+        .map(|pattern| pattern.matches_path(&entry.relative))
+        .unwrap_or(true);
+    old_enough && matching
+}
+
+/// Formats `bytes` with a binary unit, e.g. `1.5 KiB`.
+fn size(bytes: u64, locale: &Locale) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{} {}", locale.decimal(value, 1), UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::locale::PLAIN;
+
+    #[test]
+    fn test_clear_filters_by_age_and_pattern() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let entry = |relative: &str, age: u64| Entry {
+            relative: PathBuf::from(relative),
+            bytes: 1,
+            modified: now - Duration::from_secs(age),
+        };
+        let day = Duration::from_secs(86_400);
+        let downloads = glob::Pattern::new("downloads/*").unwrap();
+
+        assert!(matches(&entry("update-check.json", 0), now, None, None));
+        assert!(!matches(
+            &entry("downloads/a.tar.gz", 60),
+            now,
+            Some(day),
+            None
+        ));
+        assert!(matches(
+            &entry("downloads/a.tar.gz", 2 * 86_400),
+            now,
+            Some(day),
+            Some(&downloads)
+        ));
+        assert!(!matches(
+            &entry("update-check.json", 2 * 86_400),
+            now,
+            Some(day),
+            Some(&downloads)
+        ));
+        assert_eq!("1.5 KiB", size(1536, &PLAIN));
+        assert_eq!("1,5 KiB", size(1536, &Locale::new("de-DE")));
+    }
+}
@@ -0,0 +1,209 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use clap_complete::Shell;
+use log::info;
+
+use crate::examples::Example;
+use crate::{Context, Error, Output, Result};
+
+/// Generates shell completion scripts
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+struct CompletionsArgs {
+    /// Shell to print the completion script for
+    shell: Option<Shell>,
+
+    #[command(subcommand)]
+    command: Option<CompletionsCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+enum CompletionsCommand {
+    /// Installs the completion script for your shell
+    Install(InstallArgs),
+}
+
+#[derive(Debug, Args)]
+struct InstallArgs {
+    /// Shell to install for (detected from $SHELL by default)
+    #[arg(long, value_name = "SHELL")]
+    shell: Option<Shell>,
+}
+
+pub struct Completions;
+
+impl super::Command for Completions {
+    fn clap(&self) -> clap::Command {
+        CompletionsArgs::augment_args(clap::Command::new("completions"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new(
+                "FIXME completions bash > FIXME.bash",
+                "Writes the bash completion script to FIXME.bash",
+            ),
+            Example::new(
+                "FIXME completions install --dry-run",
+                "Shows where completions for the current shell would be installed",
+            ),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: CompletionsArgs = ctx.args()?;
+        let cmd = ctx.app().command();
+        if let Some(CompletionsCommand::Install(install_args)) = &args.command {
+            let shell = match install_args.shell {
+                Some(shell) => shell,
+                None => Shell::from_env().ok_or_else(|| {
+                    Error::Subcommand("unable to detect shell; pass --shell".to_string())
+                })?,
+            };
+            let home = home_dir()?;
+            let dry_run = ctx.is_dry_run();
+            let path = install_path(shell, cmd.get_name(), &home)?;
+            if !dry_run
+                && path.exists()
+                && !ctx
+                    .prompt()
+                    .confirm(&format!("Overwrite {}?", path.display()), false)?
+            {
+                return Ok(None);
+            }
+            install(cmd, shell, &home, dry_run, &mut *ctx.err())?;
+            return Ok(None);
+        }
+
+        let shell = match args.shell {
+            Some(shell) => shell,
+            None => Shell::from_env().ok_or_else(|| {
+                Error::Subcommand("unable to detect shell; pass a SHELL argument".to_string())
+            })?,
+        };
+        let mut cmd = cmd.clone();
+        let name = cmd.get_name().to_string();
+        clap_complete::generate(shell, &mut cmd, name, &mut *ctx.out());
+        Ok(None)
+    }
+}
+
+/// Writes the completion script for `shell` into its conventional location under `home`.
+///
+/// Reports to `status` the rc-file snippet needed to enable the completions afterwards, if any.
+/// When `dry_run` is set, only reports the destination and the snippet.
+///
+/// # Errors
+///
+/// Returns an error if the destination directory or script cannot be written.
+pub fn install(
+    cmd: &clap::Command,
+    shell: Shell,
+    home: &Path,
+    dry_run: bool,
+    status: &mut dyn Write,
+) -> Result<()> {
+    let mut cmd = cmd.clone();
+    let name = cmd.get_name().to_string();
+    let path = install_path(shell, &name, home)?;
+
+    if dry_run {
+        writeln!(
+            status,
+            "Would write {} completions to {}",
+            shell,
+            path.display()
+        )?;
+    } else {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(Error::io(parent))?;
+        }
+        let mut file = std::fs::File::create(&path).map_err(Error::io(&path))?;
+        clap_complete::generate(shell, &mut cmd, name.clone(), &mut file);
+        info!("Wrote completions: {}", path.display());
+        writeln!(
+            status,
+            "Installed {} completions to {}",
+            shell,
+            path.display()
+        )?;
+    }
+
+    if let Some(snippet) = rc_snippet(shell, &name, &path) {
+        writeln!(
+            status,
+            "Add the following to your shell's rc file to enable them:\n\n{}",
+            snippet
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the conventional completion script location for `shell`.
+fn install_path(shell: Shell, name: &str, home: &Path) -> Result<PathBuf> {
+    let path = match shell {
+        Shell::Bash => home
+            .join(".local/share/bash-completion/completions")
+            .join(name),
+        Shell::Zsh => home.join(".zsh/completions").join(format!("_{}", name)),
+        Shell::Fish => home
+            .join(".config/fish/completions")
+            .join(format!("{}.fish", name)),
+        Shell::Elvish => home
+            .join(".config/elvish/lib")
+            .join(format!("{}.elv", name)),
+        Shell::PowerShell => home
+            .join(".config/powershell")
+            .join(format!("{}.ps1", name)),
+        _ => return Err(Error::Subcommand(format!("unsupported shell: {}", shell))),
+    };
+    Ok(path)
+}
+
+/// Returns the rc-file lines needed to load completions from `path`, if any.
+fn rc_snippet(shell: Shell, name: &str, path: &Path) -> Option<String> {
+    match shell {
+        Shell::Zsh => Some(format!(
+            "fpath=({} $fpath)\nautoload -Uz compinit && compinit",
+            path.parent()?.display()
+        )),
+        Shell::Elvish => Some(format!("use {}", name)),
+        Shell::PowerShell => Some(format!(". {}", path.display())),
+        // bash-completion and fish load scripts from these directories automatically.
+        _ => None,
+    }
+}
+
+fn home_dir() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .ok_or_else(|| Error::Subcommand("unable to determine home directory".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_writes_script() {
+        let home = tempfile::tempdir().unwrap();
+        let cmd = clap::Command::new("fixme").subcommand(clap::Command::new("fixme1"));
+
+        install(&cmd, Shell::Zsh, home.path(), false, &mut Vec::new()).unwrap();
+
+        assert!(home.path().join(".zsh/completions/_fixme").exists());
+    }
+
+    #[test]
+    fn test_install_dry_run_writes_nothing() {
+        let home = tempfile::tempdir().unwrap();
+        let cmd = clap::Command::new("fixme");
+
+        install(&cmd, Shell::Fish, home.path(), true, &mut Vec::new()).unwrap();
+
+        assert!(!home.path().join(".config").exists());
+    }
+}
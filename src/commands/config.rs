@@ -0,0 +1,752 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand, ValueEnum};
+use cor_args::Handler;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::examples::Example;
+use crate::prompt::PromptMode;
+use crate::schema::ValueKind;
+use crate::settings::{JsonFileHandler, Resolved};
+use crate::values::PathArg;
+use crate::{schema, settings, Context, Error, InputSource, Output, Result};
+
+/// Reads and writes configuration settings
+#[derive(Debug, Args)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Prints the resolved value of a setting
+    Get {
+        /// Name of the setting, e.g. verbosity
+        key: String,
+    },
+    /// Stores a setting in the user's config file, asking for what is omitted
+    Set {
+        /// Name of the setting, e.g. verbosity; picked from a list if omitted
+        key: Option<String>,
+        /// Value to store; asked for if omitted
+        value: Option<String>,
+    },
+    /// Walks through every setting interactively and saves the answers
+    Wizard,
+    /// Lists where settings are read from, in order of precedence
+    Sources,
+    /// Prints every setting in effect, merged from all sources, with secrets masked
+    Dump {
+        /// Syntax of the printed configuration; `--format` is taken by templates
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t = Format::Json)]
+        to: Format,
+        /// Notes where each setting comes from in a comment; not for JSON
+        #[arg(long)]
+        sources: bool,
+    },
+    /// Rewrites a configuration file in another syntax, keeping its nesting and types
+    Convert {
+        /// Syntax of INPUT; guessed from its extension if omitted
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        from: Option<Format>,
+        /// Syntax to write; guessed from OUTPUT's extension if omitted
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        to: Option<Format>,
+        /// File to read, or - for stdin
+        input: InputSource,
+        /// File to write, or - for stdout
+        #[arg(value_name = "OUTPUT")]
+        destination: PathBuf,
+    },
+    /// Checks a config file against the schema, listing every problem found
+    Validate {
+        /// File to check; the user's config file if omitted
+        #[arg(value_parser = PathArg::new().file().parser())]
+        path: Option<PathBuf>,
+    },
+    /// Manages settings that must not be echoed, such as tokens
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SecretCommand {
+    /// Prompts for a secret without echoing it and stores it in the config file
+    Set {
+        /// Name of the secret setting, e.g. auth.token
+        key: String,
+    },
+}
+
+/// The syntaxes configuration can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Json,
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            Format::Toml => "TOML",
+            #[cfg(feature = "yaml")]
+            Format::Yaml => "YAML",
+        }
+    }
+
+    /// Returns the format files named like `path` are written in, if known.
+    fn guess(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Parses `text` written in this format.
+    fn parse(self, text: &str) -> std::result::Result<Value, String> {
+        match self {
+            Format::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+            Format::Toml => toml::from_str(text).map_err(|e| e.to_string()),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => serde_yaml::from_str(text).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Writes `value` in this format, ending with a newline.
+    fn write(self, value: &Value) -> std::result::Result<String, String> {
+        match self {
+            Format::Json => Ok(serde_json::to_string_pretty(value)
+                .expect("JSON values always serialize")
+                + "\n"),
+            Format::Toml if has_null(value) => Err("TOML has no null values".to_string()),
+            // Going through toml's own values puts each table after the
+            // plain values next to it, as TOML requires.
+            Format::Toml => toml::Value::try_from(value)
+                .and_then(|value| toml::to_string(&value))
+                .map_err(|e| e.to_string()),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+fn has_null(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(values) => values.iter().any(has_null),
+        Value::Object(map) => map.values().any(has_null),
+        _ => false,
+    }
+}
+
+/// A problem `config validate` found in a config file.
+#[derive(Debug, PartialEq, Serialize)]
+struct Finding {
+    /// The setting at fault, if the problem is not with the file as a whole.
+    key: Option<String>,
+    message: String,
+}
+
+pub struct Config;
+
+impl super::Command for Config {
+    fn clap(&self) -> clap::Command {
+        ConfigArgs::augment_args(clap::Command::new("config"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new(
+                "FIXME config set verbosity debug",
+                "Logs debug messages on every run",
+            ),
+            Example::new(
+                "FIXME config set",
+                "Picks a setting from a searchable list, then asks for its value",
+            ),
+            Example::new(
+                "FIXME config get verbosity",
+                "Prints the verbosity in effect, whichever source provides it",
+            ),
+            Example::new(
+                "FIXME config secret set auth.token",
+                "Asks for the token without echoing it or recording it in shell history",
+            ),
+            Example::new(
+                "FIXME config sources",
+                "Shows which environment variables and files settings are read from",
+            ),
+            Example::new(
+                "FIXME config dump --to toml --sources",
+                "Prints the merged configuration, noting where each setting comes from",
+            ),
+            Example::new(
+                "FIXME config convert config.json config.toml",
+                "Rewrites a JSON config file as TOML",
+            ),
+            Example::new(
+                "FIXME config validate deploy/config.json --output json",
+                "Lists the problems in a config file as JSON, failing if there are any",
+            ),
+            Example::new(
+                "FIXME config wizard",
+                "Asks for each setting in turn and writes the config file",
+            ),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: ConfigArgs = ctx.args()?;
+        match &args.command {
+            ConfigCommand::Get { key } => {
+                let key = schema::lookup(key)?;
+                let value = current(ctx, key).ok_or_else(|| Error::MissingKey {
+                    key: key.name.to_string(),
+                })?;
+                Ok(Some(Output::new(&value)))
+            }
+            ConfigCommand::Set { key, value } => {
+                let key = match key {
+                    Some(key) => schema::lookup(key)?,
+                    None => pick_key(ctx)?,
+                };
+                if key.secret {
+                    return Err(Error::Subcommand(format!(
+                        "'{}' is a secret; use `config secret set {}` to keep it out of shell history",
+                        key.name, key.name
+                    )));
+                }
+                let value = match value {
+                    Some(value) => value.clone(),
+                    None => {
+                        let current = current(ctx, key);
+                        ctx.prompt()
+                            .input(key.name, current.as_deref(), |value| key.validate(value))?
+                    }
+                };
+                key.validate(&value).map_err(|reason| Error::InvalidValue {
+                    key: key.name.to_string(),
+                    reason,
+                })?;
+                let path = ctx.app().layout().config_path();
+                ctx.effect(
+                    format_args!("set {} to {} in {}", key.name, value, path.display()),
+                    || settings::set(&path, key.name, &value),
+                )?;
+                Ok(None)
+            }
+            ConfigCommand::Wizard => {
+                wizard(ctx)?;
+                Ok(None)
+            }
+            ConfigCommand::Sources => {
+                let sources = ctx.app().layout().sources();
+                let human = sources
+                    .iter()
+                    .enumerate()
+                    .map(|(i, source)| {
+                        format!(
+                            "{}. {} {}: {}",
+                            i + 1,
+                            source.kind,
+                            source.target,
+                            source.detail
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Ok(Some(Output::new(&sources).human(human)))
+            }
+            ConfigCommand::Dump { to, sources } => {
+                if *sources && *to == Format::Json {
+                    return Err(Error::Subcommand(
+                        "JSON has no comments to note sources in; use --to toml or yaml"
+                            .to_string(),
+                    ));
+                }
+                let settings = ctx.app().layout().resolve(Some(ctx.matches()));
+                let text = dump(&settings, *to, *sources)?;
+                write!(ctx.out(), "{}", text).map_err(Error::Output)?;
+                Ok(None)
+            }
+            ConfigCommand::Convert {
+                from,
+                to,
+                input,
+                destination,
+            } => {
+                let stdout = destination == Path::new("-");
+                let from = from
+                    .or_else(|| match input {
+                        InputSource::File(path) => Format::guess(path),
+                        InputSource::Stdin => None,
+                    })
+                    .ok_or_else(|| {
+                        Error::Subcommand(format!(
+                            "cannot tell the format of {}; pass --from",
+                            input
+                        ))
+                    })?;
+                let to = to
+                    .or_else(|| Format::guess(destination).filter(|_| !stdout))
+                    .ok_or_else(|| {
+                        Error::Subcommand(format!(
+                            "cannot tell the format of {}; pass --to",
+                            destination.display()
+                        ))
+                    })?;
+                let value = from.parse(&ctx.read_to_string(input)?).map_err(|reason| {
+                    Error::Subcommand(format!(
+                        "cannot read {} as {}: {}",
+                        input,
+                        from.name(),
+                        reason
+                    ))
+                })?;
+                let text = to.write(&value).map_err(|reason| {
+                    Error::Subcommand(format!(
+                        "cannot write {} as {}: {}",
+                        destination.display(),
+                        to.name(),
+                        reason
+                    ))
+                })?;
+                if stdout {
+                    write!(ctx.out(), "{}", text).map_err(Error::Output)?;
+                    return Ok(None);
+                }
+                ctx.effect(
+                    format_args!("write {} as {}", destination.display(), to.name()),
+                    || std::fs::write(destination, &text).map_err(Error::io(destination)),
+                )?;
+                Ok(None)
+            }
+            ConfigCommand::Validate { path } => {
+                let path = path
+                    .clone()
+                    .unwrap_or_else(|| ctx.app().layout().config_path());
+                let text = std::fs::read_to_string(&path).map_err(Error::io(&path))?;
+                let findings = check(Format::guess(&path).unwrap_or(Format::Json), &text);
+                for finding in &findings {
+                    let human = match &finding.key {
+                        Some(key) => format!("{}: {}: {}", path.display(), key, finding.message),
+                        None => format!("{}: {}", path.display(), finding.message),
+                    };
+                    ctx.item(Output::new(finding).human(human))?;
+                }
+                if findings.is_empty() {
+                    let human = format!("{}: no problems found", path.display());
+                    ctx.summary(Output::new(&findings).human(human))?;
+                }
+                match findings.len() {
+                    0 => Ok(None),
+                    problems => Err(Error::Subcommand(format!(
+                        "{} problem(s) in {}",
+                        problems,
+                        path.display()
+                    ))),
+                }
+            }
+            ConfigCommand::Secret {
+                command: SecretCommand::Set { key },
+            } => {
+                let key = schema::lookup(key)?;
+                if !key.secret {
+                    return Err(Error::Subcommand(format!(
+                        "'{}' is not a secret; use `config set {} <VALUE>`",
+                        key.name, key.name
+                    )));
+                }
+                let value = ctx.prompt().password(key.name)?;
+                key.validate(&value).map_err(|reason| Error::InvalidValue {
+                    key: key.name.to_string(),
+                    reason,
+                })?;
+                let path = ctx.app().layout().config_path();
+                ctx.effect(
+                    format_args!("store {} in {}", key.name, path.display()),
+                    || settings::set(&path, key.name, &value),
+                )?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Returns the value of `key` in effect, or its default.
+fn current(ctx: &Context, key: &schema::Key) -> Option<String> {
+    ctx.settings()
+        .handle_request(key.name)
+        .or_else(|| key.default.map(str::to_string))
+}
+
+/// Parses `text` as a config file in `format` and checks each of its
+/// settings against the [`schema`].
+fn check(format: Format, text: &str) -> Vec<Finding> {
+    let whole = |message: String| vec![Finding { key: None, message }];
+    let root = match format.parse(text) {
+        Ok(Value::Object(root)) => root,
+        Ok(_) => return whole("the file does not hold a table of settings".to_string()),
+        Err(reason) => return whole(format!("not valid {}: {}", format.name(), reason)),
+    };
+    let mut leaves = Vec::new();
+    collect_leaves("", &root, &mut leaves);
+    let mut findings = leaves
+        .iter()
+        .filter_map(|(name, value)| {
+            let problem = |message: String| {
+                Some(Finding {
+                    key: Some(name.clone()),
+                    message,
+                })
+            };
+            let key = match schema::lookup(name) {
+                Ok(key) => key,
+                Err(e) => return problem(e.to_string()),
+            };
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Null => return None,
+                Value::Array(_) | Value::Object(_) => {
+                    return problem("must be a single value, not a list".to_string())
+                }
+                other => other.to_string(),
+            };
+            key.validate(&value).err().and_then(problem)
+        })
+        .collect::<Vec<_>>();
+    let set: Vec<&str> = leaves.iter().map(|(name, _)| name.as_str()).collect();
+    if let Err(Error::InvalidValue { key, reason }) =
+        schema::check_relations(schema::RELATIONS, |key| set.contains(&key))
+    {
+        findings.push(Finding {
+            key: Some(key),
+            message: reason,
+        });
+    }
+    findings
+}
+
+/// Adds the dotted name and value of every non-table value under `table`.
+fn collect_leaves<'a>(
+    path: &str,
+    table: &'a Map<String, Value>,
+    leaves: &mut Vec<(String, &'a Value)>,
+) {
+    for (name, value) in table {
+        match value {
+            Value::Object(child) => collect_leaves(&join(path, name), child, leaves),
+            value => leaves.push((join(path, name), value)),
+        }
+    }
+}
+
+/// Renders `settings` as a configuration file in `format`, nested by their
+/// dotted names, with secrets masked and, given `sources`, a comment after
+/// each noting where it comes from.
+fn dump(settings: &[Resolved], format: Format, sources: bool) -> Result<String> {
+    let mut root = Map::new();
+    let mut comments = BTreeMap::new();
+    for setting in settings {
+        let key = schema::lookup(&setting.key)?;
+        let value = match (key.secret, key.kind, setting.value.as_str()) {
+            (true, _, _) => Value::from(settings::MASK),
+            (false, ValueKind::Bool, "true") => Value::Bool(true),
+            (false, ValueKind::Bool, "false") => Value::Bool(false),
+            (false, _, value) => Value::from(value),
+        };
+        let (parents, name) = match setting.key.rsplit_once('.') {
+            Some((parents, name)) => (parents.split('.').collect(), name),
+            None => (Vec::new(), setting.key.as_str()),
+        };
+        let table = parents.into_iter().fold(&mut root, |table, parent| {
+            match table
+                .entry(parent)
+                .or_insert_with(|| Value::Object(Map::new()))
+            {
+                Value::Object(child) => child,
+                _ => unreachable!("settings are never both a value and a table"),
+            }
+        });
+        table.insert(name.to_string(), value);
+        comments.insert(setting.key.as_str(), setting.source.as_str());
+    }
+    let comments = sources.then_some(&comments);
+    let mut out = String::new();
+    match format {
+        Format::Json => {
+            out = Format::Json
+                .write(&Value::Object(root))
+                .map_err(Error::Subcommand)?
+        }
+        Format::Toml => write_toml(&mut out, "", &root, comments),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => write_yaml(&mut out, "", &root, comments, 0),
+    }
+    Ok(out)
+}
+
+/// Appends the comment for the setting `key`, if any, and ends the line.
+fn end_line(out: &mut String, key: &str, comments: Option<&BTreeMap<&str, &str>>) {
+    if let Some(comment) = comments.and_then(|comments| comments.get(key)) {
+        out.push_str("  # ");
+        out.push_str(comment);
+    }
+    out.push('\n');
+}
+
+fn join(path: &str, name: &str) -> String {
+    match path {
+        "" => name.to_string(),
+        path => format!("{}.{}", path, name),
+    }
+}
+
+/// Writes the values of `table` at `path`, then each of its tables under a
+/// `[header]` of its own.
+fn write_toml(
+    out: &mut String,
+    path: &str,
+    table: &Map<String, Value>,
+    comments: Option<&BTreeMap<&str, &str>>,
+) {
+    let key = |name: &str| match name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        true => name.to_string(),
+        false => toml::Value::from(name).to_string(),
+    };
+    for (name, value) in table.iter().filter(|(_, value)| !value.is_object()) {
+        let scalar = toml::Value::try_from(value).expect("settings are TOML scalars");
+        out.push_str(&format!("{} = {}", key(name), scalar));
+        end_line(out, &join(path, name), comments);
+    }
+    for (name, value) in table {
+        if let Value::Object(child) = value {
+            let header = match path {
+                "" => key(name),
+                path => format!("{}.{}", path, key(name)),
+            };
+            if child.values().any(|value| !value.is_object()) {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                out.push_str(&format!("[{}]\n", header));
+            }
+            write_toml(out, &header, child, comments);
+        }
+    }
+}
+
+/// Writes `table` as a YAML mapping indented by `depth` levels.
+#[cfg(feature = "yaml")]
+fn write_yaml(
+    out: &mut String,
+    path: &str,
+    table: &Map<String, Value>,
+    comments: Option<&BTreeMap<&str, &str>>,
+    depth: usize,
+) {
+    let scalar = |value: &Value| {
+        serde_yaml::to_string(value)
+            .expect("JSON values always serialize")
+            .trim_end()
+            .to_string()
+    };
+    for (name, value) in table {
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(&scalar(&Value::from(name.as_str())));
+        out.push(':');
+        match value {
+            Value::Object(child) => {
+                out.push('\n');
+                write_yaml(out, &join(path, name), child, comments, depth + 1);
+            }
+            value => {
+                out.push(' ');
+                out.push_str(&scalar(value));
+                end_line(out, &join(path, name), comments);
+            }
+        }
+    }
+}
+
+/// Lets the user pick a setting from a list, searchable by typing part of
+/// its name or description, showing the value of each. Secrets are left out,
+/// as `config secret set` stores those.
+fn pick_key(ctx: &Context) -> Result<&'static schema::Key> {
+    let keys: Vec<&'static schema::Key> = schema::KEYS
+        .iter()
+        .filter(|key| !key.is_pattern() && !key.secret)
+        .collect();
+    let width = keys.iter().map(|key| key.name.len()).max().unwrap_or(0);
+    let items: Vec<String> = keys
+        .iter()
+        .map(|key| {
+            let value = current(ctx, key).unwrap_or_else(|| "(not set)".to_string());
+            format!(
+                "{:width$}  {}  [{}]",
+                key.name,
+                key.description,
+                value,
+                width = width
+            )
+        })
+        .collect();
+    let index = ctx.prompt().fuzzy_select("Setting", &items, None)?;
+    Ok(keys[index])
+}
+
+/// Asks for every key of the [`schema`], offering the current or default value,
+/// and writes the answers that changed to the user's config file.
+///
+/// Keys sharing a dotted prefix, such as `theme.*`, are only asked for after
+/// the user agrees to configure that group.
+fn wizard(ctx: &Context) -> Result<()> {
+    let path = ctx.app().layout().config_path();
+    let current = JsonFileHandler::new(&path);
+    let prompt = ctx.prompt();
+    let mut groups: BTreeMap<&str, bool> = BTreeMap::new();
+    let mut changed = 0;
+
+    for key in schema::KEYS.iter().filter(|k| !k.is_pattern()) {
+        if let Some((group, _)) = key.name.split_once('.') {
+            let wanted = match groups.get(group) {
+                Some(wanted) => *wanted,
+                None => {
+                    let wanted =
+                        prompt.confirm(&format!("Configure {} settings?", group), false)?;
+                    groups.insert(group, wanted);
+                    wanted
+                }
+            };
+            if !wanted {
+                continue;
+            }
+        }
+
+        let existing = current.handle_request(key.name);
+        let default = existing.as_deref().or(key.default);
+        if prompt.mode() == PromptMode::AssumeYes && (key.secret || default.is_none()) {
+            continue;
+        }
+        let question = format!("{} ({})", key.name, key.description);
+        let value = if key.secret {
+            prompt.password(&question)?
+        } else {
+            prompt.input(&question, default, |value| match value.as_str() {
+                "" => Ok(()),
+                value => key.validate(value),
+            })?
+        };
+        if value.is_empty() || Some(&value) == existing.as_ref() {
+            continue;
+        }
+        if key.secret {
+            key.validate(&value).map_err(|reason| Error::InvalidValue {
+                key: key.name.to_string(),
+                reason,
+            })?;
+        }
+        let description = if key.secret {
+            format!("set {}", key.name)
+        } else {
+            format!("set {} to {}", key.name, value)
+        };
+        ctx.effect(description, || settings::set(&path, key.name, &value))?;
+        changed += 1;
+    }
+
+    if !ctx.is_dry_run() {
+        ctx.progress(format_args!(
+            "Saved {} setting(s) to {}",
+            changed,
+            path.display()
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump_nests_masks_and_notes_sources() {
+        let setting = |key: &str, value: &str, source: &str| Resolved {
+            key: key.to_string(),
+            value: value.to_string(),
+            source: source.to_string(),
+        };
+        let settings = [
+            setting("auth.token", "hunter2", "file config.json"),
+            setting("update.check", "false", "environment FIXME_update_check"),
+            setting("verbosity", "info", "default"),
+        ];
+
+        assert_eq!(
+            "verbosity = \"info\"  # default\n\
+             \n\
+             [auth]\n\
+             token = \"********\"  # file config.json\n\
+             \n\
+             [update]\n\
+             check = false  # environment FIXME_update_check\n",
+            dump(&settings, Format::Toml, true).unwrap()
+        );
+        let json: Value =
+            serde_json::from_str(&dump(&settings, Format::Json, false).unwrap()).unwrap();
+        assert_eq!(Value::Bool(false), json["update"]["check"]);
+    }
+
+    #[test]
+    fn test_convert_keeps_nesting_and_types() {
+        let json = r#"{"hooks": {"pre": {"build": "make"}}, "retries": 3, "ratio": 0.5, "on": true, "tags": ["a", "b"]}"#;
+        let value = Format::Json.parse(json).unwrap();
+
+        let toml = Format::Toml.write(&value).unwrap();
+        assert!(toml.contains("[hooks.pre]"), "{}", toml);
+        assert_eq!(value, Format::Toml.parse(&toml).unwrap());
+        #[cfg(feature = "yaml")]
+        assert_eq!(
+            value,
+            Format::Yaml
+                .parse(&Format::Yaml.write(&value).unwrap())
+                .unwrap()
+        );
+        assert!(Format::Toml.write(&serde_json::json!({"a": null})).is_err());
+        assert_eq!(Some(Format::Toml), Format::guess(Path::new("config.toml")));
+    }
+
+    #[test]
+    fn test_check_reports_each_problem() {
+        let findings = check(
+            Format::Json,
+            r#"{"verbosity": "loud", "update": {"chek": true}, "ascii": true}"#,
+        );
+
+        assert_eq!(
+            vec![Some("update.chek"), Some("verbosity")],
+            findings
+                .iter()
+                .map(|finding| finding.key.as_deref())
+                .collect::<Vec<_>>()
+        );
+        assert!(findings[1].message.starts_with("must be one of"));
+        assert_eq!(None, check(Format::Json, "{").pop().unwrap().key);
+        assert!(check(Format::Toml, "ascii = false").is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+use clap::{Args, Subcommand};
+use serde::Serialize;
+
+use crate::examples::Example;
+use crate::ipc::{self, Request};
+use crate::{Context, Output, Result};
+
+/// Controls the running daemon through its control socket
+#[derive(Debug, Args)]
+struct CtlArgs {
+    #[command(subcommand)]
+    command: CtlCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum CtlCommand {
+    /// Shows the daemon's pid and uptime
+    Status,
+    /// Makes the daemon re-read its settings
+    ReloadConfig,
+    /// Changes the daemon's logging verbosity until it restarts
+    SetLogLevel {
+        #[arg(value_parser = ["off", "error", "warn", "info", "debug", "trace"])]
+        level: String,
+    },
+    /// Asks the daemon to stop
+    Shutdown,
+}
+
+/// What `ctl` reports.
+#[derive(Debug, Serialize)]
+struct Answer {
+    request: String,
+    message: String,
+}
+
+pub struct Ctl;
+
+impl super::Command for Ctl {
+    fn clap(&self) -> clap::Command {
+        CtlArgs::augment_args(clap::Command::new("ctl"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new("FIXME ctl status", "Checks that the daemon answers"),
+            Example::new(
+                "FIXME ctl set-log-level debug",
+                "Turns on debug logging in the daemon without restarting it",
+            ),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: CtlArgs = ctx.args()?;
+        let request = match args.command {
+            CtlCommand::Status => Request::Status,
+            CtlCommand::ReloadConfig => Request::ReloadConfig,
+            CtlCommand::SetLogLevel { level } => {
+                Request::SetLogLevel(level.parse().expect("clap validates the level"))
+            }
+            CtlCommand::Shutdown => Request::Shutdown,
+        };
+        let path = ipc::socket_path();
+        let message = if request == Request::Status {
+            Some(ipc::request(&path, &request)?)
+        } else {
+            ctx.effect(format!("send '{}' to the daemon", request), || {
+                ipc::request(&path, &request)
+            })?
+        };
+        Ok(message.map(|message| {
+            let answer = Answer {
+                request: request.to_string(),
+                message: message.clone(),
+            };
+            Output::new(&answer).human(message)
+        }))
+    }
+}
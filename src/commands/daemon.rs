@@ -0,0 +1,342 @@
+use std::net::{AddrParseError, SocketAddr};
+use std::time::{Duration, SystemTime};
+
+use clap::{Args, Subcommand};
+use log::info;
+use serde::Serialize;
+
+use crate::examples::Example;
+use crate::ipc::{self, Request};
+use crate::schedule::Job;
+use crate::settings::JsonFileHandler;
+use crate::{daemon, http, reload, Context, Error, Output, Result};
+
+/// How often the foreground daemon checks whether it was asked to stop.
+const POLL: Duration = Duration::from_millis(100);
+
+/// Runs in the background until stopped
+#[derive(Debug, Args)]
+struct DaemonArgs {
+    #[command(subcommand)]
+    command: DaemonCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum DaemonCommand {
+    /// Starts the daemon, detached from the terminal
+    Start {
+        /// Stay attached to the terminal and log to stderr
+        #[arg(long)]
+        foreground: bool,
+    },
+    /// Stops the running daemon
+    Stop,
+    /// Shows whether the daemon is running
+    Status,
+}
+
+/// What `daemon status` reports.
+#[derive(Debug, Serialize)]
+struct Status {
+    running: bool,
+    pid: Option<u32>,
+    pidfile: String,
+}
+
+pub struct Daemon;
+
+impl super::Command for Daemon {
+    fn clap(&self) -> clap::Command {
+        DaemonArgs::augment_args(clap::Command::new("daemon"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new(
+                "FIXME daemon start",
+                "Starts the daemon in the background, logging to a file",
+            ),
+            Example::new(
+                "FIXME -v debug daemon start --foreground",
+                "Runs the daemon in the terminal until Ctrl-C, e.g. under a service manager",
+            ),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: DaemonArgs = ctx.args()?;
+        let pidfile = daemon::pidfile_path();
+        match args.command {
+            DaemonCommand::Start { foreground: true } => {
+                ctx.effect("run the daemon", || run_foreground(ctx))?;
+                Ok(None)
+            }
+            DaemonCommand::Start { foreground: false } => {
+                if let Some(pid) = daemon::running(&pidfile)? {
+                    return Err(Error::Subcommand(format!(
+                        "the daemon is already running (pid {})",
+                        pid
+                    )));
+                }
+                let log = daemon::log_path();
+                // Nothing would reach the log file without a level. A default
+                // one is passed in the environment; any other is read by the
+                // daemon itself, so reloading its settings can change it.
+                let mut env = Vec::new();
+                if ctx.settings().handle_request("verbosity").is_none() {
+                    env.push((
+                        format!("{}verbosity", ctx.app().layout().env_prefix),
+                        ctx.require("verbosity")?,
+                    ));
+                }
+                let args = ["daemon", "start", "--foreground"];
+                let started = ctx.effect("start the daemon", || {
+                    let mut child = daemon::spawn_detached(&args, &env, &log)?;
+                    daemon::wait_started(&mut child, daemon::START_TIMEOUT)
+                        .map_err(|e| Error::Subcommand(format!("{}; see {}", e, log.display())))?;
+                    Ok(child.id())
+                })?;
+                if let Some(pid) = started {
+                    ctx.progress(format_args!(
+                        "Started the daemon (pid {}); logging to {}",
+                        pid,
+                        log.display()
+                    ))?;
+                }
+                Ok(None)
+            }
+            DaemonCommand::Stop => {
+                let pid = daemon::running(&pidfile)?
+                    .ok_or_else(|| Error::Subcommand("the daemon is not running".to_string()))?;
+                let description = format!("stop the daemon (pid {})", pid);
+                if ctx
+                    .effect(description, || daemon::stop(pid, daemon::STOP_TIMEOUT))?
+                    .is_some()
+                {
+                    ctx.progress(format_args!("Stopped the daemon (pid {})", pid))?;
+                }
+                Ok(None)
+            }
+            DaemonCommand::Status => {
+                let pid = daemon::running(&pidfile)?;
+                let status = Status {
+                    running: pid.is_some(),
+                    pid,
+                    pidfile: pidfile.display().to_string(),
+                };
+                let human = match pid {
+                    Some(pid) => format!("The daemon is running (pid {})", pid),
+                    None => "The daemon is not running".to_string(),
+                };
+                Ok(Some(Output::new(&status).human(human)))
+            }
+        }
+    }
+}
+
+/// Records the process in the pidfile and works until cancelled, e.g. by
+/// `daemon stop`, Ctrl-C or the Windows service being stopped, or until
+/// asked to shut down through the control socket.
+///
+/// Under systemd it reports readiness and stopping, pings the watchdog and
+/// takes the control socket passed by socket activation.
+pub(crate) fn run_foreground(ctx: &Context) -> Result<()> {
+    let _pidfile = daemon::Pidfile::create(daemon::pidfile_path())?;
+    let mut control = ipc::Server::listen(&ipc::socket_path())?;
+    let health = match address(ctx, "daemon.http")? {
+        Some(address) => Some(http::Server::listen(address)?),
+        None => None,
+    };
+    #[cfg(feature = "grpc")]
+    let grpc = match address(ctx, "daemon.grpc")? {
+        Some(address) => {
+            let token = ctx.settings().handle_request("daemon.grpc_token");
+            // Anyone who can reach it could otherwise run subcommands here.
+            if token.is_none() && !address.ip().is_loopback() {
+                return Err(Error::InvalidValue {
+                    key: "daemon.grpc".to_string(),
+                    reason: "must be a loopback address unless daemon.grpc_token is set"
+                        .to_string(),
+                });
+            }
+            Some(crate::grpc::Server::start(address, token)?)
+        }
+        None => None,
+    };
+    #[cfg(not(feature = "grpc"))]
+    if ctx.settings().handle_request("daemon.grpc").is_some() {
+        log::warn!("ignoring daemon.grpc: built without the grpc feature");
+    }
+    let started = ctx.time().now();
+    let mut jobs = scheduled(ctx, started)?;
+    info!("daemon started (pid {})", std::process::id());
+    daemon::notify("READY=1")?;
+    let watchdog = daemon::watchdog_interval();
+    let mut pinged = started;
+    while !ctx.cancellation_token().is_cancelled() {
+        // FIXME: do the daemon's work here.
+        let handled = control.poll(|request| answer(ctx, started, request))?;
+        if handled.contains(&Request::Shutdown) {
+            break;
+        }
+        if let Some(health) = &health {
+            health.poll(|| metrics(ctx, started))?;
+        }
+        #[cfg(feature = "grpc")]
+        for call in grpc.iter().flat_map(crate::grpc::Server::pending) {
+            answer_grpc(ctx, started, call);
+        }
+        let now = ctx.time().now();
+        for job in &mut jobs {
+            if let Err(e) = job.poll(now) {
+                log::warn!("{}", e);
+            }
+        }
+        ctx.time().sleep(POLL);
+        if let Some(interval) = watchdog {
+            let now = ctx.time().now();
+            if now.duration_since(pinged).unwrap_or_default() >= interval {
+                daemon::notify("WATCHDOG=1")?;
+                pinged = now;
+            }
+        }
+    }
+    daemon::notify("STOPPING=1")?;
+    jobs.iter_mut().for_each(Job::finish);
+    info!("daemon stopped");
+    Ok(())
+}
+
+/// Loads the jobs of the `schedule.<name>` settings, first due after `now`.
+fn scheduled(ctx: &Context, now: SystemTime) -> Result<Vec<Job>> {
+    let root = JsonFileHandler::new(ctx.app().layout().config_path()).load()?;
+    let specs = root
+        .as_ref()
+        .and_then(|root| root.get("schedule"))
+        .and_then(|specs| specs.as_object())
+        .cloned()
+        .unwrap_or_default();
+    let mut jobs = Vec::new();
+    for (name, spec) in specs {
+        let spec = match spec {
+            serde_json::Value::String(spec) => spec,
+            other => other.to_string(),
+        };
+        let job = Job::parse(&name, &spec, now)?;
+        info!("scheduled {}: {}", name, spec);
+        jobs.push(job);
+    }
+    Ok(jobs)
+}
+
+/// Carries out a `request` from the control socket, returning the message
+/// for the client.
+fn answer(ctx: &Context, started: SystemTime, request: &Request) -> Result<String> {
+    info!("control request: {}", request);
+    match request {
+        Request::Status => Ok(format!(
+            "running (pid {}, up {})",
+            std::process::id(),
+            humantime::format_duration(uptime(ctx, started))
+        )),
+        Request::ReloadConfig => {
+            reload::trigger()?;
+            Ok("reloading the settings".to_string())
+        }
+        Request::SetLogLevel(level) => {
+            log::set_max_level(*level);
+            Ok(format!(
+                "log level set to {}",
+                level.as_str().to_lowercase()
+            ))
+        }
+        Request::Shutdown => Ok("shutting down".to_string()),
+    }
+}
+
+/// Carries out a `call` from the gRPC interface, sending its answer back.
+#[cfg(feature = "grpc")]
+fn answer_grpc(ctx: &Context, started: SystemTime, call: crate::grpc::Call) {
+    use crate::grpc::{proto, Call};
+
+    info!("gRPC call: {:?}", call);
+    let failed = |e: Error| tonic::Status::internal(e.to_string());
+    match call {
+        Call::Status(reply) => {
+            let _ = reply.send(Ok(proto::StatusReply {
+                pid: std::process::id(),
+                uptime_seconds: uptime(ctx, started).as_secs(),
+                version: crate::identity::IDENTITY.version.to_string(),
+            }));
+        }
+        Call::Reload(reply) => {
+            let reloaded = reload::trigger().map(|()| proto::ReloadReply {
+                message: "reloading the settings".to_string(),
+            });
+            let _ = reply.send(reloaded.map_err(failed));
+        }
+        Call::RunCommand(args, reply) => {
+            if let Err(refusal) = crate::rpc::check_command_line(ctx.app().command(), &args) {
+                let _ = reply.send(Err(tonic::Status::invalid_argument(refusal.to_string())));
+                return;
+            }
+            let args = [ctx.app().command().get_name(), "--output", "json"]
+                .into_iter()
+                .map(str::to_string)
+                .chain(args);
+            let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+            let result = ctx.app().dispatch(args, &mut stdout, &mut stderr, false);
+            let _ = reply.send(Ok(proto::RunCommandReply {
+                success: result.is_ok(),
+                stdout: String::from_utf8_lossy(&stdout).into_owned(),
+                stderr: String::from_utf8_lossy(&stderr).into_owned(),
+                error_code: result
+                    .as_ref()
+                    .err()
+                    .map(|e| e.code().to_string())
+                    .unwrap_or_default(),
+                error: result.err().map(|e| e.to_string()).unwrap_or_default(),
+            }));
+        }
+    }
+}
+
+/// Renders the daemon's metrics, followed by those of the
+/// [registry](crate::metrics), in Prometheus' text format.
+fn metrics(ctx: &Context, started: SystemTime) -> String {
+    let identity = crate::identity::IDENTITY;
+    let mut metrics = format!(
+        "# HELP {prefix}_uptime_seconds How long the daemon has been running.\n\
+         # TYPE {prefix}_uptime_seconds gauge\n\
+         {prefix}_uptime_seconds {}\n\
+         # HELP {prefix}_build_info The running version.\n\
+         # TYPE {prefix}_build_info gauge\n\
+         {prefix}_build_info{{version=\"{}\"}} 1\n",
+        uptime(ctx, started).as_secs(),
+        identity.version,
+        prefix = identity.ident,
+    );
+    metrics.push_str(&crate::metrics::render());
+    metrics
+}
+
+/// Reads the socket address in the setting `key`, if set.
+fn address(ctx: &Context, key: &str) -> Result<Option<SocketAddr>> {
+    ctx.settings()
+        .handle_request(key)
+        .map(|address| {
+            address
+                .parse()
+                .map_err(|e: AddrParseError| Error::InvalidValue {
+                    key: key.to_string(),
+                    reason: e.to_string(),
+                })
+        })
+        .transpose()
+}
+
+/// How long the daemon has been running, to the second.
+fn uptime(ctx: &Context, started: SystemTime) -> Duration {
+    let uptime = ctx.time().now().duration_since(started).unwrap_or_default();
+    Duration::from_secs(uptime.as_secs())
+}
@@ -0,0 +1,313 @@
+use std::io::IsTerminal;
+
+use cor_args::Handler;
+use serde::Serialize;
+
+use crate::examples::Example;
+use crate::settings::JsonFileHandler;
+use crate::{flags, i18n, plugin, render, schema, update, Context, Error, Output, Result};
+
+/// How a [`Check`] turned out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// The outcome of one diagnosis, with a hint when something needs fixing.
+#[derive(Debug, Serialize)]
+struct Check {
+    check: &'static str,
+    status: Status,
+    detail: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass<D: Into<String>>(check: &'static str, detail: D) -> Self {
+        Check {
+            check,
+            status: Status::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn problem<D: Into<String>, H: Into<String>>(
+        check: &'static str,
+        status: Status,
+        detail: D,
+        hint: H,
+    ) -> Self {
+        Check {
+            check,
+            status,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+pub struct Doctor;
+
+impl super::Command for Doctor {
+    fn clap(&self) -> clap::Command {
+        clap::Command::new("doctor")
+            .about("Checks the configuration, plugins and terminal for problems")
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example::new(
+            "FIXME doctor --output json",
+            "Diagnoses the environment, for attaching to a bug report",
+        )]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let mut checks = vec![check_config_file(ctx), check_config_values(ctx)];
+        checks.extend(check_config_permissions(ctx));
+        checks.push(check_remote(ctx));
+        checks.extend(check_plugins(ctx));
+        checks.push(check_terminal());
+        checks.push(Check::pass("locale", i18n::catalog().locale()));
+
+        let human = checks
+            .iter()
+            .map(|c| {
+                let status = match c.status {
+                    Status::Pass => "pass",
+                    Status::Warn => "warn",
+                    Status::Fail => "FAIL",
+                };
+                match &c.hint {
+                    Some(hint) => {
+                        format!("[{}] {}: {}\n       {}", status, c.check, c.detail, hint)
+                    }
+                    None => format!("[{}] {}: {}", status, c.check, c.detail),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+        ctx.render(&Output::new(&checks).human(human))?;
+        match failed {
+            0 => Ok(None),
+            failed => Err(Error::Subcommand(format!("{} check(s) failed", failed))),
+        }
+    }
+}
+
+fn check_config_file(ctx: &Context) -> Check {
+    let path = ctx.app().layout().config_path();
+    if !path.exists() {
+        return Check::pass(
+            "config file",
+            format!("{} does not exist; defaults apply", path.display()),
+        );
+    }
+    match ctx.app().layout().validate() {
+        Ok(()) => Check::pass("config file", format!("{} is valid", path.display())),
+        Err(e) => Check::problem(
+            "config file",
+            Status::Fail,
+            e.to_string(),
+            format!(
+                "fix the file, or run `{} explain {}`",
+                ctx.app().command().get_name(),
+                e.code()
+            ),
+        ),
+    }
+}
+
+fn check_config_values(ctx: &Context) -> Check {
+    let invalid: Vec<String> = schema::KEYS
+        .iter()
+        .filter(|key| !key.is_pattern())
+        .filter_map(|key| {
+            let value = ctx.settings().handle_request(key.name)?;
+            let reason = key.validate(&value).err()?;
+            Some(format!("{} {}", key.name, reason))
+        })
+        .collect();
+    match invalid.is_empty() {
+        true => Check::pass("config values", "every setting has a valid value"),
+        false => Check::problem(
+            "config values",
+            Status::Fail,
+            invalid.join("; "),
+            format!(
+                "correct them with `{} config set <KEY> <VALUE>`",
+                ctx.app().command().get_name()
+            ),
+        ),
+    }
+}
+
+/// Warns when the config file holds a secret but others may read it.
+#[cfg(unix)]
+fn check_config_permissions(ctx: &Context) -> Option<Check> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = ctx.app().layout().config_path();
+    let mode = path.metadata().ok()?.permissions().mode();
+    let file = JsonFileHandler::new(&path);
+    let has_secret = schema::KEYS
+        .iter()
+        .any(|key| key.secret && file.handle_request(key.name).is_some());
+    Some(match has_secret && mode & 0o077 != 0 {
+        true => Check::problem(
+            "config permissions",
+            Status::Warn,
+            format!(
+                "{} holds a secret but has mode {:o}",
+                path.display(),
+                mode & 0o777
+            ),
+            format!("run `chmod 600 {}`", path.display()),
+        ),
+        false => Check::pass("config permissions", format!("mode {:o}", mode & 0o777)),
+    })
+}
+
+#[cfg(not(unix))]
+fn check_config_permissions(_ctx: &Context) -> Option<Check> {
+    None
+}
+
+/// Checks that the release server consulted by the update check can be reached.
+fn check_remote(ctx: &Context) -> Check {
+    if !flags::resolve(ctx.matches(), ctx.settings(), "update.check") {
+        return Check::pass("release server", "update checks are disabled");
+    }
+    match reach(update::RELEASES_URL) {
+        Ok(()) => Check::pass(
+            "release server",
+            format!("{} is reachable", update::RELEASES_URL),
+        ),
+        Err(e) => Check::problem(
+            "release server",
+            Status::Warn,
+            e,
+            format!(
+                "check the network or proxy, or disable with `{} config set update.check false`",
+                ctx.app().command().get_name()
+            ),
+        ),
+    }
+}
+
+/// Sends a request to `url`, succeeding on any response at all.
+#[cfg(feature = "network")]
+fn reach(url: &str) -> std::result::Result<(), String> {
+    let client = crate::net::client().map_err(|e| e.to_string())?;
+    match client.head(url).timeout(update::TIMEOUT).call() {
+        Ok(_) | Err(ureq::Error::Status(..)) => Ok(()),
+        Err(e) => {
+            crate::metrics::increment(
+                &crate::metrics::REMOTE_FETCH_FAILURES,
+                &[("target", "doctor")],
+            );
+            Err(e.to_string())
+        }
+    }
+}
+
+#[cfg(not(feature = "network"))]
+fn reach(_url: &str) -> std::result::Result<(), String> {
+    Err("built without the network feature".to_string())
+}
+
+/// Checks installed plugins against their recorded checksums, and flags
+/// external subcommands that built-in ones hide.
+fn check_plugins(ctx: &Context) -> Vec<Check> {
+    let dir = plugin::plugins_dir();
+    let installed = match plugin::manifest(&dir) {
+        Ok(installed) => installed,
+        Err(e) => {
+            return vec![Check::problem(
+                "plugins",
+                Status::Fail,
+                e.to_string(),
+                format!("remove or fix {}", dir.join(plugin::MANIFEST).display()),
+            )]
+        }
+    };
+
+    let mut checks = Vec::new();
+    for (name, recorded) in &installed {
+        let path = plugin::executable(&dir, name);
+        let check = match std::fs::read(&path) {
+            Ok(content) if plugin::sha256(&content) == recorded.sha256 => {
+                Check::pass("plugin", format!("{} is intact", name))
+            }
+            Ok(_) => Check::problem(
+                "plugin",
+                Status::Fail,
+                format!("{} was modified since it was installed", path.display()),
+                format!(
+                    "reinstall it with `{} plugin install {}`",
+                    ctx.app().command().get_name(),
+                    recorded.source.display()
+                ),
+            ),
+            Err(e) => Check::problem(
+                "plugin",
+                Status::Fail,
+                format!("{} cannot be read: {}", path.display(), e),
+                format!(
+                    "run `{} plugin remove {}` and install it again",
+                    ctx.app().command().get_name(),
+                    name
+                ),
+            ),
+        };
+        checks.push(check);
+    }
+
+    let search_path = std::env::var_os("PATH");
+    for (name, path) in plugin::discover(search_path.as_deref()) {
+        if ctx.app().command().find_subcommand(&name).is_some() {
+            checks.push(Check::problem(
+                "plugin",
+                Status::Warn,
+                format!(
+                    "{} is hidden by the built-in subcommand '{}'",
+                    path.display(),
+                    name
+                ),
+                "rename the executable to use it",
+            ));
+        }
+    }
+    if checks.is_empty() {
+        checks.push(Check::pass("plugins", "none installed"));
+    }
+    checks
+}
+
+fn check_terminal() -> Check {
+    let describe = |terminal: bool| if terminal { "a terminal" } else { "redirected" };
+    let mut detail = format!(
+        "stdout is {}, stderr is {}",
+        describe(std::io::stdout().is_terminal()),
+        describe(std::io::stderr().is_terminal())
+    );
+    if let Some(width) = render::terminal_width() {
+        detail.push_str(&format!(", {} columns", width));
+    }
+    if std::env::var_os("NO_COLOR").is_some() {
+        detail.push_str(", colors disabled by NO_COLOR");
+    }
+    match std::env::var("TERM").as_deref() {
+        Ok("dumb") => Check::problem(
+            "terminal",
+            Status::Warn,
+            detail + ", TERM is dumb",
+            "spinners and colors are unavailable; pass --ascii for plain output",
+        ),
+        _ => Check::pass("terminal", detail),
+    }
+}
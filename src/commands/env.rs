@@ -0,0 +1,97 @@
+use std::io::Write;
+
+use clap::{Args, ValueEnum};
+
+use crate::examples::Example;
+use crate::settings::{self, shell_variable};
+use crate::{schema, Context, Error, Output, Result};
+
+/// Prints the settings in effect as environment variables for a shell to
+/// source; secrets are left out
+#[derive(Debug, Args)]
+struct EnvArgs {
+    /// Syntax of the printed lines
+    #[arg(long, value_enum, default_value_t = Syntax::Bash)]
+    shell: Syntax,
+}
+
+/// The shell whose syntax `env` prints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Syntax {
+    /// `export NAME=value`, for bash, zsh and other POSIX shells
+    Bash,
+    /// `set -gx NAME value`
+    Fish,
+    /// `$env:NAME = 'value'`
+    Powershell,
+}
+
+impl Syntax {
+    /// Returns the line setting `name` to `value`.
+    fn export(self, name: &str, value: &str) -> String {
+        match self {
+            Syntax::Bash => format!("export {}={}", name, shell_words::quote(value)),
+            Syntax::Fish => format!(
+                "set -gx {} '{}'",
+                name,
+                value.replace('\\', "\\\\").replace('\'', "\\'")
+            ),
+            Syntax::Powershell => format!("$env:{} = '{}'", name, value.replace('\'', "''")),
+        }
+    }
+}
+
+pub struct Env;
+
+impl super::Command for Env {
+    fn clap(&self) -> clap::Command {
+        EnvArgs::augment_args(clap::Command::new("env"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new(
+                "eval \"$(FIXME env)\"",
+                "Exports the resolved settings into a bash script's environment",
+            ),
+            Example::new("FIXME env --shell fish | source", "Does the same in fish"),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: EnvArgs = ctx.args()?;
+        let prefix = &ctx.app().layout().env_prefix;
+        let mut out = ctx.out();
+        for (key, value) in settings::snapshot(ctx.settings()) {
+            if schema::lookup(key).is_ok_and(|key| key.secret) {
+                continue;
+            }
+            let line = args.shell.export(&shell_variable(prefix, key), &value);
+            writeln!(out, "{}", line).map_err(Error::Output)?;
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_quotes_for_each_shell() {
+        let value = "it's \\ here";
+
+        assert_eq!(
+            "export FIXME_a='it'\\''s \\ here'",
+            Syntax::Bash.export("FIXME_a", value)
+        );
+        assert_eq!(
+            "set -gx FIXME_a 'it\\'s \\\\ here'",
+            Syntax::Fish.export("FIXME_a", value)
+        );
+        assert_eq!(
+            "$env:FIXME_a = 'it''s \\ here'",
+            Syntax::Powershell.export("FIXME_a", value)
+        );
+    }
+}
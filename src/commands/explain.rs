@@ -0,0 +1,42 @@
+use clap::Args;
+
+use crate::error::explain;
+use crate::examples::Example;
+use crate::{Context, Error, Output, Result};
+
+/// Explains an error code in detail
+#[derive(Debug, Args)]
+struct ExplainArgs {
+    /// The error code, e.g. E005
+    code: String,
+}
+
+pub struct Explain;
+
+impl super::Command for Explain {
+    fn clap(&self) -> clap::Command {
+        ExplainArgs::augment_args(clap::Command::new("explain"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example::new(
+            "FIXME explain E005",
+            "Describes the file access error and how to resolve it",
+        )]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: ExplainArgs = ctx.args()?;
+        let explanation = explain(&args.code)
+            .ok_or_else(|| Error::Subcommand(format!("unknown error code '{}'", args.code)))?;
+        let value = serde_json::json!({
+            "code": explanation.code,
+            "summary": explanation.summary,
+            "description": explanation.description,
+        });
+        Ok(Some(Output::new(&value).human(format!(
+            "{}: {}\n\n{}",
+            explanation.code, explanation.summary, explanation.description
+        ))))
+    }
+}
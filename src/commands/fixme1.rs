@@ -0,0 +1,71 @@
+use clap::Args;
+use cor_args::{DefaultHandler, FileHandler, Handler};
+use serde::Serialize;
+
+use crate::examples::Example;
+use crate::{paths, Context, InputSource, Output, Result};
+
+/// Executes the fixme1 function
+#[derive(Debug, Args)]
+struct Fixme1Args {
+    /// Input for the fixme1 function, or - to read it from stdin
+    input: Option<InputSource>,
+}
+
+#[derive(Debug, Serialize)]
+struct Fixme1Result {
+    input: Option<String>,
+    verbosity: Option<String>,
+    passthrough: Vec<String>,
+}
+
+pub struct Fixme1;
+
+impl super::Command for Fixme1 {
+    fn clap(&self) -> clap::Command {
+        Fixme1Args::augment_args(clap::Command::new("fixme1"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new("FIXME fixme1 foo", "Runs fixme1 with foo as input"),
+            Example::new(
+                "FIXME fixme1 foo -- --bar baz",
+                "Runs fixme1, passing --bar baz through untouched",
+            ),
+        ]
+    }
+
+    fn passthrough(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: Fixme1Args = ctx.args()?;
+        // Anything but - is the input itself rather than a file to read.
+        let input = match args.input {
+            Some(InputSource::Stdin) => Some(
+                ctx.read_to_string(&InputSource::Stdin)?
+                    .trim_end()
+                    .to_string(),
+            ),
+            input => input.map(|input| input.to_string()),
+        };
+        let verbosity = ctx.settings().handle_request("verbosity").or_else(|| {
+            FileHandler::new(paths::config_dir().join("verbosity"))
+                .next(DefaultHandler::new("info").into())
+                .handle_request("verbosity")
+        });
+        let result = Fixme1Result {
+            input,
+            verbosity,
+            passthrough: ctx
+                .passthrough()
+                .iter()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+        };
+        ctx.progress(format_args!("Running fixme1: {:?}", result))?;
+        Ok(Some(Output::new(&result)))
+    }
+}
@@ -0,0 +1,53 @@
+use clap::Args;
+use serde::Serialize;
+
+use crate::examples::Example;
+use crate::{Context, InputSource, Output, Result};
+
+/// Executes the fixme2 function
+#[derive(Debug, Args)]
+struct Fixme2Args {
+    /// Input for the fixme2 function, or - to read it from stdin
+    input: InputSource,
+}
+
+#[derive(Debug, Serialize)]
+struct Fixme2Result {
+    input: String,
+}
+
+pub struct Fixme2;
+
+impl super::Command for Fixme2 {
+    fn clap(&self) -> clap::Command {
+        Fixme2Args::augment_args(clap::Command::new("fixme2"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new(
+                "FIXME -v debug fixme2 foo",
+                "Runs fixme2 with foo as input, logging debug messages",
+            ),
+            Example::new(
+                "echo foo | FIXME fixme2 -",
+                "Runs fixme2 with input read from stdin",
+            ),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: Fixme2Args = ctx.args()?;
+        // Anything but - is the input itself rather than a file to read.
+        let input = match args.input {
+            InputSource::Stdin => ctx
+                .read_to_string(&InputSource::Stdin)?
+                .trim_end()
+                .to_string(),
+            input => input.to_string(),
+        };
+        let result = Fixme2Result { input };
+        ctx.progress(format_args!("Running fixme2: {:?}", result))?;
+        Ok(Some(Output::new(&result)))
+    }
+}
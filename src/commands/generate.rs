@@ -0,0 +1,299 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use log::info;
+use roff::Roff;
+
+use crate::settings::shell_variable;
+use crate::{examples, schema, Context, Error, Output, Result};
+
+/// Generates packaging artifacts
+#[derive(Debug, Args)]
+struct GenerateArgs {
+    #[command(subcommand)]
+    command: GenerateCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum GenerateCommand {
+    /// Renders roff man pages for the app and each subcommand
+    Man {
+        /// Directory to write the man pages into
+        dir: PathBuf,
+    },
+    /// Prints a Markdown reference of the environment variables the app reads
+    EnvDocs,
+}
+
+/// Environment variables read besides the settings, by group.
+const ENVIRONMENT: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Output",
+        &[
+            ("NO_COLOR", "Disables colors when set to anything"),
+            (
+                "CLICOLOR_FORCE",
+                "Enables colors even when not writing to a terminal",
+            ),
+            (
+                "RUST_LOG",
+                "Per-module log filters, e.g. `ureq=off`, on top of `--verbose`",
+            ),
+            (
+                "RUST_LOG_STYLE",
+                "Whether log lines are colored: `auto`, `always` or `never`",
+            ),
+        ],
+    ),
+    (
+        "Locale",
+        &[
+            (
+                "LC_ALL",
+                "Language of messages, unless the `locale` setting is given",
+            ),
+            (
+                "LC_MESSAGES",
+                "Language of messages, unless `LC_ALL` is set",
+            ),
+            (
+                "LANG",
+                "Language of messages, unless `LC_ALL` or `LC_MESSAGES` is set",
+            ),
+        ],
+    ),
+    (
+        "Directories",
+        &[
+            (
+                "HOME",
+                "Home directory, under which the directories below default",
+            ),
+            ("XDG_CONFIG_HOME", "Base of the configuration directory"),
+            ("XDG_CACHE_HOME", "Base of the cache directory"),
+            (
+                "XDG_DATA_HOME",
+                "Base of the data directory, where plugins are installed",
+            ),
+            (
+                "XDG_STATE_HOME",
+                "Base of the state directory, where logs are kept",
+            ),
+            (
+                "XDG_RUNTIME_DIR",
+                "Base of the directory of the daemon's socket and pidfile",
+            ),
+        ],
+    ),
+    (
+        "Network",
+        &[
+            (
+                "ALL_PROXY",
+                "Proxy for every request, unless `network.proxy` is set",
+            ),
+            (
+                "HTTPS_PROXY",
+                "Proxy for every request, unless `ALL_PROXY` is set",
+            ),
+            (
+                "HTTP_PROXY",
+                "Proxy for every request, unless `ALL_PROXY` or `HTTPS_PROXY` is set",
+            ),
+        ],
+    ),
+    (
+        "Plugins",
+        &[(
+            "PATH",
+            "Searched for external subcommands after the installed plugins",
+        )],
+    ),
+    (
+        "Service managers",
+        &[
+            (
+                "NOTIFY_SOCKET",
+                "Where the daemon reports readiness to systemd",
+            ),
+            (
+                "WATCHDOG_USEC",
+                "How often the daemon pings systemd's watchdog",
+            ),
+            ("WATCHDOG_PID", "The process systemd's watchdog watches"),
+            (
+                "LISTEN_FDS",
+                "Number of sockets systemd passes to the daemon",
+            ),
+            ("LISTEN_PID", "The process systemd passes sockets to"),
+        ],
+    ),
+];
+
+pub struct Generate;
+
+impl super::Command for Generate {
+    fn clap(&self) -> clap::Command {
+        GenerateArgs::augment_args(clap::Command::new("generate").hide(true))
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: GenerateArgs = ctx.args()?;
+        let app = ctx.app();
+        match &args.command {
+            GenerateCommand::Man { dir } => generate_man(app.command(), app.examples(), dir)?,
+            GenerateCommand::EnvDocs => {
+                write!(ctx.out(), "{}", env_docs(&app.layout().env_prefix))?;
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Writes a man page for `cmd` and, recursively, one for each visible subcommand.
+///
+/// Pages are named after the full command path, e.g. `fixme.1` and
+/// `fixme-fixme1.1`, following the convention used by `git` and `cargo`. Examples
+/// registered in `examples` are rendered into an `EXAMPLES` section.
+///
+/// # Errors
+///
+/// Returns an error if `out_dir` cannot be created or a page cannot be written.
+pub fn generate_man(
+    cmd: &clap::Command,
+    examples: &examples::Registry,
+    out_dir: &Path,
+) -> Result<()> {
+    std::fs::create_dir_all(out_dir).map_err(Error::io(out_dir))?;
+    let mut cmd = cmd.clone();
+    cmd.build();
+    render_man(&cmd, examples, "", cmd.get_name(), out_dir)
+}
+
+fn render_man(
+    cmd: &clap::Command,
+    examples: &examples::Registry,
+    cmd_path: &str,
+    page_name: &str,
+    out_dir: &Path,
+) -> Result<()> {
+    let path = out_dir.join(format!("{}.1", page_name));
+    // The after_help only carries the terminal rendering of the examples.
+    let man = clap_mangen::Man::new(
+        cmd.clone()
+            .name(page_name.to_string())
+            .after_help(None::<&str>),
+    );
+    let mut buffer: Vec<u8> = Vec::new();
+    man.render_title(&mut buffer)?;
+    man.render_name_section(&mut buffer)?;
+    man.render_synopsis_section(&mut buffer)?;
+    man.render_description_section(&mut buffer)?;
+    if cmd.get_arguments().any(|a| !a.is_hide_set()) {
+        man.render_options_section(&mut buffer)?;
+    }
+    if cmd.get_subcommands().any(|s| !s.is_hide_set()) {
+        man.render_subcommands_section(&mut buffer)?;
+    }
+    let cmd_examples = examples.get(cmd_path);
+    if !cmd_examples.is_empty() {
+        let mut roff = Roff::new();
+        examples::render_roff(cmd_examples, &mut roff);
+        roff.to_writer(&mut buffer)?;
+    }
+    if cmd.get_version().is_some() {
+        man.render_version_section(&mut buffer)?;
+    }
+    if cmd.get_author().is_some() {
+        man.render_authors_section(&mut buffer)?;
+    }
+    std::fs::write(&path, buffer).map_err(Error::io(&path))?;
+    info!("Wrote man page: {}", path.display());
+
+    for sub in cmd
+        .get_subcommands()
+        .filter(|s| !s.is_hide_set() && s.get_name() != "help")
+    {
+        render_man(
+            sub,
+            examples,
+            &examples::join_path(cmd_path, sub.get_name()),
+            &format!("{}-{}", page_name, sub.get_name()),
+            out_dir,
+        )?;
+    }
+    Ok(())
+}
+
+/// Renders a Markdown reference of the environment variables the app reads:
+/// one per setting of the [`schema`], named with `prefix`, then the
+/// [others](ENVIRONMENT).
+pub fn env_docs(prefix: &str) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut out = String::from("# Environment variables\n\n## Settings\n\n");
+    out.push_str(&format!(
+        "Each setting can be given as `{prefix}<key>` or, as shells need, with dots \
+         and dashes in the key replaced by underscores; either overrides the \
+         config file.\n\n| Variable | Description | Default |\n|---|---|---|\n",
+        prefix = prefix
+    ));
+    for key in schema::KEYS {
+        let name = key.name.replace('*', "<name>");
+        let (verbatim, shell) = (format!("{}{}", prefix, name), shell_variable(prefix, &name));
+        let variables = match verbatim == shell {
+            true => format!("`{}`", cell(&verbatim)),
+            false => format!("`{}`, `{}`", cell(&verbatim), cell(&shell)),
+        };
+        out.push_str(&format!(
+            "| {} | {} | {} |\n",
+            variables,
+            cell(key.description),
+            key.default.map(|d| format!("`{}`", d)).unwrap_or_default()
+        ));
+    }
+    for (group, variables) in ENVIRONMENT {
+        out.push_str(&format!(
+            "\n## {}\n\n| Variable | Description |\n|---|---|\n",
+            group
+        ));
+        for (name, description) in *variables {
+            out.push_str(&format!("| `{}` | {} |\n", name, cell(description)));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_man_writes_page_per_subcommand() {
+        let dir = tempfile::tempdir().unwrap();
+        let cmd = clap::Command::new("fixme")
+            .subcommand(clap::Command::new("fixme1"))
+            .subcommand(clap::Command::new("generate").hide(true));
+        let mut registry = examples::Registry::new();
+        registry.register(
+            "fixme1",
+            vec![examples::Example::new("fixme fixme1", "Runs fixme1")],
+        );
+
+        generate_man(&cmd, &registry, dir.path()).unwrap();
+
+        assert!(dir.path().join("fixme.1").exists());
+        let page = std::fs::read_to_string(dir.path().join("fixme-fixme1.1")).unwrap();
+        assert!(page.contains(".SH EXAMPLES"));
+        assert!(!dir.path().join("fixme-generate.1").exists());
+    }
+
+    #[test]
+    fn test_env_docs_lists_settings_and_others() {
+        let docs = env_docs("FIXME_");
+
+        assert!(docs.contains("| `FIXME_update.check`, `FIXME_update_check` |"));
+        assert!(docs.contains("`FIXME_alias.<name>`"));
+        assert!(docs.contains("| `NO_COLOR` |"));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::examples::Example;
+use crate::{Context, Output, Result};
+
+pub mod alias;
+pub mod cache;
+#[cfg(feature = "completions")]
+pub mod completions;
+pub mod config;
+pub mod ctl;
+pub mod daemon;
+pub mod doctor;
+pub mod env;
+pub mod explain;
+pub mod fixme1;
+pub mod fixme2;
+pub mod generate;
+pub mod plugin;
+pub mod schedule;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod serve;
+pub mod service;
+pub mod telemetry;
+pub mod version;
+
+/// A subcommand of the application.
+///
+/// Adding a subcommand means implementing this trait in a new module and
+/// listing it in [`registry`]; the [`crate::App`] builds, documents and dispatches
+/// every registered command. Commands are shared between the threads running
+/// `--batch` items concurrently, hence `Send + Sync`.
+pub trait Command: Send + Sync {
+    /// Builds the clap definition of the subcommand.
+    ///
+    /// A required argument whose id is a [schema](crate::schema) key, e.g.
+    /// `auth.token`, may be left out when the environment or config file
+    /// provides that key; see [`crate::flags::relax_configured`].
+    fn clap(&self) -> clap::Command;
+
+    /// Usage examples rendered into the subcommand's help and man page.
+    fn examples(&self) -> Vec<Example> {
+        Vec::new()
+    }
+
+    /// Whether the subcommand accepts arguments after `--`, which are made
+    /// available untouched through [`Context::passthrough`], e.g. to forward
+    /// them to an external tool.
+    fn passthrough(&self) -> bool {
+        false
+    }
+
+    /// Runs the subcommand; its parsed arguments are available through `ctx`.
+    ///
+    /// The returned [`Output`], if any, is rendered in the format selected
+    /// with `--output`. Subcommands producing many records may instead report
+    /// each through [`Context::item`] as it is ready, and return a summary
+    /// of them or `None`.
+    fn run(&self, ctx: &Context) -> Result<Option<Output>>;
+
+    /// Runs the subcommand on the application's tokio runtime, which is
+    /// how the application invokes every subcommand when built with the
+    /// `async` feature. Defaults to [`Command::run`]; subcommands spending
+    /// their time on network requests override it instead.
+    #[cfg(feature = "async")]
+    fn run_async<'a>(&'a self, ctx: &'a Context<'_>) -> BoxFuture<'a, Result<Option<Output>>> {
+        Box::pin(async move { self.run(ctx) })
+    }
+}
+
+/// The future returned by [`Command::run_async`]. It need not be `Send`, as
+/// the [`Context`] it borrows stays on the calling thread.
+#[cfg(feature = "async")]
+pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + 'a>>;
+
+/// Returns every subcommand of the application, in help order.
+pub fn registry() -> Vec<Box<dyn Command>> {
+    vec![
+        Box::new(fixme1::Fixme1),
+        Box::new(fixme2::Fixme2),
+        Box::new(config::Config),
+        Box::new(env::Env),
+        #[cfg(feature = "completions")]
+        Box::new(completions::Completions),
+        Box::new(explain::Explain),
+        Box::new(generate::Generate),
+        Box::new(plugin::Plugin),
+        Box::new(alias::Alias),
+        Box::new(version::Version),
+        Box::new(doctor::Doctor),
+        Box::new(telemetry::Telemetry),
+        Box::new(cache::Cache),
+        Box::new(daemon::Daemon),
+        Box::new(ctl::Ctl),
+        Box::new(schedule::Schedule),
+        Box::new(serve::Serve),
+        Box::new(service::Service),
+    ]
+}
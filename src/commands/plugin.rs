@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use serde::Serialize;
+
+use crate::examples::Example;
+use crate::plugin::{self, Installed};
+use crate::{Context, Error, Output, Result};
+
+/// Manages external subcommand plugins
+#[derive(Debug, Args)]
+struct PluginArgs {
+    #[command(subcommand)]
+    command: PluginCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum PluginCommand {
+    /// Lists the external subcommands, installed or found on PATH
+    List,
+    /// Installs an executable as an external subcommand
+    Install(InstallArgs),
+    /// Removes installed plugins
+    Remove {
+        /// Subcommands provided by the plugins to remove
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Reinstalls plugins from the files they were installed from
+    Update {
+        /// Subcommands provided by the plugins to update [default: all]
+        names: Vec<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+struct InstallArgs {
+    /// Executable to install, e.g. ./fixme-foo
+    source: PathBuf,
+    /// Subcommand the plugin provides [default: the file name without fixme-]
+    #[arg(long)]
+    name: Option<String>,
+    /// Expected SHA-256 checksum of the executable, in hex
+    #[arg(long, value_name = "HEX")]
+    sha256: Option<String>,
+}
+
+/// An external subcommand, as listed by `plugin list`.
+#[derive(Debug, Serialize)]
+struct Listed {
+    subcommand: String,
+    path: PathBuf,
+    source: Option<PathBuf>,
+    sha256: Option<String>,
+}
+
+pub struct Plugin;
+
+impl super::Command for Plugin {
+    fn clap(&self) -> clap::Command {
+        PluginArgs::augment_args(clap::Command::new("plugin"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new(
+                "FIXME plugin install ./fixme-deploy --sha256 <HEX>",
+                "Installs fixme-deploy as `FIXME deploy` after checking its checksum",
+            ),
+            Example::new(
+                "FIXME plugin update",
+                "Reinstalls every plugin whose source file changed",
+            ),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: PluginArgs = ctx.args()?;
+        let dir = plugin::plugins_dir();
+        let mut installed = plugin::manifest(&dir)?;
+        match args.command {
+            PluginCommand::List => {
+                let mut listed: Vec<Listed> = installed
+                    .iter()
+                    .map(|(name, plugin)| Listed {
+                        subcommand: name.clone(),
+                        path: plugin::executable(&dir, name),
+                        source: Some(plugin.source.clone()),
+                        sha256: Some(plugin.sha256.clone()),
+                    })
+                    .collect();
+                for (name, path) in plugin::discover(std::env::var_os("PATH").as_deref()) {
+                    if !installed.contains_key(&name) {
+                        listed.push(Listed {
+                            subcommand: name,
+                            path,
+                            source: None,
+                            sha256: None,
+                        });
+                    }
+                }
+                let human = listed
+                    .iter()
+                    .map(|l| match &l.source {
+                        Some(source) => format!(
+                            "{}  {} (installed from {})",
+                            l.subcommand,
+                            l.path.display(),
+                            source.display()
+                        ),
+                        None => format!("{}  {}", l.subcommand, l.path.display()),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Ok(Some(Output::new(&listed).human(human)))
+            }
+            PluginCommand::Install(install) => {
+                let name = match install.name {
+                    Some(name) => name,
+                    None => plugin::subcommand_name(&install.source).ok_or_else(|| {
+                        Error::Subcommand(format!(
+                            "cannot tell the subcommand from '{}'; pass --name",
+                            install.source.display()
+                        ))
+                    })?,
+                };
+                plugin::check_name(&name)?;
+                if ctx.app().command().find_subcommand(&name).is_some() {
+                    return Err(Error::Subcommand(format!(
+                        "'{}' is a built-in subcommand, which a plugin cannot replace",
+                        name
+                    )));
+                }
+                if install.sha256.is_none()
+                    && ctx
+                        .settings()
+                        .handle_request("plugins.public_key")
+                        .is_none()
+                {
+                    ctx.warn(format_args!(
+                        "installing '{}' unverified; pass --sha256 or set plugins.public_key",
+                        name
+                    ))?;
+                }
+                if !ctx.is_dry_run()
+                    && installed.contains_key(&name)
+                    && !ctx
+                        .prompt()
+                        .confirm(&format!("Replace the installed plugin '{}'?", name), false)?
+                {
+                    return Ok(None);
+                }
+                let source =
+                    std::path::absolute(&install.source).map_err(Error::io(&install.source))?;
+                let (content, sha256) = fetch(ctx, &source, install.sha256.as_deref())?;
+                let installed_now =
+                    ctx.effect(format_args!("install '{}' as a plugin", name), || {
+                        plugin::install(&dir, &name, &content)?;
+                        installed.insert(name.clone(), Installed { source, sha256 });
+                        plugin::write_manifest(&dir, &installed)
+                    })?;
+                if installed_now.is_some() {
+                    ctx.progress(format_args!(
+                        "Installed `{} {}`",
+                        ctx.app().command().get_name(),
+                        name
+                    ))?;
+                }
+                Ok(None)
+            }
+            PluginCommand::Remove { names } => {
+                for name in &names {
+                    require_installed(&installed, name)?;
+                }
+                ctx.effect(format_args!("remove {}", names.join(", ")), || {
+                    for name in &names {
+                        let path = plugin::executable(&dir, name);
+                        match std::fs::remove_file(&path) {
+                            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                                return Err(Error::io(&path)(e))
+                            }
+                            _ => {}
+                        }
+                        installed.remove(name);
+                    }
+                    plugin::write_manifest(&dir, &installed)
+                })?;
+                Ok(None)
+            }
+            PluginCommand::Update { names } => {
+                for name in &names {
+                    require_installed(&installed, name)?;
+                }
+                let names: Vec<String> = match names.is_empty() {
+                    true => installed.keys().cloned().collect(),
+                    false => names,
+                };
+                for name in names {
+                    let source = installed[&name].source.clone();
+                    let (content, sha256) = fetch(ctx, &source, None)?;
+                    if sha256 == installed[&name].sha256 {
+                        ctx.progress(format_args!("'{}' is up to date", name))?;
+                        continue;
+                    }
+                    let description = format!("update '{}' from {}", name, source.display());
+                    let updated = ctx.effect(description, || {
+                        plugin::install(&dir, &name, &content)?;
+                        installed.insert(name.clone(), Installed { source, sha256 });
+                        plugin::write_manifest(&dir, &installed)
+                    })?;
+                    if updated.is_some() {
+                        ctx.progress(format_args!("Updated '{}'", name))?;
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Reads the plugin at `source`, checking it against the expected `sha256`
+/// and, when `plugins.public_key` is set, against its minisign signature in
+/// `<source>.minisig`. Returns the content and its checksum.
+fn fetch(ctx: &Context, source: &Path, sha256: Option<&str>) -> Result<(Vec<u8>, String)> {
+    let content = std::fs::read(source).map_err(Error::io(source))?;
+    let actual = plugin::sha256(&content);
+    if let Some(expected) = sha256 {
+        if !expected.eq_ignore_ascii_case(&actual) {
+            return Err(Error::Subcommand(format!(
+                "checksum mismatch for '{}': expected {}, got {}",
+                source.display(),
+                expected,
+                actual
+            )));
+        }
+    }
+    if let Some(public_key) = ctx.settings().handle_request("plugins.public_key") {
+        let mut signature_path = source.as_os_str().to_owned();
+        signature_path.push(".minisig");
+        let signature = std::fs::read_to_string(&signature_path)
+            .map_err(Error::io(PathBuf::from(&signature_path)))?;
+        plugin::verify_signature(&content, &signature, &public_key)?;
+    }
+    Ok((content, actual))
+}
+
+fn require_installed(
+    installed: &std::collections::BTreeMap<String, Installed>,
+    name: &str,
+) -> Result<()> {
+    match installed.contains_key(name) {
+        true => Ok(()),
+        false => Err(Error::Subcommand(format!(
+            "plugin '{}' is not installed",
+            name
+        ))),
+    }
+}
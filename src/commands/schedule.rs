@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use clap::Args;
+
+use crate::examples::Example;
+use crate::schedule::{Job, When};
+use crate::{Context, Error, Output, Result};
+
+/// How often the scheduler checks whether a run is due or has finished.
+const POLL: Duration = Duration::from_millis(100);
+
+/// Runs a subcommand repeatedly, at a fixed interval or on a cron expression,
+/// until interrupted
+#[derive(Debug, Args)]
+struct ScheduleArgs {
+    /// Runs the subcommand at this interval, e.g. 5m
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = crate::values::duration,
+        required_unless_present = "cron",
+        conflicts_with = "cron"
+    )]
+    every: Option<Duration>,
+    /// Runs the subcommand whenever this cron expression matches, in local time
+    #[arg(long, value_name = "EXPR")]
+    cron: Option<String>,
+    /// Delays each run by a random duration up to this, e.g. 30s
+    #[arg(long, value_name = "DURATION", value_parser = crate::values::duration, default_value = "0s")]
+    jitter: Duration,
+}
+
+pub struct Schedule;
+
+impl super::Command for Schedule {
+    fn clap(&self) -> clap::Command {
+        ScheduleArgs::augment_args(clap::Command::new("schedule"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new(
+                "FIXME schedule --every 5m -- fixme1 foo",
+                "Runs 'fixme1 foo' every five minutes",
+            ),
+            Example::new(
+                "FIXME schedule --cron '0 3 * * *' --jitter 10m -- fixme2",
+                "Runs 'fixme2' around 3 a.m. every day",
+            ),
+        ]
+    }
+
+    fn passthrough(&self) -> bool {
+        true
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: ScheduleArgs = ctx.args()?;
+        let when = match (args.every, args.cron) {
+            (Some(interval), _) => When::Every(interval),
+            (None, Some(expression)) => When::cron(&expression)?,
+            (None, None) => unreachable!("clap requires --every or --cron"),
+        };
+        let command: Vec<String> = ctx
+            .passthrough()
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        if command.is_empty() {
+            return Err(Error::Subcommand(
+                "name the subcommand to run after '--', e.g. -- fixme1 foo".to_string(),
+            ));
+        }
+        let description = format!("run '{}' {}", shell_words::join(&command), when);
+        let mut job = Job::new("schedule", when, command, args.jitter, ctx.time().now());
+        ctx.effect(description, || {
+            if let Some(next) = job.next() {
+                ctx.progress(format_args!(
+                    "next run at {}; press Ctrl-C to stop",
+                    ctx.numbers().datetime(next)
+                ))?;
+            }
+            let result = run(ctx, &mut job);
+            job.finish();
+            result
+        })?;
+        Ok(None)
+    }
+}
+
+/// Runs `job` until the scheduler is interrupted.
+fn run(ctx: &Context, job: &mut Job) -> Result<()> {
+    while !ctx.cancellation_token().is_cancelled() {
+        job.poll(ctx.time().now())?;
+        ctx.time().sleep(POLL);
+    }
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+use crate::{schema, settings, Context, Error, Output, Result};
+
+/// Extension of the scripts registered as subcommands.
+pub const EXTENSION: &str = "rhai";
+
+/// A subcommand implemented by a user's rhai script.
+///
+/// The script sees its command-line arguments as the `args` array and the
+/// resolved, non-secret settings as the `settings` map. `print` writes to
+/// stdout and `debug` to stderr; a value the script ends with is its result,
+/// rendered like any other subcommand's.
+pub struct Script {
+    name: String,
+    path: PathBuf,
+    about: String,
+    ast: AST,
+}
+
+impl Script {
+    /// Compiles the script at `path`, named after the file's stem. Its
+    /// leading `//` comment lines become the subcommand's description.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read, or
+    /// [`Error::Subcommand`] if it does not compile.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path).map_err(Error::io(path))?;
+        let ast = Engine::new().compile(&source).map_err(|e| {
+            Error::Subcommand(format!("failed to compile '{}': {}", path.display(), e))
+        })?;
+        let about = source
+            .lines()
+            .map_while(|line| line.strip_prefix("//"))
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(" ");
+        Ok(Script {
+            name: path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            path: path.to_path_buf(),
+            about,
+            ast,
+        })
+    }
+}
+
+/// Returns the directory scripts are loaded from, next to the config file.
+pub fn scripts_dir() -> PathBuf {
+    settings::config_path()
+        .parent()
+        .map(|dir| dir.join("scripts"))
+        .unwrap_or_default()
+}
+
+/// Appends the scripts found in [`scripts_dir`] to `commands`.
+pub fn register(mut commands: Vec<Box<dyn super::Command>>) -> Vec<Box<dyn super::Command>> {
+    let scripts = discover(&scripts_dir(), &commands);
+    commands.extend(scripts);
+    commands
+}
+
+/// Loads every script in `dir`, in name order, skipping with a warning those
+/// that fail to load or whose names are already taken by `existing` commands.
+pub fn discover(dir: &Path, existing: &[Box<dyn super::Command>]) -> Vec<Box<dyn super::Command>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .collect();
+    paths.sort();
+
+    let mut scripts: Vec<Box<dyn super::Command>> = Vec::new();
+    for path in paths {
+        match Script::load(&path) {
+            Ok(script)
+                if existing
+                    .iter()
+                    .chain(&scripts)
+                    .any(|c| c.clap().get_name() == script.name) =>
+            {
+                warn!(
+                    "ignoring script '{}': a command named '{}' already exists",
+                    path.display(),
+                    script.name
+                );
+            }
+            Ok(script) => scripts.push(Box::new(script)),
+            Err(e) => warn!("ignoring script: {}", e),
+        }
+    }
+    scripts
+}
+
+impl super::Command for Script {
+    fn clap(&self) -> clap::Command {
+        clap::Command::new(self.name.clone())
+            .about(format!("{} [script: {}]", self.about, self.path.display()))
+            .arg(
+                clap::Arg::new("args")
+                    .help("Arguments passed to the script")
+                    .num_args(0..)
+                    .trailing_var_arg(true)
+                    .allow_hyphen_values(true),
+            )
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: rhai::Array = ctx
+            .matches()
+            .get_many::<String>("args")
+            .unwrap_or_default()
+            .map(|arg| Dynamic::from(arg.clone()))
+            .collect();
+        let settings: Map = settings::snapshot(ctx.settings())
+            .into_iter()
+            .filter(|(key, _)| schema::lookup(key).is_ok_and(|k| !k.secret))
+            .map(|(key, value)| (key.into(), Dynamic::from(value)))
+            .collect();
+
+        let stdout = Arc::new(Mutex::new(Vec::new()));
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        let out = Arc::clone(&stdout);
+        engine.on_print(move |text| {
+            let _ = writeln!(out.lock().expect("script output lock"), "{}", text);
+        });
+        let err = Arc::clone(&stderr);
+        engine.on_debug(move |text, _, _| {
+            let _ = writeln!(err.lock().expect("script output lock"), "{}", text);
+        });
+        let mut scope = Scope::new();
+        scope.push("args", args).push("settings", settings);
+
+        let result = engine.eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast);
+        ctx.out()
+            .write_all(&stdout.lock().expect("script output lock"))?;
+        ctx.err()
+            .write_all(&stderr.lock().expect("script output lock"))?;
+        let value = result.map_err(|e| {
+            Error::Subcommand(format!("script '{}' failed: {}", self.path.display(), e))
+        })?;
+        if value.is_unit() {
+            return Ok(None);
+        }
+        Ok(Some(Output::new(&value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::App;
+
+    #[test]
+    fn test_script_prints_and_returns_a_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("greet.rhai");
+        std::fs::write(
+            &path,
+            "// Greets someone\nprint(`hello ${args[0]}`);\n#{ name: args[0] }",
+        )
+        .unwrap();
+        let scripts = discover(dir.path(), &[]);
+        assert_eq!(1, scripts.len());
+        let script = &scripts[0];
+        let app = App::new();
+        let matches = script.clap().get_matches_from(["greet", "world"]);
+        let mut stdout = Vec::new();
+
+        let output = script
+            .run(&Context::new(&app, &matches).stdout(&mut stdout))
+            .unwrap();
+
+        assert_eq!("hello world\n", String::from_utf8(stdout).unwrap());
+        assert_eq!(
+            serde_json::json!({"name": "world"}),
+            *output.unwrap().value()
+        );
+    }
+}
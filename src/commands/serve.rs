@@ -0,0 +1,49 @@
+use std::io::{BufRead, BufReader, Write};
+
+use clap::Args;
+
+use crate::examples::Example;
+use crate::{rpc, Context, InputSource, Output, Result};
+
+/// Answers requests from another program without starting a process for each
+#[derive(Debug, Args)]
+struct ServeArgs {
+    /// Read JSON-RPC 2.0 requests from stdin, one per line, and write the responses to stdout
+    #[arg(long, required = true)]
+    stdio: bool,
+}
+
+pub struct Serve;
+
+impl super::Command for Serve {
+    fn clap(&self) -> clap::Command {
+        ServeArgs::augment_args(clap::Command::new("serve"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example::new(
+            r#"echo '{"jsonrpc": "2.0", "id": 1, "method": "config.get", "params": ["verbosity"]}' | FIXME serve --stdio"#,
+            "Runs `config get verbosity` and answers with its JSON output",
+        )]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let _args: ServeArgs = ctx.args()?;
+        let stdin = BufReader::new(ctx.open(&InputSource::Stdin)?);
+        for line in stdin.lines() {
+            if ctx.cancellation_token().is_cancelled() {
+                break;
+            }
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = rpc::respond(ctx.app(), &line) {
+                let mut out = ctx.out();
+                writeln!(out, "{}", response)?;
+                out.flush()?;
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,108 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+
+use crate::examples::Example;
+#[cfg(windows)]
+use crate::service;
+use crate::{daemon, Context, Error, Output, Result};
+
+/// Runs the daemon under the system's service manager
+#[derive(Debug, Args)]
+struct ServiceArgs {
+    #[command(subcommand)]
+    command: ServiceCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ServiceCommand {
+    /// Prints a launchd job running the daemon at login, for macOS
+    LaunchdPlist {
+        /// Path of the executable to run [default: this executable]
+        #[arg(long, value_name = "PATH")]
+        program: Option<PathBuf>,
+    },
+    /// Registers the service, started at boot
+    #[cfg(windows)]
+    Install,
+    /// Stops and removes the service
+    #[cfg(windows)]
+    Uninstall,
+    /// Runs the daemon as the service; started by the service control manager
+    #[cfg(windows)]
+    #[command(hide = true)]
+    Run,
+}
+
+pub struct Service;
+
+impl super::Command for Service {
+    fn clap(&self) -> clap::Command {
+        ServiceArgs::augment_args(clap::Command::new("service"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new(
+                "FIXME service launchd-plist > ~/Library/LaunchAgents/com.example.fixme.plist",
+                "Writes the launchd job for the current user",
+            ),
+            Example::new(
+                "launchctl load ~/Library/LaunchAgents/com.example.fixme.plist",
+                "Starts the daemon now and at every login",
+            ),
+            #[cfg(windows)]
+            Example::new(
+                "FIXME service install",
+                "Registers the daemon as a service, from an elevated prompt",
+            ),
+            #[cfg(windows)]
+            Example::new("sc.exe start fixme", "Starts the installed service"),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: ServiceArgs = ctx.args()?;
+        match args.command {
+            ServiceCommand::LaunchdPlist { program } => {
+                let program = match program {
+                    Some(program) => program,
+                    None => std::env::current_exe().map_err(Error::Output)?,
+                };
+                // Nothing would reach the log file without a level.
+                let verbosity = ctx.require("verbosity")?;
+                let args = ["--verbose", &verbosity, "daemon", "start", "--foreground"];
+                let plist = daemon::launchd_plist(&program, &args, &daemon::log_path());
+                write!(ctx.out(), "{}", plist)?;
+            }
+            #[cfg(windows)]
+            ServiceCommand::Install => {
+                if ctx
+                    .effect("install the service", service::install)?
+                    .is_some()
+                {
+                    ctx.progress(format_args!("Installed the service {}", service::NAME))?;
+                }
+            }
+            #[cfg(windows)]
+            ServiceCommand::Uninstall => {
+                if ctx
+                    .effect("uninstall the service", service::uninstall)?
+                    .is_some()
+                {
+                    ctx.progress(format_args!("Uninstalled the service {}", service::NAME))?;
+                }
+            }
+            #[cfg(windows)]
+            ServiceCommand::Run => {
+                ctx.effect("run the service", || {
+                    service::run(ctx.cancellation_token().clone(), || {
+                        super::daemon::run_foreground(ctx)
+                    })
+                })?;
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,85 @@
+use clap::{Args, Subcommand};
+use serde::Serialize;
+
+use crate::examples::Example;
+use crate::{flags, settings, telemetry, Context, Output, Result};
+
+/// Shows or changes whether anonymous usage statistics are collected
+#[derive(Debug, Args)]
+struct TelemetryArgs {
+    #[command(subcommand)]
+    command: TelemetryCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum TelemetryCommand {
+    /// Shows whether telemetry is enabled and how many events await upload
+    Status,
+    /// Starts recording and uploading usage statistics
+    Enable,
+    /// Stops recording usage statistics; pending events are not uploaded
+    Disable,
+}
+
+/// What `telemetry status` reports.
+#[derive(Debug, Serialize)]
+struct Status {
+    enabled: bool,
+    pending: usize,
+    path: String,
+    endpoint: &'static str,
+}
+
+pub struct Telemetry;
+
+impl super::Command for Telemetry {
+    fn clap(&self) -> clap::Command {
+        TelemetryArgs::augment_args(clap::Command::new("telemetry"))
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example::new(
+                "FIXME telemetry enable",
+                "Shares which subcommands are run, how long they take and whether they succeed",
+            ),
+            Example::new(
+                "FIXME --no-telemetry fixme1 foo",
+                "Runs once without recording, even with telemetry enabled",
+            ),
+        ]
+    }
+
+    fn run(&self, ctx: &Context) -> Result<Option<Output>> {
+        let args: TelemetryArgs = ctx.args()?;
+        let enabled = match args.command {
+            TelemetryCommand::Status => {
+                let path = telemetry::events_path();
+                let status = Status {
+                    enabled: flags::resolve(ctx.matches(), ctx.settings(), "telemetry"),
+                    pending: telemetry::pending(&path)?.len()
+                        + telemetry::pending(&telemetry::spool_path(&path))?.len(),
+                    path: path.display().to_string(),
+                    endpoint: telemetry::ENDPOINT,
+                };
+                let human = format!(
+                    "Telemetry is {}; {} event(s) pending in {}",
+                    if status.enabled {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    },
+                    ctx.numbers().integer(status.pending as u64),
+                    status.path
+                );
+                return Ok(Some(Output::new(&status).human(human)));
+            }
+            TelemetryCommand::Enable => "true",
+            TelemetryCommand::Disable => "false",
+        };
+        let path = ctx.app().layout().config_path();
+        let description = format!("set telemetry to {} in {}", enabled, path.display());
+        ctx.effect(description, || settings::set(&path, "telemetry", enabled))?;
+        Ok(None)
+    }
+}
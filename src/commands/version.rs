@@ -0,0 +1,22 @@
+use crate::examples::Example;
+use crate::version::BUILD;
+use crate::{Context, Output, Result};
+
+pub struct Version;
+
+impl super::Command for Version {
+    fn clap(&self) -> clap::Command {
+        clap::Command::new("version").about("Prints the version and how this binary was built")
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example::new(
+            "FIXME version --output json",
+            "Prints the build details for a bug report or script",
+        )]
+    }
+
+    fn run(&self, _ctx: &Context) -> Result<Option<Output>> {
+        Ok(Some(Output::new(&BUILD).human(BUILD.long())))
+    }
+}
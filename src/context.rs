@@ -0,0 +1,517 @@
+use std::cell::{RefCell, RefMut};
+use std::ffi::{OsStr, OsString};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use clap::{ArgMatches, FromArgMatches};
+use cor_args::Handler;
+use log::debug;
+
+use serde::Serialize;
+
+use crate::clock::{Clock, SystemClock};
+use crate::events::{Bus, Event, Report, Sink};
+use crate::glyphs::{self, Glyphs};
+use crate::progress::Heartbeat;
+use crate::prompt::{PromptMode, Prompter};
+use crate::reporter::{self, HumanReporter, Reporter};
+use crate::{schema, App, Error, InputSource, Output, OutputFormat, Renderer, Result};
+
+/// Id of the argument collecting everything after `--` for subcommands that
+/// accept [passthrough](crate::commands::Command::passthrough) arguments.
+pub const PASSTHROUGH: &str = "passthrough";
+
+/// A flag shared between the application and a running subcommand, signalling
+/// that the subcommand should stop as soon as it safely can.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    parent: Option<Box<CancellationToken>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Requests cancellation; visible to every clone of this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns a token that is also cancelled when this one is, but whose
+    /// own cancellation does not affect this one.
+    pub fn child(&self) -> CancellationToken {
+        CancellationToken {
+            cancelled: Arc::default(),
+            parent: Some(Box::new(self.clone())),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+            || self.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+}
+
+/// Everything a subcommand needs to run, handed to [`crate::commands::Command::run`].
+pub struct Context<'a> {
+    app: &'a App,
+    matches: &'a ArgMatches,
+    settings: Box<dyn Handler + 'a>,
+    stdin: RefCell<Box<dyn Read + 'a>>,
+    stdout: RefCell<Box<dyn Write + 'a>>,
+    stderr: RefCell<Box<dyn Write + 'a>>,
+    cancellation: CancellationToken,
+    clock: Arc<dyn Clock>,
+    dry_run: bool,
+    renderer: Renderer,
+    bus: RefCell<Bus>,
+    reporter: RefCell<Box<dyn Reporter>>,
+    glyphs: &'static Glyphs,
+    prompter: Prompter,
+    rng: RefCell<fastrand::Rng>,
+}
+
+impl<'a> Context<'a> {
+    /// Creates a context for the subcommand whose parsed arguments are `matches`.
+    ///
+    /// The settings chain starts at `matches`, input and output use the
+    /// process's standard streams, and dry-run is disabled.
+    pub fn new(app: &'a App, matches: &'a ArgMatches) -> Self {
+        Context {
+            app,
+            matches,
+            settings: app.layout().chain(Some(matches)),
+            stdin: RefCell::new(Box::new(std::io::stdin())),
+            stdout: RefCell::new(Box::new(std::io::stdout())),
+            stderr: RefCell::new(Box::new(std::io::stderr())),
+            cancellation: CancellationToken::new(),
+            clock: Arc::new(SystemClock),
+            dry_run: false,
+            renderer: Renderer::default(),
+            bus: RefCell::new(Bus::reporting(Report::Human)),
+            reporter: RefCell::new(Box::new(HumanReporter)),
+            glyphs: &glyphs::UNICODE,
+            prompter: Prompter::default(),
+            rng: RefCell::new(fastrand::Rng::new()),
+        }
+    }
+
+    /// Redirects the subcommand's standard input to `stdin`.
+    pub fn stdin<R: Read + 'a>(mut self, stdin: R) -> Self {
+        self.stdin = RefCell::new(Box::new(stdin));
+        self
+    }
+
+    /// Redirects the subcommand's standard output to `stdout`.
+    pub fn stdout<W: Write + 'a>(mut self, stdout: W) -> Self {
+        self.stdout = RefCell::new(Box::new(stdout));
+        self
+    }
+
+    /// Redirects the subcommand's standard error to `stderr`.
+    pub fn stderr<W: Write + 'a>(mut self, stderr: W) -> Self {
+        self.stderr = RefCell::new(Box::new(stderr));
+        self
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets the symbols drawn in progress reporting.
+    pub fn glyphs(mut self, glyphs: &'static Glyphs) -> Self {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// Sets how questions to the user are answered.
+    pub fn prompter(mut self, prompter: Prompter) -> Self {
+        self.prompter = prompter;
+        self
+    }
+
+    /// Sets how the subcommand's result is rendered, and the [`Reporter`]
+    /// for its format.
+    pub fn renderer(mut self, renderer: Renderer) -> Self {
+        self.reporter = RefCell::new(reporter::select(&renderer));
+        self.renderer = renderer;
+        self
+    }
+
+    /// Sets the bus the subcommand's events are published on, which
+    /// otherwise reports them for people.
+    pub fn bus(mut self, bus: Bus) -> Self {
+        self.bus = RefCell::new(bus);
+        self
+    }
+
+    pub fn cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Sets the source of the current time, e.g. a [`crate::clock::FrozenClock`] in tests.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Seeds the random numbers of [`Context::rng`], which are otherwise
+    /// seeded from entropy.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = RefCell::new(fastrand::Rng::with_seed(seed));
+        self
+    }
+
+    /// The application the subcommand belongs to.
+    pub fn app(&self) -> &App {
+        self.app
+    }
+
+    /// Parses the subcommand's arguments into `T`.
+    pub fn args<T: FromArgMatches>(&self) -> Result<T, clap::Error> {
+        T::from_arg_matches(self.matches)
+    }
+
+    /// The arguments given after `--`, untouched, for subcommands that accept them.
+    pub fn passthrough(&self) -> Vec<OsString> {
+        match self.matches.try_get_raw(PASSTHROUGH) {
+            Ok(Some(values)) => values.map(OsStr::to_os_string).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The subcommand's raw argument matches, for handlers such as [`cor_args::ArgHandler`].
+    pub fn matches(&self) -> &ArgMatches {
+        self.matches
+    }
+
+    /// The resolved settings chain: arguments, then environment, then config file.
+    pub fn settings(&self) -> &dyn Handler {
+        &*self.settings
+    }
+
+    /// Resolves the setting `name` from the settings chain or its default.
+    ///
+    /// A [secret](schema::Key::secret) that no source provides is asked for
+    /// without echoing it, when the user can be asked.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::UnknownKey`] if `name` is not in the schema, or
+    /// [`crate::Error::MissingKey`] if nothing provides a value.
+    pub fn require(&self, name: &str) -> Result<String> {
+        let key = schema::lookup(name)?;
+        if let Some(value) = self
+            .settings()
+            .handle_request(key.name)
+            .or_else(|| key.default.map(str::to_string))
+        {
+            return Ok(value);
+        }
+        if key.secret && self.prompter.mode() == PromptMode::Interactive {
+            return self.prompter.password(key.name);
+        }
+        Err(Error::MissingKey {
+            key: key.name.to_string(),
+        })
+    }
+
+    /// Resolves the setting `name` like [`Context::require`] and converts it
+    /// with `parse`, e.g. [`crate::values::duration`] for `network.timeout`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Context::require`], or
+    /// [`crate::Error::InvalidValue`] if `parse` rejects the value.
+    pub fn require_as<T, F>(&self, name: &str, parse: F) -> Result<T>
+    where
+        F: FnOnce(&str) -> std::result::Result<T, String>,
+    {
+        let value = self.require(name)?;
+        parse(&value).map_err(|reason| Error::InvalidValue {
+            key: name.to_string(),
+            reason,
+        })
+    }
+
+    /// Opens `source` for reading; [`InputSource::Stdin`] reads the
+    /// subcommand's standard input.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Io`] if the file cannot be opened.
+    pub fn open(&self, source: &InputSource) -> Result<Box<dyn Read + '_>> {
+        match source {
+            InputSource::Stdin => Ok(Box::new(StdinReader(self.stdin.borrow_mut()))),
+            InputSource::File(path) => Ok(Box::new(
+                std::fs::File::open(path).map_err(Error::io(path))?,
+            )),
+        }
+    }
+
+    /// Reads all of `source` into a string.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Io`] if the file cannot be read, or
+    /// [`crate::Error::Output`] if stdin cannot.
+    pub fn read_to_string(&self, source: &InputSource) -> Result<String> {
+        let mut content = String::new();
+        self.open(source)?
+            .read_to_string(&mut content)
+            .map_err(|e| match source {
+                InputSource::Stdin => Error::Output(e),
+                InputSource::File(path) => Error::io(path)(e),
+            })?;
+        Ok(content)
+    }
+
+    /// The writer for the subcommand's results; use it instead of `println!`.
+    ///
+    /// Only results belong on stdout, so that it can be piped into other tools.
+    /// Prefer returning an [`Output`] over writing here directly.
+    pub fn out(&self) -> RefMut<'_, Box<dyn Write + 'a>> {
+        self.stdout.borrow_mut()
+    }
+
+    /// The writer for everything that is not a result, such as diagnostics,
+    /// prompts and progress bars; use it instead of `eprintln!`.
+    ///
+    /// Prefer [`Context::progress`] and [`Context::warn`] for messages, so
+    /// that they are reported as the user asked.
+    pub fn err(&self) -> RefMut<'_, Box<dyn Write + 'a>> {
+        self.stderr.borrow_mut()
+    }
+
+    /// Publishes `event` on the subcommand's bus.
+    pub fn publish(&self, event: &Event) -> Result<()> {
+        let (mut out, mut err) = (self.out(), self.err());
+        let mut sink = Sink {
+            out: &mut **out,
+            err: &mut **err,
+            renderer: &self.renderer,
+        };
+        self.bus.borrow_mut().publish(event, &mut sink)
+    }
+
+    /// Reports a step of the subcommand's work, e.g. `Installed 'lint'`.
+    pub fn progress<M: std::fmt::Display>(&self, message: M) -> Result<()> {
+        self.publish(&Event::Progress {
+            message: &message.to_string(),
+        })
+    }
+
+    /// Reports something that went wrong without failing the subcommand.
+    pub fn warn<M: std::fmt::Display>(&self, message: M) -> Result<()> {
+        self.reporter.borrow_mut().warn(self, &message.to_string())
+    }
+
+    /// Starts reporting that `task` is still running; see [`Heartbeat`].
+    pub fn heartbeat<S: Into<String>>(&self, task: S) -> Heartbeat {
+        Heartbeat::start(task, self.glyphs)
+    }
+
+    /// Asks the user questions, honouring `--yes` and `--no-input`.
+    pub fn prompt(&self) -> &Prompter {
+        &self.prompter
+    }
+
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// The source of the current time; use it instead of [`std::time::SystemTime::now`]
+    /// and [`std::thread::sleep`] so that runs can be made deterministic.
+    pub fn time(&self) -> &dyn Clock {
+        &*self.clock
+    }
+
+    /// How to write numbers and dates in text for people, such as an
+    /// [`Output::human`] rendering; see [`Renderer::numbers`].
+    pub fn numbers(&self) -> &crate::render::locale::Locale {
+        self.renderer.numbers()
+    }
+
+    /// The source of random numbers, e.g. for IDs, sampling or jitter; use
+    /// it instead of [`fastrand`]'s global functions so that runs can be
+    /// reproduced with `--seed`.
+    pub fn rng(&self) -> RefMut<'_, fastrand::Rng> {
+        self.rng.borrow_mut()
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Performs a side effect, or with `--dry-run` only reports it.
+    ///
+    /// `description` completes the sentence "Would ...", e.g. `"write
+    /// config.json"`, and is written to stderr instead of running `action`
+    /// in a dry run. Returns the action's result, or `None` when it was skipped.
+    pub fn effect<T, D, F>(&self, description: D, action: F) -> Result<Option<T>>
+    where
+        D: std::fmt::Display,
+        F: FnOnce() -> Result<T>,
+    {
+        if self.dry_run {
+            self.progress(format_args!("Would {}", description))?;
+            return Ok(None);
+        }
+        debug!("{}", description);
+        action().map(Some)
+    }
+
+    /// The format the subcommand's result will be rendered in.
+    pub fn format(&self) -> OutputFormat {
+        self.renderer.format()
+    }
+
+    /// Reports `output` as a result of the subcommand, rendered to its stdout
+    /// as the user asked for.
+    pub fn render(&self, output: &Output) -> Result<()> {
+        self.publish(&Event::Result { output })
+    }
+
+    /// Reports one record of a subcommand that produces many.
+    ///
+    /// Human output shows each item as a line once it is ready, as does
+    /// `--output ndjson` or `--format`; other formats collect the items and
+    /// render them together once the subcommand returns.
+    pub fn item(&self, item: Output) -> Result<()> {
+        self.reporter.borrow_mut().item(self, item)
+    }
+
+    /// Reports one serializable record; see [`Context::item`].
+    pub fn emit<T: Serialize>(&self, record: &T) -> Result<()> {
+        self.item(Output::new(record))
+    }
+
+    /// Reports the outcome of the whole subcommand, e.g. totals after its
+    /// items, for people; output for programs carries only the items.
+    pub fn summary(&self, summary: Output) -> Result<()> {
+        self.reporter.borrow_mut().summary(self, summary)
+    }
+
+    /// Discards the items reported so far, before the subcommand is run again.
+    pub(crate) fn reset(&self) {
+        *self.reporter.borrow_mut() = reporter::select(&self.renderer);
+    }
+
+    /// Reports the subcommand's returned `output` as its summary, then
+    /// whatever its reporter held back.
+    pub(crate) fn finish(&self, output: Option<Output>) -> Result<()> {
+        let mut reporter = self.reporter.borrow_mut();
+        if let Some(output) = output {
+            reporter.summary(self, output)?;
+        }
+        reporter.finish(self)
+    }
+}
+
+/// Reads from the context's stdin while holding its borrow.
+struct StdinReader<'r, 'a>(RefMut<'r, Box<dyn Read + 'a>>);
+
+impl Read for StdinReader<'_, '_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_child_token_follows_parent() {
+        let parent = CancellationToken::new();
+        let child = parent.child();
+
+        child.cancel();
+        assert!(!parent.is_cancelled());
+
+        let other = parent.child();
+        parent.cancel();
+        assert!(other.is_cancelled());
+    }
+
+    #[test]
+    fn test_emit_streams_ndjson_and_buffers_otherwise() {
+        let app = App::new();
+        let matches = clap::ArgMatches::default();
+
+        let mut streamed = Vec::new();
+        let ctx = Context::new(&app, &matches)
+            .stdout(&mut streamed)
+            .renderer(Renderer::new(OutputFormat::Ndjson));
+        ctx.emit(&serde_json::json!({"id": 1})).unwrap();
+        ctx.emit(&serde_json::json!({"id": 2})).unwrap();
+        ctx.finish(None).unwrap();
+        drop(ctx);
+        assert_eq!(
+            "{\"id\":1}\n{\"id\":2}\n",
+            String::from_utf8(streamed).unwrap()
+        );
+
+        let mut buffered = Vec::new();
+        let ctx = Context::new(&app, &matches)
+            .stdout(&mut buffered)
+            .renderer(Renderer::new(OutputFormat::Csv));
+        ctx.emit(&serde_json::json!({"id": 1})).unwrap();
+        ctx.emit(&serde_json::json!({"id": 2})).unwrap();
+        ctx.finish(None).unwrap();
+        drop(ctx);
+        assert_eq!("id\n1\n2\n", String::from_utf8(buffered).unwrap());
+    }
+
+    #[test]
+    fn test_effect_is_skipped_in_dry_run() {
+        let app = App::new();
+        let matches = clap::ArgMatches::default();
+        let mut stderr = Vec::new();
+        let ctx = Context::new(&app, &matches)
+            .stderr(&mut stderr)
+            .dry_run(true);
+
+        let ran = ctx.effect("delete everything", || Ok(true)).unwrap();
+        drop(ctx);
+
+        assert_eq!(None, ran);
+        assert_eq!(
+            "Would delete everything\n",
+            String::from_utf8(stderr).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_to_string_reads_stdin_for_dash() {
+        let app = App::new();
+        let matches = clap::ArgMatches::default();
+        let ctx = Context::new(&app, &matches).stdin(&b"piped data"[..]);
+
+        assert_eq!(
+            "piped data",
+            ctx.read_to_string(&"-".parse().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_seed_makes_rng_reproducible() {
+        let app = App::new();
+        let matches = clap::ArgMatches::default();
+        let draw = |ctx: Context| -> Vec<u32> { (0..4).map(|_| ctx.rng().u32(..)).collect() };
+
+        assert_eq!(
+            draw(Context::new(&app, &matches).seed(7)),
+            draw(Context::new(&app, &matches).seed(7))
+        );
+        assert_ne!(
+            draw(Context::new(&app, &matches).seed(7)),
+            draw(Context::new(&app, &matches).seed(8))
+        );
+    }
+}
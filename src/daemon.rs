@@ -0,0 +1,311 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::identity::IDENTITY;
+use crate::{Error, Result};
+
+/// How long `daemon stop` waits for the daemon to exit: its grace period
+/// for cleaning up, and a little more.
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(6);
+
+/// How long `daemon start` waits for a detached daemon to answer.
+pub const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns the file holding the pid of the running daemon.
+pub fn pidfile_path() -> PathBuf {
+    crate::paths::runtime_dir().join("daemon.pid")
+}
+
+/// Returns the file a detached daemon's logs are written to, in the
+/// [state directory](crate::paths::state_dir).
+pub fn log_path() -> PathBuf {
+    crate::paths::state_dir().join("daemon.log")
+}
+
+/// Reads the pid recorded in `path`, if it names a process that is still running.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if the directory of `path` is not private,
+/// as another user could have recorded any pid in it.
+pub fn running(path: &Path) -> Result<Option<u32>> {
+    if let Some(dir) = path.parent().filter(|dir| dir.exists()) {
+        crate::paths::check_private_dir(dir)?;
+    }
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::io(path)(e)),
+    };
+    Ok(content.trim().parse().ok().filter(|pid| is_alive(*pid)))
+}
+
+/// Tells the service manager about the daemon's `state`, e.g. `READY=1`, if
+/// it runs under systemd as `Type=notify`.
+#[cfg(unix)]
+pub fn notify(state: &str) -> Result<()> {
+    crate::systemd::notify(state).map(|_| ())
+}
+
+#[cfg(not(unix))]
+pub fn notify(_state: &str) -> Result<()> {
+    Ok(())
+}
+
+/// Returns how often to tell the service manager the daemon is alive, if it expects to be told.
+#[cfg(unix)]
+pub fn watchdog_interval() -> Option<Duration> {
+    crate::systemd::watchdog_interval()
+}
+
+#[cfg(not(unix))]
+pub fn watchdog_interval() -> Option<Duration> {
+    None
+}
+
+/// Records the current process in a pidfile for as long as it is alive,
+/// removing the file when dropped.
+#[derive(Debug)]
+pub struct Pidfile {
+    path: PathBuf,
+}
+
+impl Pidfile {
+    /// Writes the current pid to `path`, which must not exist unless it was
+    /// left behind by a process that is gone.
+    ///
+    /// The file is linked into place with its pid already written, so that
+    /// of two daemons starting at once only one succeeds, and neither sees
+    /// the other's pidfile without its pid and takes it for a stale one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Subcommand`] if `path` names another running process.
+    pub fn create(path: PathBuf) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            crate::paths::create_private_dir(dir)?;
+        }
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}.tmp", std::process::id()));
+        let temporary = path.with_file_name(name);
+        std::fs::write(&temporary, format!("{}\n", std::process::id()))
+            .map_err(Error::io(&temporary))?;
+        let linked = loop {
+            match std::fs::hard_link(&temporary, &path) {
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => match running(&path) {
+                    Ok(Some(pid)) => {
+                        break Err(Error::Subcommand(format!(
+                            "the daemon is already running (pid {})",
+                            pid
+                        )))
+                    }
+                    // Left behind by a daemon that died.
+                    Ok(None) => match std::fs::remove_file(&path) {
+                        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                            break Err(Error::io(&path)(e))
+                        }
+                        _ => {}
+                    },
+                    Err(e) => break Err(e),
+                },
+                linked => break linked.map_err(Error::io(&path)),
+            }
+        };
+        let _ = std::fs::remove_file(&temporary);
+        linked.map(|()| Pidfile { path })
+    }
+}
+
+impl Drop for Pidfile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Runs the current executable again with `args` and `env`, in a process
+/// group of its own so it outlives the terminal, and with output appended to
+/// `log`.
+#[cfg(unix)]
+pub fn spawn_detached(
+    args: &[&str],
+    env: &[(String, String)],
+    log: &Path,
+) -> Result<std::process::Child> {
+    use std::os::unix::process::CommandExt;
+
+    if let Some(dir) = log.parent() {
+        std::fs::create_dir_all(dir).map_err(Error::io(dir))?;
+    }
+    let open = || {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log)
+            .map_err(Error::io(log))
+    };
+    let exe = std::env::current_exe().map_err(Error::Output)?;
+    std::process::Command::new(&exe)
+        .args(args)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(std::process::Stdio::null())
+        .stdout(open()?)
+        .stderr(open()?)
+        .process_group(0)
+        .spawn()
+        .map_err(Error::io(&exe))
+}
+
+#[cfg(not(unix))]
+pub fn spawn_detached(
+    _args: &[&str],
+    _env: &[(String, String)],
+    _log: &Path,
+) -> Result<std::process::Child> {
+    Err(Error::Subcommand(
+        "detaching is only supported on Unix; pass --foreground".to_string(),
+    ))
+}
+
+/// Waits up to `timeout` for the detached daemon `child` to answer on its
+/// control socket, which it opens after taking the pidfile.
+pub fn wait_started(child: &mut std::process::Child, timeout: Duration) -> Result<()> {
+    let socket = crate::ipc::socket_path();
+    let started = std::time::Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().map_err(Error::Output)? {
+            return Err(Error::Subcommand(format!(
+                "the daemon exited ({}) before it started",
+                status
+            )));
+        }
+        if crate::ipc::request(&socket, &crate::ipc::Request::Status).is_ok() {
+            return Ok(());
+        }
+        if started.elapsed() > timeout {
+            return Err(Error::Subcommand(format!(
+                "the daemon (pid {}) did not start within {}",
+                child.id(),
+                humantime::format_duration(timeout)
+            )));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Asks the process `pid` to stop with SIGTERM and waits up to `timeout` for it to exit.
+#[cfg(unix)]
+pub fn stop(pid: u32, timeout: Duration) -> Result<()> {
+    // SAFETY: kill has no memory-safety preconditions.
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+        return Err(Error::Output(std::io::Error::last_os_error()));
+    }
+    let started = std::time::Instant::now();
+    while is_alive(pid) {
+        if started.elapsed() > timeout {
+            return Err(Error::Subcommand(format!(
+                "the daemon (pid {}) did not stop within {}",
+                pid,
+                humantime::format_duration(timeout)
+            )));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn stop(_pid: u32, _timeout: Duration) -> Result<()> {
+    Err(Error::Subcommand(
+        "stopping the daemon is only supported on Unix".to_string(),
+    ))
+}
+
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists.
+    let exists = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0;
+    exists || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without Unix signals a recorded process is assumed to be running.
+#[cfg(not(unix))]
+fn is_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Renders a launchd property list running `program` with `args` at login,
+/// restarted whenever it exits, with output appended to `log`.
+pub fn launchd_plist(program: &Path, args: &[&str], log: &Path) -> String {
+    let arguments: String = std::iter::once(program.display().to_string())
+        .chain(args.iter().map(|arg| arg.to_string()))
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+        .collect();
+    let log = xml_escape(&log.display().to_string());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = IDENTITY.reverse_dns(),
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pidfile_is_removed_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("daemon.pid");
+
+        let pidfile = Pidfile::create(path.clone()).unwrap();
+        assert_eq!(Some(std::process::id()), running(&path).unwrap());
+        assert!(Pidfile::create(path.clone()).is_err());
+
+        drop(pidfile);
+        assert!(!path.exists());
+        assert_eq!(None, running(&path).unwrap());
+
+        // One left behind by a daemon that died is replaced.
+        std::fs::write(&path, format!("{}\n", i32::MAX)).unwrap();
+        let _pidfile = Pidfile::create(path.clone()).unwrap();
+        assert_eq!(Some(std::process::id()), running(&path).unwrap());
+    }
+
+    #[test]
+    fn test_launchd_plist_lists_program_and_arguments() {
+        let plist = launchd_plist(
+            Path::new("/usr/local/bin/fixme"),
+            &["daemon", "start", "--foreground"],
+            Path::new("/tmp/R&D/daemon.log"),
+        );
+
+        assert!(plist
+            .contains("<string>/usr/local/bin/fixme</string>\n        <string>daemon</string>"));
+        assert!(plist.contains("<string>--foreground</string>\n    </array>"));
+        assert!(plist.contains("<string>/tmp/R&amp;D/daemon.log</string>"));
+    }
+}
@@ -0,0 +1,336 @@
+use std::path::{Path, PathBuf};
+
+use miette::{NamedSource, SourceSpan};
+
+/// Errors produced by the application and its subcommands.
+///
+/// Every variant has a stable code (see [`Error::code`]) documented in
+/// [`EXPLANATIONS`] and shown by the `explain` subcommand.
+#[derive(Debug, thiserror::Error, miette::Diagnostic)]
+pub enum Error {
+    /// The command line could not be parsed.
+    #[error(transparent)]
+    Usage(#[from] clap::Error),
+
+    /// A configuration file exists but is not valid.
+    #[error("failed to parse config file '{}'", path.display())]
+    #[diagnostic(
+        code(E002),
+        help("fix the syntax error or move the file aside to use the defaults")
+    )]
+    ConfigParse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+        #[source_code]
+        src: NamedSource<String>,
+        #[label("{source}")]
+        span: SourceSpan,
+    },
+
+    /// A key is not part of the settings schema.
+    #[error("unknown key '{key}'{}", did_you_mean(suggestions))]
+    UnknownKey {
+        key: String,
+        suggestions: Vec<String>,
+    },
+
+    /// A failure that may succeed if tried again, such as a network hiccup;
+    /// see [`Error::transient`].
+    #[error(transparent)]
+    Transient(Box<Error>),
+
+    /// Some items of a `--batch` run failed; each was reported as it failed.
+    #[error("{failed} of {total} batch item(s) failed")]
+    Batch { failed: usize, total: usize },
+
+    /// The run was interrupted, e.g. with Ctrl-C, and stopped early.
+    #[error("interrupted")]
+    Interrupted,
+
+    /// The subcommand ran longer than `--timeout` allowed.
+    #[error("timed out after {}", humantime::format_duration(*limit))]
+    Timeout { limit: std::time::Duration },
+
+    /// A setting was given a value it does not accept.
+    #[error("invalid value for '{key}': {reason}")]
+    InvalidValue { key: String, reason: String },
+
+    /// A required setting was not provided by any source.
+    #[error("missing required setting '{key}'")]
+    MissingKey { key: String },
+
+    /// A file or directory could not be read or written.
+    #[error("failed to access '{}': {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Output could not be written to stdout or stderr.
+    #[error("failed to write output: {0}")]
+    Output(#[from] std::io::Error),
+
+    /// A subcommand failed for a reason specific to it.
+    #[error("{0}")]
+    Subcommand(String),
+
+    /// The user had to be asked something, but prompting was not possible.
+    #[error(
+        "cannot ask '{prompt}' without a terminal; pass --yes or provide the value as an argument"
+    )]
+    NoInput { prompt: String },
+}
+
+impl Error {
+    /// Returns the stable code identifying the kind of error, e.g. `E005`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Transient(error) => error.code(),
+            Error::Usage(_) => "E001",
+            Error::ConfigParse { .. } => "E002",
+            Error::UnknownKey { .. } => "E003",
+            Error::MissingKey { .. } => "E004",
+            Error::Io { .. } => "E005",
+            Error::Output(_) => "E006",
+            Error::Subcommand(_) => "E007",
+            Error::NoInput { .. } => "E008",
+            Error::InvalidValue { .. } => "E009",
+            Error::Timeout { .. } => "E010",
+            Error::Batch { .. } => "E011",
+            Error::Interrupted => "E012",
+        }
+    }
+
+    /// Marks the error as transient, so that `--retries` runs the subcommand again.
+    pub fn transient(self) -> Error {
+        match self {
+            Error::Transient(_) => self,
+            error => Error::Transient(Box::new(error)),
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Error::Transient(_))
+    }
+
+    /// Whether output could not be written because its reader went away,
+    /// e.g. `head` once it has read enough, which ends the run quietly.
+    pub fn is_broken_pipe(&self) -> bool {
+        match self {
+            Error::Transient(error) => error.is_broken_pipe(),
+            Error::Output(source) => source.kind() == std::io::ErrorKind::BrokenPipe,
+            _ => false,
+        }
+    }
+
+    /// Removes the [transient](Error::transient) mark, returning the underlying error.
+    pub fn into_inner(self) -> Error {
+        match self {
+            Error::Transient(error) => error.into_inner(),
+            error => error,
+        }
+    }
+
+    /// Builds a [`Error::ConfigParse`] pointing at the location `source` reports in `content`.
+    pub fn config_parse<P: AsRef<Path>>(
+        path: P,
+        content: String,
+        source: serde_json::Error,
+    ) -> Error {
+        let path = path.as_ref().to_path_buf();
+        let offset = offset_of(&content, source.line(), source.column());
+        Error::ConfigParse {
+            src: NamedSource::new(path.display().to_string(), content),
+            span: offset.into(),
+            path,
+            source,
+        }
+    }
+
+    /// Returns a closure wrapping an [`std::io::Error`] with the `path` it concerns,
+    /// for use with `map_err`.
+    pub fn io<P: AsRef<Path>>(path: P) -> impl FnOnce(std::io::Error) -> Error {
+        let path = path.as_ref().to_path_buf();
+        move |source| Error::Io { path, source }
+    }
+}
+
+/// The long-form description of an error code.
+pub struct Explanation {
+    pub code: &'static str,
+    pub summary: &'static str,
+    pub description: &'static str,
+}
+
+/// Explanations for every code returned by [`Error::code`].
+pub const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E001",
+        summary: "invalid command line",
+        description: "The arguments did not match what the command accepts: an option or \
+subcommand was misspelled, a required argument was missing, or a value was rejected.
+
+Run the command again with --help to see the accepted arguments.",
+    },
+    Explanation {
+        code: "E002",
+        summary: "config file could not be parsed",
+        description: "The user's JSON config file exists but is not valid JSON, so none of \
+its settings can be applied. The error shows the line and column of the problem.
+
+Fix the syntax error, or move the file aside to fall back to the defaults.",
+    },
+    Explanation {
+        code: "E003",
+        summary: "unknown setting",
+        description: "A setting was named, on the command line or in the config file, that \
+the application does not know. This is usually a typo; similarly named settings are \
+suggested when there are any.
+
+Correct the name, or remove the setting from the config file.",
+    },
+    Explanation {
+        code: "E004",
+        summary: "setting has no value",
+        description: concat!(
+            "A setting was required but none of the sources provided it: not the \
+command line, the ",
+            env!("FIXME_BUILD_APP_ENV_PREFIX"),
+            " environment variables, nor the config file, and it has no default.
+
+Provide the setting with `config set`, an environment variable, or a command-line option."
+        ),
+    },
+    Explanation {
+        code: "E005",
+        summary: "file could not be accessed",
+        description: "A file or directory could not be read, written or created. The error \
+names the path and the reason reported by the operating system.
+
+Check that the path exists and that you have permission to access it.",
+    },
+    Explanation {
+        code: "E006",
+        summary: "output could not be written",
+        description: "Writing to standard output or standard error failed, for example \
+because the disk is full or the reading end of a pipe was closed.
+
+Check where the output is being redirected to.",
+    },
+    Explanation {
+        code: "E007",
+        summary: "subcommand failed",
+        description: "The subcommand could not complete; the message explains why.
+
+Address the reason given in the message and run the command again.",
+    },
+    Explanation {
+        code: "E008",
+        summary: "input required",
+        description: "The command needed to ask a question, such as a confirmation, but \
+could not: --no-input was given, or standard input is not a terminal.
+
+Pass --yes to accept the defaults, or supply the value as an argument or setting.",
+    },
+    Explanation {
+        code: "E009",
+        summary: "invalid setting value",
+        description: "A setting was given a value it does not accept, such as a verbosity \
+level that does not exist. The error lists what the setting accepts.
+
+Run `config set` again with one of the accepted values.",
+    },
+    Explanation {
+        code: "E010",
+        summary: "timed out",
+        description: "The subcommand ran longer than the limit given with --timeout and was \
+cancelled. Any work it had not finished was abandoned.
+
+Run the command again with a longer --timeout, or without one.",
+    },
+    Explanation {
+        code: "E011",
+        summary: "batch items failed",
+        description: "With --batch the subcommand ran once per input line, and at least \
+one of those runs failed. The error of each failed item was printed, prefixed with the \
+line, before the summary.
+
+Fix or remove the failing lines and run the batch again.",
+    },
+    Explanation {
+        code: "E012",
+        summary: "interrupted",
+        description: "The run received an interrupt, such as Ctrl-C or SIGTERM, and stopped \
+before completing. The subcommand was given a few seconds to clean up; a second interrupt \
+ends the process at once.
+
+Run the command again to complete the work.",
+    },
+];
+
+/// Returns the explanation for `code`, ignoring case.
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS
+        .iter()
+        .find(|e| e.code.eq_ignore_ascii_case(code))
+}
+
+/// Formats `suggestions` as a `, did you mean ...?` suffix, or nothing if empty.
+fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions {
+        [] => String::new(),
+        [only] => format!(", did you mean '{}'?", only),
+        _ => format!(
+            ", did you mean one of {}?",
+            suggestions
+                .iter()
+                .map(|s| format!("'{}'", s))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Converts a 1-based `line` and `column` into a byte offset within `content`.
+fn offset_of(content: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = content
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    (line_start + column.saturating_sub(1)).min(content.len())
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_parse_points_at_error() {
+        let content = "{\n  \"verbosity\": debug\n}".to_string();
+        let source = serde_json::from_str::<serde_json::Value>(&content).unwrap_err();
+
+        match Error::config_parse("config.json", content, source) {
+            Error::ConfigParse { span, .. } => assert_eq!(17, span.offset()),
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_every_code_is_explained() {
+        let errors = [
+            Error::Subcommand(String::new()),
+            Error::Output(std::io::ErrorKind::Other.into()),
+            Error::MissingKey { key: String::new() },
+        ];
+        for error in errors {
+            assert!(explain(error.code()).is_some());
+        }
+        assert_eq!(12, EXPLANATIONS.len());
+        assert_eq!("E004", explain("e004").unwrap().code);
+    }
+}
@@ -0,0 +1,242 @@
+//! What a run of a subcommand reports, as typed [`Event`]s published on a
+//! [`Bus`] and presented by the [`Subscriber`]s on it, so that subcommands
+//! say what happened and never how it is shown.
+
+use std::io::Write;
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::{i18n, Output, Renderer, Result};
+
+/// Something that happened during the run of a subcommand.
+#[derive(Debug)]
+pub enum Event<'e> {
+    /// The subcommand is about to run.
+    Started { subcommand: &'e str },
+    /// The subcommand did a step of its work, e.g. `Installed 'lint'`.
+    Progress { message: &'e str },
+    /// Something went wrong that does not fail the subcommand.
+    Warning { message: &'e str },
+    /// The result of the subcommand, rendered as the user asked.
+    Result { output: &'e Output },
+    /// The subcommand has finished, successfully or not.
+    Finished {
+        subcommand: &'e str,
+        duration: Duration,
+        success: bool,
+    },
+}
+
+impl Event<'_> {
+    /// The event as a JSON object whose `event` field names its kind.
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        match self {
+            Event::Started { subcommand } => json!({"event": "started", "subcommand": subcommand}),
+            Event::Progress { message } => json!({"event": "progress", "message": message}),
+            Event::Warning { message } => json!({"event": "warning", "message": message}),
+            Event::Result { output } => json!({"event": "result", "value": output.value()}),
+            Event::Finished {
+                subcommand,
+                duration,
+                success,
+            } => json!({
+                "event": "finished",
+                "subcommand": subcommand,
+                "seconds": duration.as_secs_f64(),
+                "success": success,
+            }),
+        }
+    }
+}
+
+/// Where a [`Subscriber`] presents events: the subcommand's streams and the
+/// output options of the run.
+pub struct Sink<'s> {
+    pub out: &'s mut dyn Write,
+    pub err: &'s mut dyn Write,
+    pub renderer: &'s Renderer,
+}
+
+/// Presents the events of a run, e.g. as text for people.
+pub trait Subscriber {
+    /// Presents `event` on `sink`.
+    fn notify(&mut self, event: &Event, sink: &mut Sink) -> Result<()>;
+}
+
+/// Hands every event published on it to its subscribers, in the order they
+/// subscribed.
+#[derive(Default)]
+pub struct Bus {
+    subscribers: Vec<Box<dyn Subscriber>>,
+}
+
+impl Bus {
+    /// Creates a bus without subscribers, on which events go unreported.
+    pub fn new() -> Self {
+        Bus::default()
+    }
+
+    /// Creates a bus with the subscriber that presents `report`.
+    pub fn reporting(report: Report) -> Self {
+        match report {
+            Report::Human => Bus::new().subscribe(Human),
+            Report::Json => Bus::new().subscribe(Json),
+            Report::Quiet => Bus::new().subscribe(Quiet),
+            Report::Log => Bus::new().subscribe(Log),
+        }
+    }
+
+    /// Adds `subscriber` after those already on the bus.
+    pub fn subscribe<S: Subscriber + 'static>(mut self, subscriber: S) -> Self {
+        self.subscribers.push(Box::new(subscriber));
+        self
+    }
+
+    /// Hands `event` to every subscriber.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of a subscriber; those after it are not told.
+    pub fn publish(&mut self, event: &Event, sink: &mut Sink) -> Result<()> {
+        for subscriber in &mut self.subscribers {
+            subscriber.notify(event, sink)?;
+        }
+        Ok(())
+    }
+}
+
+/// How a run reports what happens, chosen with `--report`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Report {
+    /// Results on stdout, as chosen with --output, and messages on stderr.
+    #[default]
+    Human,
+    /// Every event, results included, as a line of JSON on stdout; not
+    /// combined with --output or --format.
+    Json,
+    /// Only results.
+    Quiet,
+    /// Results only, and every other event to the log.
+    Log,
+}
+
+/// Renders results to stdout and writes progress and warnings to stderr.
+pub struct Human;
+
+impl Subscriber for Human {
+    fn notify(&mut self, event: &Event, sink: &mut Sink) -> Result<()> {
+        match event {
+            Event::Progress { message } => writeln!(sink.err, "{}", message)?,
+            Event::Warning { message } => {
+                writeln!(sink.err, "{}: {}", i18n::t("warning-label"), message)?
+            }
+            Event::Result { output } => sink.renderer.render(output, sink.out)?,
+            Event::Started { .. } | Event::Finished { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// Writes every event to stdout as a line of JSON, for programs following
+/// a run as it goes.
+pub struct Json;
+
+impl Subscriber for Json {
+    fn notify(&mut self, event: &Event, sink: &mut Sink) -> Result<()> {
+        crate::render::write_line(&event.to_json(), sink.out)
+    }
+}
+
+/// Renders results and nothing else.
+pub struct Quiet;
+
+impl Subscriber for Quiet {
+    fn notify(&mut self, event: &Event, sink: &mut Sink) -> Result<()> {
+        match event {
+            Event::Result { output } => sink.renderer.render(output, sink.out),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Renders results, and logs the other events, warnings at warn level and
+/// the rest at info level.
+pub struct Log;
+
+impl Subscriber for Log {
+    fn notify(&mut self, event: &Event, sink: &mut Sink) -> Result<()> {
+        match event {
+            Event::Result { output } => return sink.renderer.render(output, sink.out),
+            Event::Warning { message } => warn!("{}", message),
+            event => info!("{}", event.to_json()),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publish(report: Report, events: &[Event]) -> (String, String) {
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let renderer = Renderer::new(crate::OutputFormat::Json);
+        let mut bus = Bus::reporting(report);
+        for event in events {
+            let mut sink = Sink {
+                out: &mut out,
+                err: &mut err,
+                renderer: &renderer,
+            };
+            bus.publish(event, &mut sink).unwrap();
+        }
+        (
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_reports_present_the_same_events_differently() {
+        let output = Output::new(&1);
+        let events = [
+            Event::Started {
+                subcommand: "fixme1",
+            },
+            Event::Warning { message: "careful" },
+            Event::Result { output: &output },
+            Event::Finished {
+                subcommand: "fixme1",
+                duration: Duration::ZERO,
+                success: true,
+            },
+        ];
+
+        assert_eq!(
+            ("1\n".to_string(), "Warning: careful\n".to_string()),
+            publish(Report::Human, &events)
+        );
+        assert_eq!(
+            ("1\n".to_string(), String::new()),
+            publish(Report::Quiet, &events)
+        );
+        let (json, _) = publish(Report::Json, &events);
+        let kinds: Vec<String> = json
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()["event"].to_string()
+            })
+            .collect();
+        assert_eq!(
+            vec!["\"started\"", "\"warning\"", "\"result\"", "\"finished\""],
+            kinds
+        );
+        assert_eq!(
+            ("1\n".to_string(), String::new()),
+            publish(Report::Log, &events)
+        );
+    }
+}
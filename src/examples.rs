@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+
+use clap::builder::styling::Styles;
+use roff::{bold, roman, Roff};
+
+/// Stands for the program's name in examples, e.g. `FIXME fixme1 foo`, until
+/// they are registered with the application.
+pub const PROGRAM: &str = "FIXME";
+
+/// A usage example shown in `--help` and in the generated man pages.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Example {
+    /// The full command line, e.g. `fixme fixme1 foo`.
+    pub command: String,
+    /// What running the command line does.
+    pub description: String,
+}
+
+impl Example {
+    pub fn new<C: Into<String>, D: Into<String>>(command: C, description: D) -> Self {
+        Example {
+            command: command.into(),
+            description: description.into(),
+        }
+    }
+
+    /// Replaces [`PROGRAM`] with `name` in the command line and description.
+    pub fn named(self, name: &str) -> Self {
+        Example {
+            command: self.command.replace(PROGRAM, name),
+            description: self.description.replace(PROGRAM, name),
+        }
+    }
+}
+
+/// Usage examples keyed by subcommand path (e.g. `"completions install"`).
+///
+/// The root command is registered under the empty path.
+#[derive(Default)]
+pub struct Registry {
+    examples: BTreeMap<String, Vec<Example>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Registers `examples` for the subcommand at `path`, after any already registered.
+    pub fn register<P: Into<String>>(&mut self, path: P, examples: Vec<Example>) -> &mut Self {
+        self.examples
+            .entry(path.into())
+            .or_default()
+            .extend(examples);
+        self
+    }
+
+    /// Returns the examples registered for the subcommand at `path`.
+    pub fn get(&self, path: &str) -> &[Example] {
+        self.examples
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Renders the registered examples into the `after_help` of every matching command.
+    ///
+    /// The section is styled using the styles already set on `cmd`.
+    pub fn apply(&self, cmd: clap::Command) -> clap::Command {
+        let styles = cmd.get_styles().clone();
+        self.apply_at(cmd, "", &styles)
+    }
+
+    fn apply_at(&self, mut cmd: clap::Command, path: &str, styles: &Styles) -> clap::Command {
+        let examples = self.get(path);
+        if !examples.is_empty() {
+            cmd = cmd.after_help(render_help(examples, styles));
+        }
+        let names: Vec<String> = cmd
+            .get_subcommands()
+            .map(|s| s.get_name().to_string())
+            .collect();
+        for name in names {
+            let sub_path = join_path(path, &name);
+            cmd = cmd.mut_subcommand(name, |sub| self.apply_at(sub, &sub_path, styles));
+        }
+        cmd
+    }
+}
+
+/// Appends `name` to the subcommand `path`.
+pub fn join_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} {}", path, name)
+    }
+}
+
+/// Renders `examples` as an `Examples:` help section.
+pub fn render_help(examples: &[Example], styles: &Styles) -> String {
+    let header = styles.get_header();
+    let literal = styles.get_literal();
+    let mut help = format!("{}Examples:{}\n", header.render(), header.render_reset());
+    for example in examples {
+        help.push_str(&format!(
+            "  {}{}{}\n      {}\n",
+            literal.render(),
+            example.command,
+            literal.render_reset(),
+            example.description
+        ));
+    }
+    help.trim_end().to_string()
+}
+
+/// Renders `examples` as a roff `EXAMPLES` section.
+pub fn render_roff(examples: &[Example], roff: &mut Roff) {
+    roff.control("SH", ["EXAMPLES"]);
+    for example in examples {
+        roff.control("TP", []);
+        roff.text([bold(example.command.as_str())]);
+        roff.text([roman(example.description.as_str())]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_sets_after_help_on_subcommand() {
+        let mut registry = Registry::new();
+        registry.register(
+            "fixme1",
+            vec![Example::new("fixme fixme1 foo", "Runs fixme1 on foo")],
+        );
+        let cmd = clap::Command::new("fixme")
+            .styles(Styles::plain())
+            .subcommand(clap::Command::new("fixme1"));
+
+        let cmd = registry.apply(cmd);
+
+        assert!(cmd.get_after_help().is_none());
+        let sub = cmd.find_subcommand("fixme1").unwrap();
+        assert_eq!(
+            "Examples:\n  fixme fixme1 foo\n      Runs fixme1 on foo",
+            sub.get_after_help().unwrap().to_string()
+        );
+    }
+}
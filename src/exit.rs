@@ -0,0 +1,86 @@
+use crate::Error;
+
+/// Process exit statuses, loosely following BSD `sysexits(3)`, so wrapping
+/// scripts can branch on the class of failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitCode {
+    /// The subcommand completed successfully.
+    Success = 0,
+    /// The subcommand failed while running.
+    Runtime = 1,
+    /// The subcommand exceeded its `--timeout`, as with GNU `timeout(1)`.
+    Timeout = 124,
+    /// The command line was invalid (`EX_USAGE`).
+    Usage = 64,
+    /// A configuration source could not be read or parsed (`EX_CONFIG`).
+    Config = 78,
+    /// The run was interrupted, e.g. by Ctrl-C (128 + `SIGINT`).
+    Interrupted = 130,
+    /// The reader of the output went away, e.g. `head`, as if the process
+    /// had been killed by `SIGPIPE` (128 + 13).
+    BrokenPipe = 141,
+}
+
+impl ExitCode {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+impl From<&Error> for ExitCode {
+    /// Classifies `error` into the exit status the process should report.
+    fn from(error: &Error) -> Self {
+        if error.is_broken_pipe() {
+            return ExitCode::BrokenPipe;
+        }
+        match error {
+            Error::Transient(error) => ExitCode::from(&**error),
+            Error::Usage(_) | Error::NoInput { .. } => ExitCode::Usage,
+            Error::Timeout { .. } => ExitCode::Timeout,
+            Error::Interrupted => ExitCode::Interrupted,
+            Error::ConfigParse { .. }
+            | Error::UnknownKey { .. }
+            | Error::InvalidValue { .. }
+            | Error::MissingKey { .. } => ExitCode::Config,
+            Error::Io { source, .. } | Error::Output(source)
+                if source.kind() == std::io::ErrorKind::Interrupted =>
+            {
+                ExitCode::Interrupted
+            }
+            _ => ExitCode::Runtime,
+        }
+    }
+}
+
+impl From<ExitCode> for std::process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        std::process::ExitCode::from(code.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_error() {
+        let usage = clap::Command::new("fixme")
+            .try_get_matches_from(["fixme", "--bogus"])
+            .unwrap_err();
+        assert_eq!(ExitCode::Usage, ExitCode::from(&Error::Usage(usage)));
+
+        let missing = Error::MissingKey {
+            key: "verbosity".to_string(),
+        };
+        assert_eq!(ExitCode::Config, ExitCode::from(&missing));
+
+        let interrupted = Error::Output(std::io::ErrorKind::Interrupted.into());
+        assert_eq!(ExitCode::Interrupted, ExitCode::from(&interrupted));
+
+        let closed = Error::Output(std::io::ErrorKind::BrokenPipe.into()).transient();
+        assert_eq!(ExitCode::BrokenPipe, ExitCode::from(&closed));
+
+        let other = Error::io("config.json")(std::io::ErrorKind::NotFound.into());
+        assert_eq!(ExitCode::Runtime, ExitCode::from(&other));
+    }
+}
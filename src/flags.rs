@@ -0,0 +1,373 @@
+use std::ffi::OsString;
+
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches};
+use cor_args::Handler;
+
+use crate::schema::{self, Key, Relation, ValueKind};
+use crate::settings::{Layout, Origin};
+use crate::values;
+
+/// Heading of the hidden options meant for developers of the application,
+/// which only `--help-all` lists.
+pub const DEVELOPER: &str = "Developer options";
+
+/// Returns the `--<flag>` name of a boolean `key`: its name with dots
+/// replaced by dashes, e.g. `theme.bold` becomes `theme-bold`.
+pub fn flag_name(key: &Key) -> String {
+    key.name.replace('.', "-")
+}
+
+/// Builds the `--<flag>` / `--no-<flag>` pair for a boolean `key`.
+///
+/// The last of the two given on the command line wins, and either overrides
+/// the setting; see [`resolve`]. The flag's help is the key's description
+/// without the `: true or false` a setting's value is told by.
+pub fn negatable(key: &Key) -> [Arg; 2] {
+    let name = flag_name(key);
+    let negated = format!("no-{}", name);
+    let help = key.description.trim_end_matches(": true or false");
+    [
+        Arg::new(name.clone())
+            .long(name.clone())
+            .help(help)
+            .action(ArgAction::SetTrue)
+            .overrides_with(negated.clone()),
+        Arg::new(negated.clone())
+            .long(negated)
+            .help(crate::i18n::catalog().format("arg-negated", &[("flag", &name)]))
+            .action(ArgAction::SetTrue)
+            .overrides_with(name),
+    ]
+}
+
+/// Adds the negatable flag pair of every boolean key in the [`schema`] to
+/// `cmd`, as global arguments.
+pub fn add_negatable(mut cmd: clap::Command) -> clap::Command {
+    for key in schema::KEYS.iter().filter(|k| k.kind == ValueKind::Bool) {
+        for arg in negatable(key) {
+            cmd = cmd.arg(arg.global(true));
+        }
+    }
+    cmd
+}
+
+/// Makes clap enforce `relations` between the arguments of `cmd` that stand
+/// for keys: a boolean key's `--<flag>`, or an argument whose id is the key.
+///
+/// Exclusive keys become an [`ArgGroup`]; as groups only apply to the
+/// command they are declared on, the arguments also conflict with each other
+/// directly, which global arguments carry into subcommands.
+pub fn add_relations(mut cmd: clap::Command, relations: &[Relation]) -> clap::Command {
+    let id = |cmd: &clap::Command, name: &str| {
+        let key = schema::lookup(name).ok()?;
+        [flag_name(key), key.name.to_string()]
+            .into_iter()
+            .find(|id| cmd.get_arguments().any(|arg| arg.get_id() == id.as_str()))
+    };
+    for (i, relation) in relations.iter().enumerate() {
+        match *relation {
+            Relation::Exclusive(keys) => {
+                let ids: Vec<String> = keys.iter().filter_map(|key| id(&cmd, key)).collect();
+                if ids.len() < 2 {
+                    continue;
+                }
+                cmd = cmd.group(
+                    ArgGroup::new(format!("exclusive-{}", i))
+                        .args(&ids)
+                        .multiple(false),
+                );
+                for id in &ids {
+                    let others: Vec<&String> = ids.iter().filter(|other| *other != id).collect();
+                    cmd = cmd.mut_arg(id, |arg| arg.conflicts_with_all(others));
+                }
+            }
+            Relation::Requires(key, required) => {
+                if let (Some(key), Some(required)) = (id(&cmd, key), id(&cmd, required)) {
+                    cmd = cmd.mut_arg(key, |arg| arg.requires(required));
+                }
+            }
+            Relation::Conflicts(key, other) => {
+                if let (Some(key), Some(other)) = (id(&cmd, key), id(&cmd, other)) {
+                    cmd = cmd.mut_arg(key, |arg| arg.conflicts_with(other));
+                }
+            }
+        }
+    }
+    cmd
+}
+
+/// Makes the required arguments of `cmd` and its subcommands that stand for
+/// a key optional when `settings` provide that key, so that e.g. a required
+/// `--token` with the id `auth.token` may be left out when `FIXME_auth.token`
+/// or the config file sets it. Read such arguments with
+/// [`crate::Context::require`], which falls back to the settings.
+pub fn relax_configured(cmd: clap::Command, settings: &dyn Handler) -> clap::Command {
+    let configured: Vec<clap::Id> = cmd
+        .get_arguments()
+        .filter(|arg| arg.is_required_set())
+        .map(Arg::get_id)
+        .filter(|id| schema::lookup(id.as_str()).is_ok())
+        .filter(|id| settings.handle_request(id.as_str()).is_some())
+        .cloned()
+        .collect();
+    configured
+        .into_iter()
+        .fold(cmd, |cmd, id| cmd.mut_arg(id, |arg| arg.required(false)))
+        .mut_subcommands(|sub| relax_configured(sub, settings))
+}
+
+/// Notes in the help of each argument of `cmd` and its subcommands that
+/// stands for a key the value it takes when not given and where that comes
+/// from, e.g. `[default: debug, from ~/.config/fixme/config.json]`.
+///
+/// The values are those the settings of `layout` provide when the help is
+/// built, so the help shows what leaving an option out would actually do.
+pub fn annotate_defaults(cmd: clap::Command, layout: &Layout) -> clap::Command {
+    let chain = layout.chain(None);
+    annotate_with(cmd, &|name| {
+        let key = schema::lookup(name).ok().filter(|key| !key.is_pattern())?;
+        if key.secret {
+            return None;
+        }
+        let value = chain.handle_request(key.name);
+        let origin = value.as_ref().and_then(|_| layout.origin(None, key.name));
+        let value = value.or_else(|| key.default.map(str::to_string))?;
+        Some(match origin {
+            Some(Origin::Environment(name)) => format!("[default: {}, from ${}]", value, name),
+            Some(Origin::File(path)) => format!(
+                "[default: {}, from {}]",
+                value,
+                crate::paths::abbreviate(&path)
+            ),
+            _ => format!("[default: {}]", value),
+        })
+    })
+}
+
+/// Appends the note `note` returns for the key an argument stands for: its
+/// id, or the key whose `--<flag>` it is.
+fn annotate_with(cmd: clap::Command, note: &dyn Fn(&str) -> Option<String>) -> clap::Command {
+    let notes: Vec<(clap::Id, String)> = cmd
+        .get_arguments()
+        .filter_map(|arg| {
+            let id = arg.get_id().as_str();
+            let key = schema::KEYS
+                .iter()
+                .find(|key| key.kind == ValueKind::Bool && flag_name(key) == id)
+                .map_or(id, |key| key.name);
+            Some((arg.get_id().clone(), note(key)?))
+        })
+        .collect();
+    notes
+        .into_iter()
+        .fold(cmd, |cmd, (id, note)| {
+            cmd.mut_arg(id, |arg| {
+                let help = match arg.get_help() {
+                    Some(help) => format!("{} {}", help, note),
+                    None => note.clone(),
+                };
+                let arg = match arg.get_long_help().map(|long| format!("{} {}", long, note)) {
+                    Some(long) => arg.long_help(long),
+                    None => arg,
+                };
+                arg.help(help)
+            })
+        })
+        .mut_subcommands(|sub| annotate_with(sub, note))
+}
+
+/// Replaces a `--help-all` among `args`, before any `--`, with `--help`, and
+/// returns whether there was one.
+pub fn take_help_all(args: &mut [OsString]) -> bool {
+    let position = args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .position(|arg| arg == "--help-all");
+    if let Some(position) = position {
+        args[position] = OsString::from("--help");
+    }
+    position.is_some()
+}
+
+/// Shows the hidden [`DEVELOPER`] options of `cmd` and its subcommands in
+/// their help.
+pub fn reveal_developer(cmd: clap::Command) -> clap::Command {
+    cmd.mut_args(|arg| match arg.get_help_heading() == Some(DEVELOPER) {
+        true => arg.hide(false),
+        false => arg,
+    })
+    .mut_subcommands(reveal_developer)
+}
+
+/// Resolves the boolean setting `name`: `--<flag>` or `--no-<flag>` when given,
+/// else the value from `settings`, else the key's default.
+pub fn resolve(matches: &ArgMatches, settings: &dyn Handler, name: &str) -> bool {
+    let Ok(key) = schema::lookup(name) else {
+        return false;
+    };
+    let flag = flag_name(key);
+    let given = |id: &str| {
+        matches.try_get_one::<bool>(id).ok().flatten() == Some(&true)
+            && matches.value_source(id) == Some(ValueSource::CommandLine)
+    };
+    if given(&flag) {
+        return true;
+    }
+    if given(&format!("no-{}", flag)) {
+        return false;
+    }
+    configured(settings, key)
+}
+
+/// Resolves the boolean setting `name` like [`resolve`], but from the raw
+/// `args`, for what is decided before they are parsed, such as how help
+/// is styled.
+pub fn requested(args: &[OsString], settings: &dyn Handler, name: &str) -> bool {
+    let Ok(key) = schema::lookup(name) else {
+        return false;
+    };
+    let flag = format!("--{}", flag_name(key));
+    let negated = format!("--no-{}", flag_name(key));
+    args.iter()
+        .take_while(|arg| *arg != "--")
+        .fold(None, |given, arg| match arg.to_str() {
+            Some(arg) if arg == flag => Some(true),
+            Some(arg) if arg == negated => Some(false),
+            _ => given,
+        })
+        .unwrap_or_else(|| configured(settings, key))
+}
+
+/// Whether `settings`, or else its default, enable the boolean `key`.
+fn configured(settings: &dyn Handler, key: &Key) -> bool {
+    settings
+        .handle_request(key.name)
+        .or_else(|| key.default.map(str::to_string))
+        .is_some_and(|value| values::boolean(&value) == Ok(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cor_args::DefaultHandler;
+
+    #[test]
+    fn test_resolve_lets_the_command_line_override_settings() {
+        let cmd = add_negatable(clap::Command::new("fixme"));
+        let enabled = DefaultHandler::new("true");
+        let disabled = DefaultHandler::new("false");
+        let resolve_with = |args: &[&str], settings: &dyn Handler| {
+            resolve(&cmd.clone().get_matches_from(args), settings, "ascii")
+        };
+
+        assert!(resolve_with(&["fixme"], &enabled));
+        assert!(!resolve_with(&["fixme"], &disabled));
+        assert!(!resolve_with(&["fixme", "--no-ascii"], &enabled));
+        assert!(resolve_with(&["fixme", "--ascii"], &disabled));
+        assert!(!resolve_with(
+            &["fixme", "--ascii", "--no-ascii"],
+            &disabled
+        ));
+    }
+
+    #[test]
+    fn test_requested_reads_raw_args() {
+        let enabled = DefaultHandler::new("true");
+        let disabled = DefaultHandler::new("false");
+        let args = |args: &[&str]| -> Vec<OsString> { args.iter().map(OsString::from).collect() };
+
+        assert!(requested(&args(&["fixme", "--ascii"]), &disabled, "ascii"));
+        assert!(!requested(
+            &args(&["fixme", "--ascii", "--no-ascii"]),
+            &enabled,
+            "ascii"
+        ));
+        assert!(!requested(
+            &args(&["fixme", "--", "--ascii"]),
+            &disabled,
+            "ascii"
+        ));
+        assert!(requested(&args(&["fixme"]), &enabled, "ascii"));
+    }
+
+    #[test]
+    fn test_relax_configured_lets_settings_satisfy_required_args() {
+        let cmd = clap::Command::new("fixme").subcommand(
+            clap::Command::new("login").arg(Arg::new("auth.token").long("token").required(true)),
+        );
+        let configured = DefaultHandler::new("secret");
+        let unconfigured = crate::settings::JsonFileHandler::new("/nonexistent/config.json");
+
+        assert!(relax_configured(cmd.clone(), &configured)
+            .try_get_matches_from(["fixme", "login"])
+            .is_ok());
+        assert!(relax_configured(cmd, &unconfigured)
+            .try_get_matches_from(["fixme", "login"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_annotate_defaults_names_the_source() {
+        let cmd = add_negatable(clap::Command::new("fixme"))
+            .subcommand(clap::Command::new("fixme1").arg(Arg::new("verbosity").long("verbose")));
+        let help = |cmd: &clap::Command, id: &str| {
+            cmd.get_arguments()
+                .find(|arg| arg.get_id() == id)
+                .and_then(|arg| arg.get_help())
+                .map(|help| help.to_string())
+                .unwrap_or_default()
+        };
+
+        let cmd = annotate_with(cmd, &|key| {
+            schema::lookup(key)
+                .ok()
+                .map(|key| format!("[{}]", key.name))
+        });
+        assert!(help(&cmd, "update-check").ends_with(" [update.check]"));
+        assert!(!help(&cmd, "no-update-check").contains('['));
+        assert_eq!(
+            "[verbosity]",
+            help(cmd.find_subcommand("fixme1").unwrap(), "verbosity")
+        );
+    }
+
+    #[test]
+    fn test_add_relations_makes_clap_enforce_them() {
+        let relations = [Relation::Exclusive(&["ascii", "telemetry"])];
+        let cmd = add_relations(add_negatable(clap::Command::new("fixme")), &relations)
+            .subcommand(clap::Command::new("fixme1"));
+        let parse = |args: &[&str]| cmd.clone().try_get_matches_from(args).map(drop);
+
+        assert!(parse(&["fixme", "--ascii", "--update-check"]).is_ok());
+        assert!(parse(&["fixme", "--ascii", "--telemetry"]).is_err());
+        assert!(parse(&["fixme", "fixme1", "--ascii", "--telemetry"]).is_err());
+    }
+
+    #[test]
+    fn test_help_all_reveals_developer_options() {
+        let cmd = clap::Command::new("fixme").subcommand(
+            clap::Command::new("fixme1").arg(
+                Arg::new("trace")
+                    .long("trace")
+                    .action(ArgAction::SetTrue)
+                    .hide(true)
+                    .help_heading(DEVELOPER),
+            ),
+        );
+        let help = |cmd: clap::Command| {
+            let mut cmd = cmd;
+            cmd.build();
+            let mut sub = cmd.find_subcommand("fixme1").unwrap().clone();
+            sub.render_long_help().to_string()
+        };
+        let mut args: Vec<OsString> = ["fixme", "fixme1", "--help-all", "--", "--help-all"]
+            .map(OsString::from)
+            .into();
+
+        assert!(take_help_all(&mut args));
+        assert_eq!(["--help", "--", "--help-all"], args[2..]);
+        assert!(!help(cmd.clone()).contains("--trace"));
+        assert!(help(reveal_developer(cmd)).contains("--trace"));
+    }
+}
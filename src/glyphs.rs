@@ -0,0 +1,57 @@
+/// The non-alphanumeric symbols drawn in output and progress rendering.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Marks text cut short to fit, e.g. in table cells.
+    pub ellipsis: &'static str,
+    /// Spinner animation frames, followed by the frame shown once finished.
+    pub spinner: &'static [&'static str],
+    /// Progress bar fill: the done, current and remaining characters.
+    pub bar: &'static str,
+    /// Whether spinners and progress bars are drawn at all; screen readers
+    /// would read out every redraw.
+    pub animated: bool,
+}
+
+/// Unicode glyphs, used by default.
+pub const UNICODE: Glyphs = Glyphs {
+    ellipsis: "…",
+    spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏", " "],
+    bar: "█▉ ",
+    animated: true,
+};
+
+/// Plain ASCII replacements, for legacy terminals and log files.
+pub const ASCII: Glyphs = Glyphs {
+    ellipsis: "...",
+    spinner: &["|", "/", "-", "\\", " "],
+    bar: "=> ",
+    animated: true,
+};
+
+/// Plain ASCII without spinners or progress bars, for screen readers.
+pub const ACCESSIBLE: Glyphs = Glyphs {
+    animated: false,
+    ..ASCII
+};
+
+/// Returns [`ASCII`] if `ascii` is set, [`UNICODE`] otherwise.
+pub fn select(ascii: bool) -> &'static Glyphs {
+    if ascii {
+        &ASCII
+    } else {
+        &UNICODE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_glyphs_are_ascii() {
+        assert!(ASCII.ellipsis.is_ascii());
+        assert!(ASCII.spinner.iter().all(|frame| frame.is_ascii()));
+        assert!(ASCII.bar.is_ascii());
+        assert_eq!(&ASCII, select(true));
+    }
+}
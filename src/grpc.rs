@@ -0,0 +1,211 @@
+//! The daemon's gRPC control interface, generated from `proto/control.proto`.
+//!
+//! The server runs on a thread of its own and hands each call to the daemon
+//! through [`Server::pending`], so calls are answered from the daemon's
+//! main loop like those on the [control socket](crate::ipc). With a token,
+//! calls without it are refused before they reach the daemon.
+
+use std::net::SocketAddr;
+use std::sync::mpsc;
+
+use tokio::sync::oneshot;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status};
+
+use crate::{Error, Result};
+
+pub mod proto {
+    tonic::include_proto!("fixme.control.v1");
+}
+
+use proto::control_server::{Control, ControlServer};
+use proto::{
+    ReloadReply, ReloadRequest, RunCommandReply, RunCommandRequest, StatusReply, StatusRequest,
+};
+
+/// Where the answer to a [`Call`] goes.
+pub type Reply<T> = oneshot::Sender<std::result::Result<T, Status>>;
+
+/// A call for the daemon to answer.
+#[derive(Debug)]
+pub enum Call {
+    Status(Reply<StatusReply>),
+    Reload(Reply<ReloadReply>),
+    RunCommand(Vec<String>, Reply<RunCommandReply>),
+}
+
+/// Forwards calls to the daemon and waits for its answers.
+struct Forwarder {
+    calls: mpsc::Sender<Call>,
+}
+
+impl Forwarder {
+    async fn forward<T>(
+        &self,
+        call: impl FnOnce(Reply<T>) -> Call,
+    ) -> std::result::Result<Response<T>, Status> {
+        let stopping = || Status::unavailable("the daemon is stopping");
+        let (reply, answer) = oneshot::channel();
+        self.calls.send(call(reply)).map_err(|_| stopping())?;
+        answer.await.map_err(|_| stopping())?.map(Response::new)
+    }
+}
+
+#[tonic::async_trait]
+impl Control for Forwarder {
+    async fn status(
+        &self,
+        _request: Request<StatusRequest>,
+    ) -> std::result::Result<Response<StatusReply>, Status> {
+        self.forward(Call::Status).await
+    }
+
+    async fn reload(
+        &self,
+        _request: Request<ReloadRequest>,
+    ) -> std::result::Result<Response<ReloadReply>, Status> {
+        self.forward(Call::Reload).await
+    }
+
+    async fn run_command(
+        &self,
+        request: Request<RunCommandRequest>,
+    ) -> std::result::Result<Response<RunCommandReply>, Status> {
+        let args = request.into_inner().args;
+        self.forward(|reply| Call::RunCommand(args, reply)).await
+    }
+}
+
+/// The gRPC server, stopped when dropped.
+pub struct Server {
+    address: SocketAddr,
+    calls: mpsc::Receiver<Call>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Server {
+    /// Starts serving on `address`, port 0 picking a free port, to clients
+    /// sending `token`, if any, as `authorization: Bearer <token>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Subcommand`] if `address` cannot be listened on.
+    pub fn start(address: SocketAddr, token: Option<String>) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(Error::Output)?;
+        let cannot_listen =
+            |e: std::io::Error| Error::Subcommand(format!("cannot listen on {}: {}", address, e));
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind(address))
+            .map_err(cannot_listen)?;
+        let address = listener.local_addr().map_err(cannot_listen)?;
+        let (forward, calls) = mpsc::channel();
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let expected = token.map(|token| format!("Bearer {}", token));
+        let authenticate = move |request: Request<()>| {
+            let sent = request
+                .metadata()
+                .get("authorization")
+                .and_then(|value| value.to_str().ok());
+            match &expected {
+                Some(expected) if sent != Some(expected.as_str()) => {
+                    Err(Status::unauthenticated("a valid token is required"))
+                }
+                _ => Ok(request),
+            }
+        };
+        let thread = std::thread::spawn(move || {
+            let served = runtime.block_on(
+                tonic::transport::Server::builder()
+                    .add_service(ControlServer::with_interceptor(
+                        Forwarder { calls: forward },
+                        authenticate,
+                    ))
+                    .serve_with_incoming_shutdown(TcpIncoming::from(listener), async {
+                        let _ = stopped.await;
+                    }),
+            );
+            if let Err(e) = served {
+                log::error!("the gRPC server failed: {}", e);
+            }
+        });
+        log::info!("serving gRPC on {}", address);
+        Ok(Server {
+            address,
+            calls,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns the calls waiting for an answer, without waiting for more.
+    pub fn pending(&self) -> Vec<Call> {
+        self.calls.try_iter().collect()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proto::control_client::ControlClient;
+    use super::*;
+
+    #[test]
+    fn test_server_forwards_calls() {
+        let server =
+            Server::start("127.0.0.1:0".parse().unwrap(), Some("t0k".to_string())).unwrap();
+        let address = format!("http://{}", server.address());
+        let client = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let mut client = ControlClient::connect(address).await.unwrap();
+                    let refused = client.status(StatusRequest {}).await.unwrap_err();
+                    let mut request = Request::new(StatusRequest {});
+                    let token = "Bearer t0k".parse().unwrap();
+                    request.metadata_mut().insert("authorization", token);
+                    let answer = client.status(request).await.map(|r| r.into_inner());
+                    (refused.code(), answer)
+                })
+        });
+
+        while !client.is_finished() {
+            for call in server.pending() {
+                match call {
+                    Call::Status(reply) => {
+                        let _ = reply.send(Ok(StatusReply {
+                            pid: 42,
+                            ..Default::default()
+                        }));
+                    }
+                    other => panic!("unexpected call {:?}", other),
+                }
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let (refused, answer) = client.join().unwrap();
+        assert_eq!(tonic::Code::Unauthenticated, refused);
+        assert_eq!(42, answer.unwrap().pid);
+    }
+}
@@ -0,0 +1,172 @@
+use std::ffi::OsString;
+use std::io::Write;
+use std::process::Command;
+
+use log::warn;
+
+use crate::{Context, Error, ExitCode, Result};
+
+/// When a hook runs relative to its subcommand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Pre,
+    Post,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Pre => "pre",
+            Phase::Post => "post",
+        }
+    }
+}
+
+/// The run of a subcommand that hooks are told about.
+pub struct Invocation<'a> {
+    /// Name of the subcommand.
+    pub subcommand: String,
+    /// The whole command line.
+    pub args: &'a [OsString],
+}
+
+/// Runs the `hooks.<phase>.<subcommand>` shell command from the settings, if
+/// one is configured, with its output written to stderr.
+///
+/// The hook sees the invocation through environment variables named with
+/// the prefix of the settings' variables, by default: `FIXME_HOOK_PHASE`,
+/// `FIXME_HOOK_COMMAND` (the subcommand's name), `FIXME_HOOK_ARGS` (the
+/// command line, shell-quoted) and, for post hooks, `FIXME_HOOK_STATUS` (the
+/// exit status the subcommand will report). With `--dry-run` the hook is
+/// only reported.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if the hook cannot be started or exits
+/// unsuccessfully.
+pub fn run(
+    ctx: &Context,
+    invocation: &Invocation,
+    phase: Phase,
+    status: Option<ExitCode>,
+) -> Result<()> {
+    let key = format!("hooks.{}.{}", phase.name(), invocation.subcommand);
+    let Some(script) = ctx.settings().handle_request(&key) else {
+        return Ok(());
+    };
+    let args: Vec<_> = invocation
+        .args
+        .iter()
+        .map(|a| a.to_string_lossy())
+        .collect();
+
+    let var = |name: &str| format!("{}HOOK_{}", ctx.app().layout().env_prefix, name);
+    let mut shell = shell();
+    shell
+        .arg(&script)
+        .env(var("PHASE"), phase.name())
+        .env(var("COMMAND"), &invocation.subcommand)
+        .env(var("ARGS"), shell_words::join(&args));
+    if let Some(status) = status {
+        shell.env(var("STATUS"), status.code().to_string());
+    }
+    ctx.effect(format!("run {} hook '{}'", phase.name(), script), || {
+        let output = shell.output().map_err(|e| {
+            Error::Subcommand(format!(
+                "failed to run {} hook '{}': {}",
+                phase.name(),
+                script,
+                e
+            ))
+        })?;
+        let mut err = ctx.err();
+        err.write_all(&output.stdout)?;
+        err.write_all(&output.stderr)?;
+        if !output.status.success() {
+            return Err(Error::Subcommand(format!(
+                "{} hook '{}' failed with {}",
+                phase.name(),
+                script,
+                output.status
+            )));
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Runs `command` between its pre and post hooks.
+///
+/// A failing pre hook prevents the command from running. A failing post hook
+/// fails the run if the command succeeded, and is only logged otherwise.
+pub fn around<T, F>(ctx: &Context, invocation: &Invocation, command: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T>,
+{
+    run(ctx, invocation, Phase::Pre, None)?;
+    let result = command();
+    let status = match &result {
+        Ok(_) => ExitCode::Success,
+        Err(e) => ExitCode::from(e),
+    };
+    match (run(ctx, invocation, Phase::Post, Some(status)), result) {
+        (Err(e), Ok(_)) => Err(e),
+        (Err(e), Err(error)) => {
+            warn!("{}", e);
+            Err(error)
+        }
+        (Ok(()), result) => result,
+    }
+}
+
+#[cfg(unix)]
+fn shell() -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c");
+    crate::systemd::without_sockets(&mut shell);
+    shell
+}
+
+#[cfg(windows)]
+fn shell() -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C");
+    shell
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::AppBuilder;
+
+    #[test]
+    fn test_around_runs_hooks_with_invocation() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.json");
+        let hooks = serde_json::json!({ "hooks": {
+            "pre": { "hooktest": "echo pre $HOOKTEST_HOOK_COMMAND" },
+            "post": { "hooktest": "echo status $HOOKTEST_HOOK_STATUS; exit 3" },
+        }});
+        std::fs::write(&config, hooks.to_string()).unwrap();
+        let app = AppBuilder::new()
+            .env_prefix("HOOKTEST_")
+            .config_paths([config])
+            .build();
+        let matches = clap::ArgMatches::default();
+        let mut stderr = Vec::new();
+        let ctx = Context::new(&app, &matches).stderr(&mut stderr);
+        let invocation = Invocation {
+            subcommand: "hooktest".to_string(),
+            args: &[],
+        };
+
+        let result = around(&ctx, &invocation, || Ok(()));
+        drop(ctx);
+
+        assert!(matches!(result, Err(Error::Subcommand(_))));
+        assert_eq!(
+            "pre hooktest\nstatus 0\n",
+            String::from_utf8(stderr).unwrap()
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! A minimal HTTP listener for the daemon's `/healthz` and `/metrics`: just
+//! enough HTTP/1.1 for orchestrators' probes and Prometheus to scrape it.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::{Error, Result};
+
+/// How long a client has to send its whole request.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The most bytes a request line and its headers may take.
+const MAX_REQUEST: u64 = 8 * 1024;
+
+/// The content type of Prometheus' text exposition format.
+const METRICS_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The daemon's HTTP listener. Requests are read on threads of their own, so
+/// that slow clients cannot hold up the daemon, and answered from its main
+/// loop.
+pub struct Server {
+    address: SocketAddr,
+    requests: mpsc::Receiver<Request>,
+}
+
+/// A request read in full, waiting for its answer.
+struct Request {
+    stream: TcpStream,
+    method: String,
+    path: String,
+}
+
+impl Server {
+    /// Listens on `address`; port 0 picks a free port.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Subcommand`] if `address` cannot be listened on.
+    pub fn listen(address: SocketAddr) -> Result<Self> {
+        let cannot_listen =
+            |e: std::io::Error| Error::Subcommand(format!("cannot listen on {}: {}", address, e));
+        let listener = TcpListener::bind(address).map_err(cannot_listen)?;
+        let address = listener.local_addr().map_err(cannot_listen)?;
+        let (sender, requests) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("http".to_string())
+            .spawn(move || accept(listener, sender))
+            .map_err(cannot_listen)?;
+        log::info!("serving /healthz and /metrics on http://{}", address);
+        Ok(Server { address, requests })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn address(&self) -> Result<SocketAddr> {
+        Ok(self.address)
+    }
+
+    /// Answers every request read since the last poll, without waiting for
+    /// more; `/metrics` answers with what `metrics` renders.
+    ///
+    /// Failing connections are logged and skipped, so a misbehaving client
+    /// cannot stop the daemon.
+    pub fn poll<F>(&self, metrics: F) -> Result<()>
+    where
+        F: Fn() -> String,
+    {
+        for request in self.requests.try_iter() {
+            if let Err(e) = answer(request, &metrics) {
+                log::warn!("HTTP connection failed: {}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads each connection's request on a thread of its own and hands it to
+/// the [`Server`].
+fn accept(listener: TcpListener, requests: mpsc::Sender<Request>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("HTTP connection failed: {}", e);
+                continue;
+            }
+        };
+        let requests = requests.clone();
+        std::thread::spawn(move || match read_request(stream) {
+            // The server is gone when the daemon is stopping.
+            Ok(request) => {
+                let _ = requests.send(request);
+            }
+            Err(e) => log::warn!("HTTP connection failed: {}", e),
+        });
+    }
+}
+
+/// Reads the request line and headers of `stream`, failing once [`TIMEOUT`]
+/// has passed or they take more than [`MAX_REQUEST`] bytes.
+fn read_request(stream: TcpStream) -> std::io::Result<Request> {
+    let deadline = Deadline {
+        stream: &stream,
+        at: Instant::now() + TIMEOUT,
+    };
+    let mut reader = BufReader::new(deadline.take(MAX_REQUEST));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers are not needed, but a client may wait until they are read.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    if reader.get_ref().limit() == 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "request headers are too large",
+        ));
+    }
+    let mut words = request_line.split_whitespace();
+    let method = words.next().unwrap_or_default().to_string();
+    let path = words.next().unwrap_or_default().to_string();
+    drop(reader);
+    Ok(Request {
+        stream,
+        method,
+        path,
+    })
+}
+
+/// Reads from a stream until a point in time, however slowly the client
+/// sends, rather than for a time per read.
+struct Deadline<'s> {
+    stream: &'s TcpStream,
+    at: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let left = self.at.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        let mut stream = self.stream;
+        stream.read(buf)
+    }
+}
+
+fn answer<F>(request: Request, metrics: F) -> std::io::Result<()>
+where
+    F: Fn() -> String,
+{
+    let mut stream = request.stream;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let (status, content_type, body) = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/healthz") => ("200 OK", "text/plain", "ok\n".to_string()),
+        ("GET", "/metrics") => ("200 OK", METRICS_TYPE, metrics()),
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    fn get(address: SocketAddr, path: &str) -> std::thread::JoinHandle<String> {
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+        std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        })
+    }
+
+    #[test]
+    fn test_server_answers_health_and_metrics() {
+        let server = Server::listen("127.0.0.1:0".parse().unwrap()).unwrap();
+        let address = server.address().unwrap();
+        // A client that never sends its request holds up no one.
+        let _idle = TcpStream::connect(address).unwrap();
+
+        let mut responses = Vec::new();
+        for path in ["/healthz", "/metrics", "/nope"] {
+            let client = get(address, path);
+            while !client.is_finished() {
+                server.poll(|| "up 1\n".to_string()).unwrap();
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            responses.push(client.join().unwrap());
+        }
+
+        assert!(responses[0].starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(responses[0].ends_with("\r\n\r\nok\n"));
+        assert!(responses[1].contains(METRICS_TYPE));
+        assert!(responses[1].ends_with("\r\n\r\nup 1\n"));
+        assert!(responses[2].starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::OnceLock;
+
+use cor_args::Handler;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+use crate::settings;
+
+/// The locale every catalog falls back to for messages it does not translate.
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// The message catalogs shipped with the application, by locale.
+const CATALOGS: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US/main.ftl")),
+    ("de-DE", include_str!("../locales/de-DE/main.ftl")),
+];
+
+/// User-facing messages in one locale, backed by [Fluent](https://projectfluent.org)
+/// catalogs under `locales/`.
+pub struct Catalog {
+    locale: &'static str,
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl Catalog {
+    /// Loads the catalog that best matches `requested`, falling back to
+    /// [`DEFAULT_LOCALE`] for untranslated messages.
+    pub fn new(requested: &str) -> Self {
+        let locale = negotiate(requested);
+        let mut locales = vec![locale];
+        if locale != DEFAULT_LOCALE {
+            locales.push(DEFAULT_LOCALE);
+        }
+        Catalog {
+            locale,
+            bundles: locales.into_iter().map(bundle).collect(),
+        }
+    }
+
+    /// The locale messages are translated into.
+    pub fn locale(&self) -> &'static str {
+        self.locale
+    }
+
+    /// Returns the message `id`, or `id` itself if no catalog defines it.
+    pub fn message(&self, id: &str) -> String {
+        self.format(id, &[])
+    }
+
+    /// Returns the message `id` with its `{ $name }` placeables filled from `args`.
+    pub fn format(&self, id: &str, args: &[(&str, &str)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, *value);
+        }
+        for bundle in &self.bundles {
+            if let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) {
+                let mut errors = Vec::new();
+                return bundle
+                    .format_pattern(pattern, Some(&fluent_args), &mut errors)
+                    .into_owned();
+            }
+        }
+        id.to_string()
+    }
+}
+
+fn bundle(locale: &'static str) -> FluentBundle<FluentResource> {
+    let source = CATALOGS
+        .iter()
+        .find(|(l, _)| *l == locale)
+        .map(|(_, source)| source.to_string())
+        .unwrap_or_default();
+    let resource = FluentResource::try_new(source).expect("shipped catalogs are valid Fluent");
+    let langid: LanguageIdentifier = locale.parse().expect("shipped locales are valid");
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .expect("shipped catalogs have no duplicate messages");
+    bundle
+}
+
+/// Picks the shipped locale closest to `requested`: an exact match, then one
+/// with the same language, then [`DEFAULT_LOCALE`].
+pub fn negotiate(requested: &str) -> &'static str {
+    let Ok(requested) = requested.parse::<LanguageIdentifier>() else {
+        return DEFAULT_LOCALE;
+    };
+    let available = || {
+        CATALOGS.iter().map(|(locale, _)| {
+            (
+                *locale,
+                locale
+                    .parse::<LanguageIdentifier>()
+                    .expect("shipped locales are valid"),
+            )
+        })
+    };
+    available()
+        .find(|(_, langid)| *langid == requested)
+        .or_else(|| available().find(|(_, langid)| langid.language == requested.language))
+        .map_or(DEFAULT_LOCALE, |(locale, _)| locale)
+}
+
+/// Returns the locale the user asked for: the `locale` setting, else the
+/// `LC_ALL`, `LC_MESSAGES` or `LANG` environment variables.
+///
+/// POSIX names such as `de_DE.UTF-8` are converted to `de-DE`; the `C` and
+/// `POSIX` locales count as no preference.
+pub fn requested_locale(settings: &dyn Handler) -> Option<String> {
+    settings.handle_request("locale").or_else(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| {
+                let name = value.split(['.', '@']).next().unwrap_or_default();
+                match name {
+                    "" | "C" | "POSIX" => None,
+                    name => Some(name.replace('_', "-")),
+                }
+            })
+    })
+}
+
+/// The catalog for the user's locale, loaded on first use.
+pub fn catalog() -> &'static Catalog {
+    static CATALOG: OnceLock<Catalog> = OnceLock::new();
+    CATALOG.get_or_init(|| {
+        let requested = requested_locale(&*settings::chain(None));
+        Catalog::new(requested.as_deref().unwrap_or(DEFAULT_LOCALE))
+    })
+}
+
+/// Returns the message `id` in the user's locale.
+pub fn t(id: &str) -> String {
+    catalog().message(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_negotiates_and_falls_back() {
+        assert_eq!("de-DE", negotiate("de-AT"));
+        assert_eq!("en-US", negotiate("fr-FR"));
+
+        let german = Catalog::new("de-DE");
+        assert_eq!("Fehler", german.message("error-label"));
+        assert_eq!(
+            "For more information about this error, try `fixme explain E003`.",
+            Catalog::new("en-US").format("error-hint", &[("binary", "fixme"), ("code", "E003")])
+        );
+        assert_eq!("no-such-message", german.message("no-such-message"));
+    }
+}
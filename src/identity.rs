@@ -0,0 +1,63 @@
+//! Who the application is, taken from `Cargo.toml` by the build script, so
+//! that everything naming it follows the package's metadata.
+
+/// The names, version and authorship of the application.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AppIdentity {
+    /// Name of the binary, e.g. `template-rust-cli`.
+    pub binary: &'static str,
+    /// Short name used in its directories, plugins and service, e.g. `fixme`.
+    pub name: &'static str,
+    /// The name as an identifier, in lowercase with underscores, e.g. for metrics.
+    pub ident: &'static str,
+    /// Name shown to people, e.g. `FIXME`.
+    pub display_name: &'static str,
+    /// Version of the package, e.g. `0.1.0`.
+    pub version: &'static str,
+    /// Authors of the package, separated by colons as Cargo lists them.
+    pub authors: &'static str,
+    /// One-line description of the package.
+    pub description: &'static str,
+    /// Top-level domain of the reverse-DNS name, e.g. `com`.
+    pub qualifier: &'static str,
+    /// Organization of the reverse-DNS name, e.g. `example`.
+    pub organization: &'static str,
+    /// Prefix of the environment variables read as settings, e.g. `FIXME_`.
+    pub env_prefix: &'static str,
+}
+
+/// The [`AppIdentity`] from `[package]` and `[package.metadata.app]` in
+/// `Cargo.toml`.
+pub const IDENTITY: AppIdentity = AppIdentity {
+    binary: env!("CARGO_PKG_NAME"),
+    name: env!("FIXME_BUILD_APP_NAME"),
+    ident: env!("FIXME_BUILD_APP_IDENT"),
+    display_name: env!("FIXME_BUILD_APP_DISPLAY_NAME"),
+    version: env!("CARGO_PKG_VERSION"),
+    authors: env!("CARGO_PKG_AUTHORS"),
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    qualifier: env!("FIXME_BUILD_APP_QUALIFIER"),
+    organization: env!("FIXME_BUILD_APP_ORGANIZATION"),
+    env_prefix: env!("FIXME_BUILD_APP_ENV_PREFIX"),
+};
+
+impl AppIdentity {
+    /// The name in reverse-DNS notation, e.g. `com.example.fixme`.
+    pub fn reverse_dns(&self) -> String {
+        format!("{}.{}.{}", self.qualifier, self.organization, self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_derives_from_its_name() {
+        let dns = IDENTITY.reverse_dns();
+        assert!(dns.ends_with(&format!(".{}", IDENTITY.name)));
+        assert!(!dns.contains(".."));
+        assert_eq!(IDENTITY.ident.to_uppercase() + "_", IDENTITY.env_prefix);
+        assert_eq!(env!("CARGO_PKG_NAME"), IDENTITY.binary);
+    }
+}
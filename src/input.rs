@@ -0,0 +1,70 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+/// Where a subcommand reads its input from: a file, or stdin when given as `-`.
+///
+/// Use it as the type of a positional argument so the command composes with
+/// pipes, e.g. `cat data | FIXME cmd -`, and open it with [`crate::Context::open`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputSource {
+    Stdin,
+    File(PathBuf),
+}
+
+impl InputSource {
+    pub fn is_stdin(&self) -> bool {
+        matches!(self, InputSource::Stdin)
+    }
+
+    /// Reads all of the source, using the process's stdin for [`InputSource::Stdin`].
+    /// Inside a subcommand, prefer [`crate::Context::read_to_string`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read, or [`Error::Output`]
+    /// if stdin cannot.
+    pub fn read_to_string(&self) -> Result<String> {
+        match self {
+            InputSource::Stdin => std::io::read_to_string(std::io::stdin()).map_err(Error::Output),
+            InputSource::File(path) => std::fs::read_to_string(path).map_err(Error::io(path)),
+        }
+    }
+}
+
+impl FromStr for InputSource {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "-" => InputSource::Stdin,
+            path => InputSource::File(PathBuf::from(path)),
+        })
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Stdin => write!(f, "-"),
+            InputSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dash_is_stdin() {
+        assert_eq!(InputSource::Stdin, "-".parse().unwrap());
+        assert_eq!(
+            InputSource::File(PathBuf::from("data.json")),
+            "data.json".parse().unwrap()
+        );
+        assert_eq!("-", InputSource::Stdin.to_string());
+    }
+}
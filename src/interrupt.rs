@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use crate::context::CancellationToken;
+
+/// How long a subcommand has to stop after the first interrupt, before the
+/// process exits regardless. A second interrupt exits at once.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Turns SIGINT and SIGTERM into cancellation of a [`CancellationToken`],
+/// giving the running subcommand a chance to clean up before returning.
+///
+/// If the subcommand is still running after [`GRACE_PERIOD`], or the signal
+/// arrives a second time, the process exits with [`crate::ExitCode::Interrupted`].
+/// Dropping the handler restores the previous behavior. On platforms without
+/// Unix signals it does nothing, and Ctrl-C ends the process immediately.
+pub struct Interrupts {
+    #[cfg(unix)]
+    handle: signal_hook::iterator::Handle,
+    #[cfg(unix)]
+    done: Option<std::sync::mpsc::Sender<()>>,
+    #[cfg(unix)]
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Interrupts {
+    /// Starts cancelling `token` on interrupt, or returns `None`, logging a
+    /// warning, if the handler cannot be installed.
+    #[cfg(unix)]
+    pub fn install(token: CancellationToken) -> Option<Self> {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+        use std::sync::mpsc::{self, RecvTimeoutError};
+
+        let mut signals = match signal_hook::iterator::Signals::new([SIGINT, SIGTERM]) {
+            Ok(signals) => signals,
+            Err(e) => {
+                log::warn!("cannot handle interrupts: {}", e);
+                return None;
+            }
+        };
+        let handle = signals.handle();
+        let (done, finished) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            let mut signals = signals.forever();
+            if signals.next().is_none() {
+                return;
+            }
+            log::warn!("interrupted, stopping; interrupt again to exit immediately");
+            token.cancel();
+            std::thread::spawn(move || {
+                if finished.recv_timeout(GRACE_PERIOD) == Err(RecvTimeoutError::Timeout) {
+                    eprintln!(
+                        "Error: interrupted and did not stop within {}",
+                        humantime::format_duration(GRACE_PERIOD)
+                    );
+                    exit();
+                }
+            });
+            if signals.next().is_some() {
+                exit();
+            }
+        });
+        Some(Interrupts {
+            handle,
+            done: Some(done),
+            thread: Some(thread),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn install(_token: CancellationToken) -> Option<Self> {
+        Some(Interrupts {})
+    }
+}
+
+impl Drop for Interrupts {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            self.done.take();
+            self.handle.close();
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn exit() -> ! {
+    std::process::exit(crate::ExitCode::Interrupted.code().into())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_stops_handling() {
+        let token = CancellationToken::new();
+
+        let interrupts = Interrupts::install(token.clone()).unwrap();
+        drop(interrupts);
+
+        assert!(!token.is_cancelled());
+    }
+}
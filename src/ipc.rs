@@ -0,0 +1,478 @@
+//! The daemon's control socket: a Unix-domain socket, or a named pipe on
+//! Windows, taking one command per connection and answering with one line.
+
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use log::LevelFilter;
+
+use crate::{Error, Result};
+
+/// How long either end waits for the other to send its line.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A command sent to the daemon.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    Status,
+    ReloadConfig,
+    SetLogLevel(LevelFilter),
+    Shutdown,
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Request::Status => write!(f, "status"),
+            Request::ReloadConfig => write!(f, "reload-config"),
+            Request::SetLogLevel(level) => {
+                write!(f, "set-log-level {}", level.as_str().to_lowercase())
+            }
+            Request::Shutdown => write!(f, "shutdown"),
+        }
+    }
+}
+
+impl FromStr for Request {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let invalid = || Error::Subcommand(format!("unknown control command '{}'", line));
+        let mut words = line.split_whitespace();
+        let request = match words.next() {
+            Some("status") => Request::Status,
+            Some("reload-config") => Request::ReloadConfig,
+            Some("set-log-level") => Request::SetLogLevel(
+                words
+                    .next()
+                    .and_then(|l| l.parse().ok())
+                    .ok_or_else(invalid)?,
+            ),
+            Some("shutdown") => Request::Shutdown,
+            _ => return Err(invalid()),
+        };
+        match words.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(request),
+        }
+    }
+}
+
+/// Returns where the running daemon listens: `control.sock` in the
+/// [runtime directory](crate::paths::runtime_dir).
+#[cfg(unix)]
+pub fn socket_path() -> PathBuf {
+    crate::paths::runtime_dir().join("control.sock")
+}
+
+#[cfg(windows)]
+pub fn socket_path() -> PathBuf {
+    let ident = crate::identity::IDENTITY.ident;
+    PathBuf::from(format!(r"\\.\pipe\{}-control", ident))
+}
+
+/// Sends `request` to the daemon listening at `path` and returns its answer.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if no daemon is listening, or with the
+/// daemon's message if it could not carry out the request.
+pub fn request(path: &Path, request: &Request) -> Result<String> {
+    let mut stream = connect(path)?;
+    writeln!(stream, "{}", request).map_err(Error::io(path))?;
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(Error::io(path))?;
+    match line.trim_end().split_once(' ') {
+        Some(("ok", message)) => Ok(message.to_string()),
+        Some(("error", message)) => Err(Error::Subcommand(message.to_string())),
+        _ => Err(Error::Subcommand(format!(
+            "unexpected answer from the daemon: '{}'",
+            line.trim_end()
+        ))),
+    }
+}
+
+#[cfg(unix)]
+fn connect(path: &Path) -> Result<std::os::unix::net::UnixStream> {
+    let stream = std::os::unix::net::UnixStream::connect(path).map_err(|e| not_running(path, e))?;
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(Error::io(path))?;
+    Ok(stream)
+}
+
+#[cfg(windows)]
+fn connect(path: &Path) -> Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| not_running(path, e))
+}
+
+fn not_running(path: &Path, error: std::io::Error) -> Error {
+    Error::Subcommand(format!(
+        "the daemon is not listening at {}: {}",
+        path.display(),
+        error
+    ))
+}
+
+/// Reads the request line from a client, which has [`TIMEOUT`] to send it.
+#[cfg(unix)]
+fn read_line(stream: &mut std::os::unix::net::UnixStream) -> std::io::Result<String> {
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(line)
+}
+
+#[cfg(windows)]
+fn read_line(stream: &mut std::fs::File) -> std::io::Result<String> {
+    windows::read_line(stream, TIMEOUT)
+}
+
+/// Answers the request on `line` from `stream` with `handle`'s message or
+/// error, and returns the request if it was handled.
+fn serve<S, F>(mut stream: S, line: &str, handle: F) -> std::io::Result<Option<Request>>
+where
+    S: Write,
+    F: FnOnce(&Request) -> Result<String>,
+{
+    let (request, answer) = match line.trim().parse() {
+        Ok(request) => {
+            let answer = handle(&request);
+            (answer.is_ok().then_some(request), answer)
+        }
+        Err(e) => (None, Err(e)),
+    };
+    match answer {
+        Ok(message) => writeln!(stream, "ok {}", message)?,
+        Err(e) => writeln!(stream, "error {}", e)?,
+    }
+    Ok(request)
+}
+
+/// The daemon's end of the control socket, polled from its main loop.
+pub struct Server {
+    #[cfg(unix)]
+    listener: std::os::unix::net::UnixListener,
+    /// The socket file to remove on drop, if this server created it.
+    #[cfg(unix)]
+    created: Option<PathBuf>,
+    #[cfg(windows)]
+    pipe: windows::Pipe,
+}
+
+impl Server {
+    /// Listens on the socket passed by systemd's socket activation or, without
+    /// one, at `path`, replacing a socket left behind by a daemon that died.
+    /// The directory of `path` is made [private](crate::paths::create_private_dir)
+    /// first.
+    #[cfg(unix)]
+    pub fn listen(path: &Path) -> Result<Self> {
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        let (listener, created) = match crate::systemd::control_listener() {
+            Some(listener) => {
+                log::info!("using the control socket passed by systemd");
+                (listener, None)
+            }
+            None => {
+                // Another user must not be able to take or replace the socket.
+                if let Some(dir) = path.parent() {
+                    crate::paths::create_private_dir(dir)?;
+                }
+                if path.exists() && UnixStream::connect(path).is_err() {
+                    std::fs::remove_file(path).map_err(Error::io(path))?;
+                }
+                let listener = UnixListener::bind(path).map_err(Error::io(path))?;
+                (listener, Some(path.to_path_buf()))
+            }
+        };
+        listener.set_nonblocking(true).map_err(Error::io(path))?;
+        Ok(Server { listener, created })
+    }
+
+    #[cfg(windows)]
+    pub fn listen(path: &Path) -> Result<Self> {
+        Ok(Server {
+            pipe: windows::Pipe::create(path)?,
+        })
+    }
+
+    /// Answers every pending connection with `handle`, without waiting for
+    /// more, and returns the requests that were handled.
+    ///
+    /// Failing connections are logged and skipped, so a misbehaving client
+    /// cannot stop the daemon.
+    pub fn poll<F>(&mut self, mut handle: F) -> Result<Vec<Request>>
+    where
+        F: FnMut(&Request) -> Result<String>,
+    {
+        let mut handled = Vec::new();
+        while let Some(mut stream) = self.accept()? {
+            let served = read_line(&mut stream).and_then(|line| serve(stream, &line, &mut handle));
+            match served {
+                Ok(request) => handled.extend(request),
+                Err(e) => log::warn!("control connection failed: {}", e),
+            }
+        }
+        Ok(handled)
+    }
+
+    #[cfg(unix)]
+    fn accept(&self) -> Result<Option<std::os::unix::net::UnixStream>> {
+        match self.listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false).map_err(Error::Output)?;
+                stream
+                    .set_read_timeout(Some(TIMEOUT))
+                    .map_err(Error::Output)?;
+                Ok(Some(stream))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(Error::Output(e)),
+        }
+    }
+
+    #[cfg(windows)]
+    fn accept(&mut self) -> Result<Option<std::fs::File>> {
+        self.pipe.accept()
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Server {
+    fn drop(&mut self) {
+        if let Some(path) = &self.created {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle, RawHandle};
+    use std::path::Path;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use windows_sys::Win32::Foundation::{
+        GetLastError, LocalFree, ERROR_NO_DATA, ERROR_PIPE_CONNECTED, ERROR_PIPE_LISTENING,
+        INVALID_HANDLE_VALUE,
+    };
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows_sys::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+    use windows_sys::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, SetNamedPipeHandleState,
+        PIPE_NOWAIT, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE,
+        PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+    use windows_sys::Win32::System::IO::CancelSynchronousIo;
+
+    use crate::{Error, Result};
+
+    /// A named pipe instance waiting for a client, without blocking.
+    pub struct Pipe {
+        name: Vec<u16>,
+        security: OwnerOnly,
+        waiting: OwnedHandle,
+    }
+
+    impl Pipe {
+        pub fn create(path: &Path) -> Result<Self> {
+            let name: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+            let security = OwnerOnly::new()?;
+            let waiting = instance(&name, &security)?;
+            Ok(Pipe {
+                name,
+                security,
+                waiting,
+            })
+        }
+
+        /// Returns the waiting instance as a blocking stream if a client has
+        /// connected to it, replacing it with a new one for the next client.
+        pub fn accept(&mut self) -> Result<Option<File>> {
+            let handle = self.waiting.as_raw_handle();
+            // SAFETY: the handle is a pipe instance owned by `self`, and no
+            // OVERLAPPED is used.
+            if unsafe { ConnectNamedPipe(handle as _, std::ptr::null_mut()) } == 0 {
+                // SAFETY: GetLastError has no preconditions.
+                match unsafe { GetLastError() } {
+                    ERROR_PIPE_CONNECTED => {}
+                    ERROR_PIPE_LISTENING => return Ok(None),
+                    // A client that connected and left already; serving it
+                    // fails and is logged.
+                    ERROR_NO_DATA => {}
+                    _ => return Err(Error::Output(std::io::Error::last_os_error())),
+                }
+            }
+            let mode = PIPE_READMODE_BYTE | PIPE_WAIT;
+            // SAFETY: as above; `mode` outlives the call.
+            let set = unsafe {
+                SetNamedPipeHandleState(handle as _, &mode, std::ptr::null(), std::ptr::null())
+            };
+            if set == 0 {
+                return Err(Error::Output(std::io::Error::last_os_error()));
+            }
+            let next = instance(&self.name, &self.security)?;
+            let connected = std::mem::replace(&mut self.waiting, next);
+            Ok(Some(File::from(connected)))
+        }
+    }
+
+    /// Reads a line from `pipe` on a thread of its own, disconnecting the
+    /// client if it has not sent one within `timeout`, as a blocking pipe
+    /// has no read timeout.
+    pub fn read_line(pipe: &File, timeout: Duration) -> std::io::Result<String> {
+        let reader = pipe.try_clone()?;
+        let (sender, receiver) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            let mut line = String::new();
+            let read = BufReader::new(reader).read_line(&mut line).map(|_| line);
+            let _ = sender.send(read);
+        });
+        match receiver.recv_timeout(timeout) {
+            Ok(read) => read,
+            Err(_) => {
+                // SAFETY: both handles are open. Disconnecting fails the
+                // reads to come and cancelling the one under way, so that
+                // the thread ends.
+                unsafe {
+                    DisconnectNamedPipe(pipe.as_raw_handle() as _);
+                    CancelSynchronousIo(thread.as_raw_handle() as _);
+                }
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "the client sent no request in time",
+                ))
+            }
+        }
+    }
+
+    /// A security descriptor granting access to the pipe's owner only, so
+    /// that other users cannot control the daemon.
+    struct OwnerOnly(PSECURITY_DESCRIPTOR);
+
+    impl OwnerOnly {
+        fn new() -> Result<Self> {
+            // A protected DACL whose only entry gives the owner full access.
+            let sddl: Vec<u16> = "D:P(A;;GA;;;OW)".encode_utf16().chain([0]).collect();
+            let mut descriptor = std::ptr::null_mut();
+            // SAFETY: `sddl` is NUL-terminated, and the descriptor is freed
+            // on drop.
+            let converted = unsafe {
+                ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                    sddl.as_ptr(),
+                    SDDL_REVISION_1,
+                    &mut descriptor,
+                    std::ptr::null_mut(),
+                )
+            };
+            if converted == 0 {
+                return Err(Error::Output(std::io::Error::last_os_error()));
+            }
+            Ok(OwnerOnly(descriptor))
+        }
+    }
+
+    impl Drop for OwnerOnly {
+        fn drop(&mut self) {
+            // SAFETY: the descriptor was allocated by the conversion above.
+            unsafe { LocalFree(self.0 as _) };
+        }
+    }
+
+    fn instance(name: &[u16], security: &OwnerOnly) -> Result<OwnedHandle> {
+        let attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: security.0,
+            bInheritHandle: 0,
+        };
+        // SAFETY: `name` is NUL-terminated and `attributes` outlives the call.
+        let handle = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_NOWAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                &attributes,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(Error::Output(std::io::Error::last_os_error()));
+        }
+        // SAFETY: the handle was just created and is owned by nobody else.
+        Ok(unsafe { OwnedHandle::from_raw_handle(handle as RawHandle) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_round_trips_through_text() {
+        for request in [
+            Request::Status,
+            Request::ReloadConfig,
+            Request::SetLogLevel(LevelFilter::Debug),
+            Request::Shutdown,
+        ] {
+            assert_eq!(request, request.to_string().parse().unwrap());
+        }
+        assert!("set-log-level loud".parse::<Request>().is_err());
+        assert!("status now".parse::<Request>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_server_answers_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("control.sock");
+        let mut server = Server::listen(&path).unwrap();
+
+        let client = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let answer = request(&path, &Request::Status);
+                let refused = request(&path, &Request::Shutdown);
+                (answer, refused)
+            })
+        };
+        let mut handled = Vec::new();
+        while handled.len() < 2 && !client.is_finished() {
+            handled.extend(
+                server
+                    .poll(|request| match request {
+                        Request::Status => Ok("running".to_string()),
+                        _ => Err(Error::Subcommand("not now".to_string())),
+                    })
+                    .unwrap(),
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let (answer, refused) = client.join().unwrap();
+
+        assert_eq!("running", answer.unwrap());
+        assert_eq!("not now", refused.unwrap_err().to_string());
+        assert_eq!(vec![Request::Status], handled);
+        drop(server);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,1283 @@
+use std::io::{IsTerminal, Write};
+use std::sync::Arc;
+
+use clap::error::ErrorKind;
+use clap::{ArgMatches, FromArgMatches, Parser};
+use log::{debug, error, info, trace, warn, LevelFilter};
+
+pub mod alias;
+pub mod argfile;
+pub mod batch;
+pub mod builder;
+pub mod clipboard;
+pub mod clock;
+pub mod commands;
+pub mod context;
+pub mod daemon;
+pub mod error;
+pub mod events;
+pub mod examples;
+pub mod exit;
+pub mod flags;
+pub mod glyphs;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hooks;
+pub mod http;
+pub mod i18n;
+pub mod identity;
+pub mod input;
+pub mod interrupt;
+pub mod ipc;
+pub mod metrics;
+#[cfg(feature = "network")]
+pub mod net;
+pub mod paths;
+pub mod plugin;
+pub mod profile;
+pub mod progress;
+pub mod prompt;
+pub mod reload;
+pub mod render;
+pub mod reporter;
+pub mod retry;
+pub mod rpc;
+pub mod schedule;
+pub mod schema;
+#[cfg(windows)]
+pub mod service;
+pub mod settings;
+pub mod startup;
+pub mod state;
+pub mod stats;
+#[cfg(unix)]
+pub mod systemd;
+pub mod telemetry;
+pub mod theme;
+pub mod timeout;
+pub mod update;
+pub mod values;
+pub mod version;
+pub mod watch;
+
+pub use builder::AppBuilder;
+pub use context::Context;
+pub use error::{Error, Result};
+pub use exit::ExitCode;
+pub use input::InputSource;
+use prompt::{PromptMode, Prompter};
+pub use render::{Output, OutputFormat, Renderer};
+
+/// Sets up logging based on the specified verbosity level.
+///
+/// This function initializes the logging framework using `env_logger` crate.
+/// The verbosity level determines the amount of log output that will be displayed.
+///
+/// # Examples
+///
+/// ```
+/// use template_rust_cli::setup_logging;
+///
+/// setup_logging("debug");
+/// ```
+///
+/// # Arguments
+///
+/// * `verbosity` - A string slice representing the desired verbosity level.
+///   Valid values are "off", "error", "warn", "info", "debug", and "trace".
+///   If an invalid value is provided, the default level will be set to "info".
+///
+/// # Dependencies
+///
+/// This function depends on the following crates:
+///
+/// - `env_logger` - For setting up logging.
+/// - `log` - For defining log levels.
+///
+/// # Notes
+///
+/// It is recommended to call this function early in the program to set up logging
+/// before any log messages are generated. Calling it again changes the level of
+/// the logger set up by the first call.
+///
+pub fn setup_logging(verbosity: &str) {
+    // The logger lets everything through; the level is enforced by `log`
+    // itself so that it can be changed later.
+    let _ = env_logger::builder()
+        .filter(None, LevelFilter::Trace)
+        .try_init();
+    log::set_max_level(verbosity.parse().unwrap_or(LevelFilter::Info));
+
+    error!("log level enabled: error");
+    warn!("log level enabled: warn");
+    info!("log level enabled: info");
+    debug!("log level enabled: debug");
+    trace!("log level enabled: trace");
+}
+
+#[derive(Parser)]
+#[command(
+    infer_subcommands = true,
+    arg_required_else_help = true,
+    after_long_help = "Exit status:\n  0    success\n  1    the subcommand failed\n  64   usage error, including a missing or unknown subcommand\n  78   configuration error\n  124  timed out\n  130  interrupted\n  141  the output was closed, e.g. by head"
+)]
+pub(crate) struct Cli {
+    /// Set the logging verbosity level.
+    #[arg(
+        id = "verbosity",
+        short,
+        long = "verbose",
+        value_name = "VERBOSE",
+        long_help = "Choices: [off, error, warn, info, debug, trace]"
+    )]
+    verbose: Option<String>,
+
+    /// Format of the subcommand's result [default: human on a terminal, json otherwise].
+    #[arg(short, long, global = true, value_enum)]
+    output: Option<OutputFormat>,
+
+    /// Comma-separated columns to show in tabular output and reports, in order.
+    #[arg(long, global = true, value_delimiter = ',', value_name = "COLUMNS")]
+    columns: Vec<String>,
+
+    /// Render each result record through a template, e.g. '{{ name }}: {{ value }}'.
+    #[arg(
+        long,
+        global = true,
+        value_name = "TEMPLATE",
+        conflicts_with = "output"
+    )]
+    format: Option<String>,
+
+    /// Answer yes to every confirmation and accept defaults without asking.
+    #[arg(short, long, global = true, conflicts_with = "no_input")]
+    yes: bool,
+
+    /// Fail instead of prompting when input would be needed.
+    #[arg(long, global = true)]
+    no_input: bool,
+
+    /// Cancel the subcommand if it runs longer than this, e.g. 30s or 5m.
+    #[arg(long, global = true, value_name = "DURATION", value_parser = crate::values::duration)]
+    timeout: Option<std::time::Duration>,
+
+    /// Run the subcommand again up to this many times when it fails transiently.
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    retries: u32,
+
+    /// Delay before the first retry, doubling for each one after, e.g. 500ms.
+    #[arg(long, global = true, value_name = "DURATION", value_parser = crate::values::duration, default_value = "1s")]
+    retry_delay: std::time::Duration,
+
+    /// Pretend the current time is this instant, e.g. 2024-01-01T00:00:00Z, and
+    /// make waits return at once; for deterministic tests.
+    #[arg(long, global = true, hide = true, help_heading = flags::DEVELOPER, value_name = "TIME", value_parser = humantime::parse_rfc3339_weak)]
+    frozen_time: Option<std::time::SystemTime>,
+
+    /// Print how long each phase of startup took to stderr.
+    #[arg(long, global = true, hide = true, help_heading = flags::DEVELOPER)]
+    startup_report: bool,
+
+    /// Print a table of every setting, its value and where that comes from to stderr before running.
+    #[arg(long, global = true, hide = true, help_heading = flags::DEVELOPER)]
+    debug_config: bool,
+
+    /// Ignore cached results of remote lookups, such as the latest release.
+    #[arg(long, global = true, hide = true, help_heading = flags::DEVELOPER)]
+    no_cache: bool,
+
+    /// Print help, including the options for developers of the application.
+    #[arg(long, global = true)]
+    help_all: bool,
+
+    /// Write numbers and dates in human output as usual in this locale, e.g. de-DE.
+    #[arg(long, global = true, value_name = "LOCALE", value_parser = |s: &str| s.parse::<unic_langid::LanguageIdentifier>().map(|_| s.to_string()))]
+    locale: Option<String>,
+
+    /// Seed random numbers with this, to reproduce a run [default: a fresh one each run].
+    #[arg(long, global = true, value_name = "N", value_parser = |s: &str| crate::values::count(s).map(|_| s.to_string()))]
+    seed: Option<String>,
+
+    /// Run the subcommand once per line of this file, or - for stdin, appending the line's words.
+    #[arg(long, global = true, value_name = "FILE")]
+    batch: Option<InputSource>,
+
+    /// Number of batch items to run at the same time.
+    #[arg(
+        short,
+        long,
+        global = true,
+        value_name = "N",
+        default_value = "1",
+        requires = "batch"
+    )]
+    jobs: std::num::NonZeroUsize,
+
+    /// Order in which the output of concurrent batch items is written.
+    #[arg(long, global = true, value_enum, default_value_t = batch::Order::Input, requires = "batch")]
+    batch_order: batch::Order,
+
+    /// Run the subcommand again whenever one of these files or directories changes.
+    #[arg(long, global = true, value_name = "PATHS", value_delimiter = ',')]
+    watch: Vec<std::path::PathBuf>,
+
+    /// Show what would change without changing anything.
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// How the run reports its progress, warnings and results.
+    #[arg(long, global = true, value_enum, default_value_t = events::Report::Human)]
+    report: events::Report,
+
+    /// Also copy the primary value of the result to the clipboard.
+    #[arg(long, global = true)]
+    copy: bool,
+
+    /// Wrap help, tables and text at this many columns, or 0 not to
+    /// [default: $COLUMNS, else the terminal's width].
+    #[arg(long, global = true, value_name = "N")]
+    width: Option<usize>,
+
+    /// Print the run's wall time, peak memory and CPU time to stderr once it finishes.
+    #[arg(long, global = true)]
+    stats: bool,
+
+    /// Write the run's metrics to this file in Prometheus' text format.
+    #[arg(long, global = true, value_name = "FILE")]
+    metrics_out: Option<std::path::PathBuf>,
+
+    /// Write a trace of where the run spent its time to this file, for Perfetto or speedscope.
+    #[arg(long, global = true, value_name = "FILE")]
+    profile_out: Option<std::path::PathBuf>,
+}
+
+pub struct App {
+    args: clap::Command,
+    examples: examples::Registry,
+    commands: Vec<Box<dyn commands::Command>>,
+    layout: settings::Layout,
+    /// When the application was built, the start of `--startup-report` and
+    /// of the wall time of `--stats`.
+    created: std::time::Instant,
+    /// Whether to look for a newer release; see [`App::run`].
+    check_updates: bool,
+}
+
+impl App {
+    /// Creates the application as configured by a default [`AppBuilder`].
+    pub fn new() -> Self {
+        App::builder().build()
+    }
+
+    /// Starts configuring an application; see [`AppBuilder`].
+    pub fn builder() -> AppBuilder {
+        AppBuilder::new()
+    }
+
+    /// Formats the registered subcommands similar to `name` as a tip, or
+    /// nothing if there are none. External subcommands stop clap from
+    /// suggesting them itself.
+    fn similar_commands(&self, name: &str) -> String {
+        let similar: Vec<String> = self
+            .commands
+            .iter()
+            .map(|c| c.clap().get_name().to_string())
+            .filter(|candidate| strsim::damerau_levenshtein(name, candidate) <= 2)
+            .map(|candidate| format!("'{}'", candidate))
+            .collect();
+        match similar.is_empty() {
+            true => String::new(),
+            false => format!(
+                "\n\n  tip: a similar subcommand exists: {}",
+                similar.join(", ")
+            ),
+        }
+    }
+
+    /// Runs the subcommand that the program is named after, busybox-style,
+    /// so that a link to the binary named `fixme1` runs `fixme1` with the
+    /// rest of `args`.
+    fn multicall(&self, mut args: Vec<std::ffi::OsString>) -> Vec<std::ffi::OsString> {
+        let program = args
+            .first()
+            .and_then(|program| std::path::Path::new(program).file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned());
+        let Some(name) = program.filter(|name| {
+            name != self.args.get_name()
+                && self.commands.iter().any(|c| c.clap().get_name() == name)
+        }) else {
+            return args;
+        };
+        args[0] = self.args.get_name().into();
+        args.insert(1, name.into());
+        args
+    }
+
+    /// Returns the full clap definition of the application.
+    pub fn command(&self) -> &clap::Command {
+        &self.args
+    }
+
+    /// Returns where settings are read from.
+    pub fn layout(&self) -> &settings::Layout {
+        &self.layout
+    }
+
+    /// Returns the usage examples of every subcommand.
+    pub fn examples(&self) -> &examples::Registry {
+        &self.examples
+    }
+
+    pub fn run_with_args<I, T>(&mut self, args: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let terminal = std::io::stdout().is_terminal();
+        self.dispatch(
+            args,
+            &mut std::io::stdout(),
+            &mut std::io::stderr(),
+            terminal,
+        )
+    }
+
+    /// Runs the application with `args`, writing all user-facing output to
+    /// `stdout` and `stderr` instead of the process's standard streams.
+    ///
+    /// Arguments of the form `@path` are replaced with those read from `path`,
+    /// see [`argfile::expand`]. Run through a link named after a subcommand,
+    /// e.g. `fixme1`, the application runs that subcommand. Requests for
+    /// `--help` or `--version` are written to `stdout` and succeed.
+    /// Results are rendered for a terminal unless `--output` says otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Argument parsing failures, including a missing or unknown subcommand, are
+    /// returned as [`Error::Usage`], whose [`clap::Error`] callers can inspect for
+    /// its [`ErrorKind`] or render. The binary exits with
+    /// [`ExitCode::Usage`] for these usage errors.
+    pub fn run_with_io<I, T>(
+        &mut self,
+        args: I,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        self.dispatch(args, stdout, stderr, true)
+    }
+
+    /// Parses `args` and runs the selected subcommand. Without an explicit
+    /// `--output`, results are human-readable when `terminal` is true and
+    /// JSON otherwise, so piped output stays machine-readable.
+    fn dispatch<I, T>(
+        &self,
+        args: I,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        terminal: bool,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let mut startup = startup::StartupReport::new(self.created);
+        startup.mark("app setup");
+        let mut args = self.multicall(argfile::expand(args)?);
+        // Started before parsing, so that the trace covers it too.
+        let profile = requested(&args, "--profile-out", None)
+            .map(|path| profile::start(std::path::Path::new(&path)))
+            .transpose()?;
+        let help_all = flags::take_help_all(&mut args);
+        let parser = match batch::is_batch(&args) {
+            // The batch items supply the subcommand's required arguments.
+            true => batch::relax(self.args.clone()),
+            // The settings may supply those that stand for keys.
+            false => flags::relax_configured(self.args.clone(), &*self.layout.chain(None)),
+        };
+        let parser = match help_all {
+            true => flags::reveal_developer(parser),
+            false => parser,
+        };
+        let width = requested_width(&args);
+        let parser = parser.term_width(width.unwrap_or(0));
+        // Help is written while parsing, so its colors are decided before.
+        let accessible = flags::requested(&args, &*self.layout.chain(None), "accessible");
+        let parser = match accessible {
+            true => parser.styles(clap::builder::Styles::plain()),
+            false => parser,
+        };
+        let parsing = tracing::info_span!("parse arguments").entered();
+        let matches = match parser.try_get_matches_from(&args) {
+            Ok(matches) => matches,
+            Err(e) if e.kind() == ErrorKind::DisplayVersion => {
+                match requested_output(&args) {
+                    // Tooling asking for structured output gets the build details.
+                    Some(format) if format != OutputFormat::Human => {
+                        let output = Output::new(&version::BUILD);
+                        Renderer::new(format).render(&output, stdout)?;
+                    }
+                    _ => write!(stdout, "{}", e.render().ansi())?,
+                }
+                return Ok(());
+            }
+            Err(e) if e.kind() == ErrorKind::DisplayHelp => {
+                write!(stdout, "{}", e.render().ansi())?;
+                return Ok(());
+            }
+            Err(e) => return Err(Error::Usage(e)),
+        };
+        let cli = Cli::from_arg_matches(&matches)?;
+        // Results are events like the rest, so no other format applies to them.
+        if cli.report == events::Report::Json && (cli.output.is_some() || cli.format.is_some()) {
+            let err = self.args.clone().error(
+                ErrorKind::ArgumentConflict,
+                "the argument '--report json' cannot be used with '--output' or '--format'",
+            );
+            return Err(Error::Usage(err));
+        }
+        drop(parsing);
+        startup.mark("argument parsing");
+        let resolving = tracing::info_span!("resolve settings").entered();
+        let settings = self.layout.chain(None);
+        let ascii = flags::resolve(&matches, &*settings, "ascii");
+        // Only the first hook takes effect; a second run keeps the first choice.
+        if accessible {
+            let _ = miette::set_hook(Box::new(|_| {
+                Box::new(miette::NarratableReportHandler::new())
+            }));
+        } else if ascii {
+            let _ = miette::set_hook(Box::new(|_| {
+                Box::new(miette::MietteHandlerOpts::new().unicode(false).build())
+            }));
+        }
+        self.layout.validate()?;
+        #[cfg(feature = "network")]
+        net::configure(&*settings);
+
+        let verbosity = cli
+            .verbose
+            .clone()
+            .or_else(|| settings.handle_request("verbosity"));
+        let seed = match cli.seed.clone().or_else(|| settings.handle_request("seed")) {
+            Some(seed) => values::count(&seed).map_err(|reason| Error::InvalidValue {
+                key: "seed".to_string(),
+                reason,
+            })?,
+            None => fastrand::u64(..),
+        };
+        drop(resolving);
+        startup.mark("config resolution");
+        if let Some(verbosity) = &verbosity {
+            setup_logging(verbosity);
+        }
+        startup.mark("logger init");
+        debug!("random seed: {}", seed);
+        if cli.startup_report {
+            writeln!(stderr, "{}", startup.render())?;
+        }
+        if cli.debug_config {
+            let settings = serde_json::to_value(self.layout.debug(Some(&matches)))
+                .expect("settings serialize");
+            let columns = ["key", "value", "source"].map(str::to_string);
+            render::table::render(&settings, &columns, None, "…", stderr)?;
+        }
+
+        let command = matches.subcommand().and_then(|(name, sub_m)| {
+            self.commands
+                .iter()
+                .find(|c| c.clap().get_name() == name)
+                .map(|c| (c, sub_m))
+        });
+        let Some((command, sub_m)) = command else {
+            if let Some((name, external_m)) = matches.subcommand() {
+                let external_args: Vec<std::ffi::OsString> = external_m
+                    .get_many::<std::ffi::OsString>("")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect();
+                // The external subcommand takes every remaining argument.
+                let position = args.len() - external_args.len() - 1;
+                let expanded = alias::expand(&args, position, &*settings, |name| {
+                    self.commands.iter().any(|c| c.clap().get_name() == name)
+                })?;
+                if let Some(expanded) = expanded {
+                    debug!("expanded alias '{}' to {:?}", name, expanded);
+                    // The expanded command line records a profile of its own.
+                    if let Some(profile) = profile {
+                        profile.finish();
+                    }
+                    return self.dispatch(expanded, stdout, stderr, terminal);
+                }
+                if let Some(program) = plugin::locate(name) {
+                    let mut env: Vec<(String, String)> = settings::snapshot(&*settings)
+                        .into_iter()
+                        .filter(|(key, _)| schema::lookup(key).is_ok_and(|k| !k.secret))
+                        .map(|(key, value)| (format!("{}{}", self.layout.env_prefix, key), value))
+                        .collect();
+                    if let Some(verbosity) = &verbosity {
+                        env.push((
+                            format!("{}verbosity", self.layout.env_prefix),
+                            verbosity.clone(),
+                        ));
+                    }
+                    debug!("running external subcommand {}", program.display());
+                    return plugin::exec(&program, &external_args, &env);
+                }
+            }
+            let mut cmd = self.args.clone();
+            let err = match matches.subcommand_name() {
+                Some(name) => cmd.error(
+                    ErrorKind::InvalidSubcommand,
+                    format!(
+                        "unrecognized subcommand '{}'{}",
+                        name,
+                        self.similar_commands(name)
+                    ),
+                ),
+                None => cmd.error(ErrorKind::MissingSubcommand, "a subcommand is required"),
+            };
+            return Err(Error::Usage(err));
+        };
+
+        let format = cli.output.unwrap_or(if terminal {
+            OutputFormat::Human
+        } else {
+            OutputFormat::Json
+        });
+        let renderer = Renderer::new(format)
+            .columns(cli.columns.clone())
+            .width(width)
+            .template(cli.format.clone())
+            .ascii(ascii)
+            .accessible(accessible)
+            .locale(
+                cli.locale
+                    .clone()
+                    .or_else(|| i18n::requested_locale(&*settings))
+                    .map_or(render::locale::PLAIN, |name| {
+                        render::locale::Locale::new(&name)
+                    }),
+            )
+            .title(format!(
+                "{} {}",
+                self.args.get_name(),
+                command.clap().get_name()
+            ));
+        let interrupted = context::CancellationToken::new();
+        let _interrupts = interrupt::Interrupts::install(interrupted.clone());
+        let _reloader = reload::Reloader::install(self.layout.clone(), cli.verbose.is_none());
+        let options = RunOptions {
+            cli: &cli,
+            args: &args,
+            renderer: &renderer,
+            interrupted,
+            clock: match cli.frozen_time {
+                Some(at) => Arc::new(clock::FrozenClock::new(at)),
+                None => Arc::new(clock::SystemClock),
+            },
+            seed,
+            #[cfg(feature = "async")]
+            runtime: tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .map_err(Error::Output)?,
+        };
+        if cli.watch.is_empty() {
+            let mut update_check = (cfg!(feature = "network")
+                && self.check_updates
+                && std::io::stderr().is_terminal()
+                && flags::resolve(&matches, &*settings, "update.check"))
+            .then(|| {
+                update::UpdateCheck::start(
+                    update::state_path(),
+                    update::RELEASES_URL,
+                    options.clock.now(),
+                    !cli.no_cache,
+                )
+            });
+            let telemetry = (cfg!(feature = "network")
+                && flags::resolve(&matches, &*settings, "telemetry"))
+            .then(|| telemetry::Telemetry::start(telemetry::events_path(), telemetry::ENDPOINT));
+            let started = options.clock.now();
+            let result = self.run_selected(&options, command.as_ref(), sub_m, stdout, stderr);
+            if let Some(telemetry) = telemetry {
+                telemetry.record(&telemetry::Event::new(
+                    command.clap().get_name(),
+                    started,
+                    options
+                        .clock
+                        .now()
+                        .duration_since(started)
+                        .unwrap_or_default(),
+                    result.is_ok(),
+                ));
+            }
+            let notice = update_check
+                .as_mut()
+                .and_then(|check| check.notice(identity::IDENTITY.version, options.clock.now()));
+            if let Some(notice) = notice {
+                writeln!(stderr, "{}", notice)?;
+            }
+            if cli.stats {
+                writeln!(stderr, "{}", stats::Usage::since(self.created).render())?;
+            }
+            return result.and(write_metrics(&cli));
+        }
+
+        let clear = terminal && !accessible;
+        let watched = self.watch(&options, command.as_ref(), sub_m, stdout, stderr, clear);
+        // Watching ends with an interruption, which still gets its reports.
+        if cli.stats {
+            writeln!(stderr, "{}", stats::Usage::since(self.created).render())?;
+        }
+        watched.and(write_metrics(&cli))
+    }
+
+    /// Runs `command` again whenever one of the `--watch` paths changes,
+    /// clearing the screen first if `clear`, until interrupted.
+    fn watch(
+        &self,
+        options: &RunOptions,
+        command: &dyn commands::Command,
+        sub_m: &ArgMatches,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        clear: bool,
+    ) -> Result<()> {
+        let watcher = watch::Watcher::new(&options.cli.watch, watch::DEBOUNCE)?;
+        loop {
+            if clear {
+                write!(stdout, "{}", watch::CLEAR_SCREEN)?;
+            }
+            match self.run_selected(options, command, sub_m, stdout, stderr) {
+                Err(Error::Interrupted) => return Err(Error::Interrupted),
+                Err(e) => writeln!(stderr, "{}[{}]: {}", i18n::t("error-label"), e.code(), e)?,
+                Ok(()) => {}
+            }
+            writeln!(stderr, "Watching for changes; press Ctrl-C to stop")?;
+            let changed = watcher.wait(&options.interrupted)?;
+            debug!("changed: {:?}", changed);
+        }
+    }
+
+    /// Runs `command` once, or once per item with `--batch`.
+    fn run_selected(
+        &self,
+        options: &RunOptions,
+        command: &dyn commands::Command,
+        sub_m: &ArgMatches,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<()> {
+        match &options.cli.batch {
+            Some(source) => self.run_batch(options, source, command, stdout, stderr),
+            None => self.execute(options, command, sub_m, stdout, stderr),
+        }
+    }
+
+    /// Runs `command` once per non-blank line of `source`, appending the
+    /// line's words to the command line, then reports how many items failed.
+    ///
+    /// Lines starting with `#` are skipped. A failing item is reported on
+    /// `stderr` and does not stop the others, unless the output was closed.
+    /// With `--jobs` above one, items run concurrently and the output of each
+    /// is held back until it completes, then written in the order chosen
+    /// with `--batch-order`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Batch`] if any item failed.
+    fn run_batch(
+        &self,
+        options: &RunOptions,
+        source: &InputSource,
+        command: &dyn commands::Command,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<()> {
+        let content = source.read_to_string()?;
+        let items: Vec<&str> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+        let run_item = |item: &str, stdout: &mut dyn Write, stderr: &mut dyn Write| {
+            let _span = tracing::info_span!("batch item", item = %item).entered();
+            let args = batch::item_args(options.args, item)?;
+            let matches = self
+                .args
+                .clone()
+                .try_get_matches_from(args)
+                .map_err(Error::Usage)?;
+            let sub_m = matches
+                .subcommand_matches(command.clap().get_name())
+                .expect("the batch repeats the selected subcommand");
+            self.execute(options, command, sub_m, stdout, stderr)
+        };
+
+        let (mut processed, mut failed) = (0, 0);
+        let jobs = options.cli.jobs.get().min(items.len());
+        if jobs <= 1 {
+            for item in items
+                .iter()
+                .take_while(|_| !options.interrupted.is_cancelled())
+            {
+                processed += 1;
+                match run_item(item, stdout, stderr) {
+                    Err(e) if e.is_broken_pipe() => return Err(e),
+                    Err(e) => {
+                        failed += 1;
+                        batch::report_failure(stderr, item, &e)?;
+                    }
+                    Ok(()) => {}
+                }
+            }
+        } else {
+            let progress = progress::Tally::start("items", items.len(), options.renderer.glyphs());
+            let mut report = |completed: batch::Completed| -> Result<()> {
+                processed += 1;
+                progress.suspend(|| -> Result<()> {
+                    stdout.write_all(&completed.stdout)?;
+                    stderr.write_all(&completed.stderr)?;
+                    if let Err(e) = &completed.result {
+                        failed += 1;
+                        batch::report_failure(stderr, items[completed.index], e)?;
+                    }
+                    Ok(())
+                })
+            };
+            let next = std::sync::atomic::AtomicUsize::new(0);
+            let (done, completions) = std::sync::mpsc::channel();
+            // The workers record their spans wherever this thread does.
+            let dispatch = tracing::dispatcher::get_default(Clone::clone);
+            std::thread::scope(|scope| -> Result<()> {
+                for _ in 0..jobs {
+                    let (next, items, run_item, done) = (&next, &items, &run_item, done.clone());
+                    let (interrupted, dispatch) = (&options.interrupted, &dispatch);
+                    scope.spawn(move || {
+                        tracing::dispatcher::with_default(dispatch, || loop {
+                            if interrupted.is_cancelled() {
+                                break;
+                            }
+                            let index = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                            let Some(item) = items.get(index) else {
+                                break;
+                            };
+                            let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+                            let result = run_item(item, &mut stdout, &mut stderr);
+                            let completed = batch::Completed {
+                                index,
+                                stdout,
+                                stderr,
+                                result,
+                            };
+                            if done.send(completed).is_err() {
+                                break;
+                            }
+                        })
+                    });
+                }
+                drop(done);
+
+                let mut pending = std::collections::BTreeMap::new();
+                let mut next_in_order = 0;
+                for completed in completions {
+                    progress.inc();
+                    match options.cli.batch_order {
+                        batch::Order::Completion => report(completed)?,
+                        batch::Order::Input => {
+                            pending.insert(completed.index, completed);
+                            while let Some(completed) = pending.remove(&next_in_order) {
+                                report(completed)?;
+                                next_in_order += 1;
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            })?;
+        }
+
+        writeln!(
+            stderr,
+            "Processed {} item(s): {} succeeded, {} failed",
+            processed,
+            processed - failed,
+            failed
+        )?;
+        match failed {
+            _ if options.interrupted.is_cancelled() => Err(Error::Interrupted),
+            0 => Ok(()),
+            failed => Err(Error::Batch {
+                failed,
+                total: items.len(),
+            }),
+        }
+    }
+
+    /// Runs `command` once with its parsed arguments `sub_m`, applying the
+    /// timeout, retry and clipboard options of the command line.
+    fn execute(
+        &self,
+        options: &RunOptions,
+        command: &dyn commands::Command,
+        sub_m: &ArgMatches,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+    ) -> Result<()> {
+        let _span = tracing::info_span!("run", subcommand = %command.clap().get_name()).entered();
+        let (cli, renderer) = (options.cli, options.renderer);
+        let cancellation = options.interrupted.child();
+        let prompt_mode = if cli.yes {
+            PromptMode::AssumeYes
+        } else if cli.no_input {
+            PromptMode::NoInput
+        } else {
+            PromptMode::Interactive
+        };
+        let ctx = Context::new(self, sub_m)
+            .stdout(stdout)
+            .stderr(stderr)
+            .glyphs(renderer.glyphs())
+            .prompter(Prompter::new(prompt_mode))
+            .dry_run(cli.dry_run)
+            .cancellation(cancellation.clone())
+            .clock(options.clock.clone())
+            .seed(options.seed)
+            .renderer(renderer.clone())
+            .bus(events::Bus::reporting(cli.report));
+        let policy = retry::RetryPolicy {
+            retries: cli.retries,
+            delay: cli.retry_delay,
+        };
+        let invocation = hooks::Invocation {
+            subcommand: command.clap().get_name().to_string(),
+            args: options.args,
+        };
+        let started = ctx.time().now();
+        ctx.publish(&events::Event::Started {
+            subcommand: &invocation.subcommand,
+        })?;
+        let output = hooks::around(&ctx, &invocation, || {
+            let watchdog = cli
+                .timeout
+                .map(|limit| timeout::Watchdog::start(limit, cancellation));
+            let output = policy.run(ctx.cancellation_token(), ctx.time(), |attempt| {
+                if attempt > 1 {
+                    ctx.reset();
+                }
+                #[cfg(feature = "async")]
+                return options.runtime.block_on(command.run_async(&ctx));
+                #[cfg(not(feature = "async"))]
+                command.run(&ctx)
+            });
+            if let (Some(watchdog), Some(limit)) = (watchdog, cli.timeout) {
+                if watchdog.finish() {
+                    return Err(Error::Timeout { limit });
+                }
+            }
+            if options.interrupted.is_cancelled() {
+                return Err(Error::Interrupted);
+            }
+            output
+        });
+        let name = invocation.subcommand.as_str();
+        let outcome = if output.is_ok() { "success" } else { "failure" };
+        metrics::increment(
+            &metrics::COMMAND_RUNS,
+            &[("command", name), ("outcome", outcome)],
+        );
+        let duration = ctx.time().now().duration_since(started).unwrap_or_default();
+        metrics::observe(
+            &metrics::COMMAND_DURATION,
+            &[("command", name)],
+            duration.as_secs_f64(),
+        );
+        let finished = |success| {
+            ctx.publish(&events::Event::Finished {
+                subcommand: name,
+                duration,
+                success,
+            })
+        };
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                // What the subcommand reported before failing is still shown.
+                ctx.finish(None)?;
+                finished(false)?;
+                return Err(e);
+            }
+        };
+        let copied = match (cli.copy, &output) {
+            (true, Some(output)) => Some(match output.primary_text() {
+                Some(text) => text,
+                None => {
+                    let mut buffer = Vec::new();
+                    renderer.render(output, &mut buffer)?;
+                    String::from_utf8_lossy(&buffer).trim_end().to_string()
+                }
+            }),
+            _ => None,
+        };
+        tracing::info_span!("render output").in_scope(|| ctx.finish(output))?;
+        if let Some(text) = copied {
+            clipboard::copy(&text)?;
+            ctx.progress("Copied to the clipboard")?;
+        }
+        finished(true)
+    }
+
+    /// Runs the application with the process's arguments and streams.
+    ///
+    /// Unlike the other entry points, this also looks for a newer release
+    /// in the background, unless disabled with the `update.check` setting.
+    pub fn run(&mut self) -> Result<()> {
+        self.check_updates = true;
+        self.run_with_args(std::env::args())
+    }
+}
+
+/// Returns the last value of the option `long`, or of its `short` form,
+/// among `args`, for options needed before clap parses the rest, such as
+/// when it answers `--version` or `--help`.
+fn requested(args: &[std::ffi::OsString], long: &str, short: Option<char>) -> Option<String> {
+    let short = short.map(|short| format!("-{}", short));
+    let mut args = args
+        .iter()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .take_while(|arg| arg != "--");
+    let mut value = None;
+    while let Some(arg) = args.next() {
+        if arg == long || Some(&arg) == short.as_ref() {
+            value = args.next().or(value);
+        } else if let Some(given) = arg
+            .strip_prefix(long)
+            .and_then(|rest| rest.strip_prefix('='))
+            .or_else(|| {
+                let short = short.as_deref()?;
+                arg.strip_prefix(short)
+                    .map(|rest| rest.strip_prefix('=').unwrap_or(rest))
+            })
+        {
+            value = Some(given.to_string());
+        }
+    }
+    value
+}
+
+/// Returns the format selected with `--output` among `args`.
+fn requested_output(args: &[std::ffi::OsString]) -> Option<OutputFormat> {
+    use clap::ValueEnum;
+
+    requested(args, "--output", Some('o'))
+        .and_then(|value| OutputFormat::from_str(&value, true).ok())
+}
+
+/// Returns the width output is wrapped at: `--width` among `args`, else that
+/// of [`render::terminal_width`]. A width of 0 does not wrap.
+fn requested_width(args: &[std::ffi::OsString]) -> Option<usize> {
+    requested(args, "--width", None)
+        .and_then(|value| value.parse().ok())
+        .or_else(render::terminal_width)
+        .filter(|width| *width > 0)
+}
+
+/// Writes the run's metrics to the `--metrics-out` file, if one was given.
+fn write_metrics(cli: &Cli) -> Result<()> {
+    match &cli.metrics_out {
+        Some(path) => std::fs::write(path, metrics::render()).map_err(Error::io(path)),
+        None => Ok(()),
+    }
+}
+
+/// Replaces the application's help text with its translation from the
+/// [`i18n`] catalog.
+pub(crate) fn localize(cmd: clap::Command) -> clap::Command {
+    use i18n::t;
+
+    let description = identity::IDENTITY.description;
+    cmd.about(i18n::catalog().format("app-about", &[("description", description)]))
+        .after_long_help(t("app-exit-status"))
+        .mut_arg("verbosity", |a| a.help(t("arg-verbose")))
+        .mut_arg("output", |a| a.help(t("arg-output")))
+        .mut_arg("columns", |a| a.help(t("arg-columns")))
+        .mut_arg("format", |a| a.help(t("arg-format")))
+        .mut_arg("ascii", |a| a.help(t("arg-ascii")))
+        .mut_arg("yes", |a| a.help(t("arg-yes")))
+        .mut_arg("no_input", |a| a.help(t("arg-no-input")))
+        .mut_arg("timeout", |a| a.help(t("arg-timeout")))
+        .mut_arg("retries", |a| a.help(t("arg-retries")))
+        .mut_arg("retry_delay", |a| a.help(t("arg-retry-delay")))
+        .mut_arg("batch", |a| a.help(t("arg-batch")))
+        .mut_arg("jobs", |a| a.help(t("arg-jobs")))
+        .mut_arg("batch_order", |a| a.help(t("arg-batch-order")))
+        .mut_arg("watch", |a| a.help(t("arg-watch")))
+        .mut_arg("dry_run", |a| a.help(t("arg-dry-run")))
+        .mut_arg("report", |a| a.help(t("arg-report")))
+        .mut_arg("copy", |a| a.help(t("arg-copy")))
+        .mut_arg("width", |a| a.help(t("arg-width")))
+        .mut_arg("stats", |a| a.help(t("arg-stats")))
+        .mut_arg("metrics_out", |a| a.help(t("arg-metrics-out")))
+        .mut_arg("profile_out", |a| a.help(t("arg-profile-out")))
+        .mut_arg("locale", |a| a.help(t("arg-locale")))
+        .mut_arg("seed", |a| a.help(t("arg-seed")))
+        .mut_arg("help_all", |a| a.help(t("arg-help-all")))
+}
+
+/// The parsed global options of an invocation, shared by every run of its
+/// subcommand.
+struct RunOptions<'a> {
+    cli: &'a Cli,
+    args: &'a [std::ffi::OsString],
+    renderer: &'a Renderer,
+    /// Cancelled when the process is interrupted; every run's token is a child of it.
+    interrupted: context::CancellationToken,
+    /// The source of the current time, frozen with `--frozen-time`.
+    clock: Arc<dyn clock::Clock>,
+    /// Seeds each run's random numbers, from `--seed` or the settings.
+    seed: u64,
+    /// Runs [`commands::Command::run_async`]; shared by concurrent batch items.
+    #[cfg(feature = "async")]
+    runtime: tokio::runtime::Runtime,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_with_args() {
+        assert_eq!(
+            Some(()),
+            App::new()
+                .run_with_args(vec!["fixme.exe", "fixme1", "0"])
+                .ok()
+        );
+    }
+
+    #[test]
+    fn test_version_respects_output() {
+        let mut stdout = Vec::new();
+
+        App::new()
+            .run_with_io(
+                vec!["fixme.exe", "--output", "json", "--version"],
+                &mut stdout,
+                &mut Vec::new(),
+            )
+            .unwrap();
+
+        let build: serde_json::Value = serde_json::from_slice(&stdout).unwrap();
+        assert_eq!(env!("CARGO_PKG_VERSION"), build["version"]);
+        assert!(build["features"].is_array());
+    }
+
+    #[test]
+    fn test_run_with_io_captures_output() {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
+        App::new()
+            .run_with_io(vec!["fixme.exe", "fixme2", "foo"], &mut stdout, &mut stderr)
+            .unwrap();
+
+        assert_eq!("input: foo\n", String::from_utf8(stdout).unwrap());
+        assert_eq!(
+            "Running fixme2: Fixme2Result { input: \"foo\" }\n",
+            String::from_utf8(stderr).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_link_named_after_subcommand_runs_it() {
+        let mut stdout = Vec::new();
+
+        App::new()
+            .run_with_io(
+                vec!["/usr/local/bin/fixme2", "-o", "json", "foo"],
+                &mut stdout,
+                &mut Vec::new(),
+            )
+            .unwrap();
+
+        let output: serde_json::Value = serde_json::from_slice(&stdout).unwrap();
+        assert_eq!("foo", output["input"]);
+    }
+
+    #[test]
+    fn test_run_with_io_runs_each_batch_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let items = dir.path().join("items.txt");
+        std::fs::write(&items, "foo\n# skipped\n\n'bar\n").unwrap();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
+        let err = App::new()
+            .run_with_io(
+                vec!["fixme.exe", "--batch", items.to_str().unwrap(), "fixme2"],
+                &mut stdout,
+                &mut stderr,
+            )
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::Batch {
+                failed: 1,
+                total: 2
+            }
+        ));
+        assert_eq!("input: foo\n", String::from_utf8(stdout).unwrap());
+        assert!(String::from_utf8(stderr)
+            .unwrap()
+            .ends_with("Processed 2 item(s): 1 succeeded, 1 failed\n"));
+    }
+
+    #[test]
+    fn test_run_with_io_keeps_input_order_with_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let items = dir.path().join("items.txt");
+        std::fs::write(&items, "a\nb\nc\nd\n").unwrap();
+        let mut stdout = Vec::new();
+
+        App::new()
+            .run_with_io(
+                vec![
+                    "fixme.exe",
+                    "-j",
+                    "3",
+                    "--batch",
+                    items.to_str().unwrap(),
+                    "fixme2",
+                ],
+                &mut stdout,
+                &mut Vec::new(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            "input: a\ninput: b\ninput: c\ninput: d\n",
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_dispatch_defaults_to_json_when_piped() {
+        let mut stdout = Vec::new();
+
+        App::new()
+            .dispatch(
+                vec!["fixme.exe", "fixme2", "foo"],
+                &mut stdout,
+                &mut Vec::new(),
+                false,
+            )
+            .unwrap();
+
+        assert_eq!(
+            "{\n  \"input\": \"foo\"\n}\n",
+            String::from_utf8(stdout).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_run_with_io_collects_passthrough_args() {
+        let mut stdout = Vec::new();
+
+        App::new()
+            .run_with_io(
+                vec![
+                    "fixme.exe",
+                    "-o",
+                    "json",
+                    "fixme1",
+                    "foo",
+                    "--",
+                    "-x",
+                    "--y",
+                ],
+                &mut stdout,
+                &mut Vec::new(),
+            )
+            .unwrap();
+
+        let result: serde_json::Value = serde_json::from_slice(&stdout).unwrap();
+        assert_eq!(serde_json::json!(["-x", "--y"]), result["passthrough"]);
+    }
+
+    #[test]
+    fn test_run_with_args_returns_parse_errors() {
+        let err = App::new()
+            .run_with_args(vec!["fixme.exe", "fixme2"])
+            .unwrap_err();
+
+        assert_eq!(
+            Some(ErrorKind::MissingRequiredArgument),
+            match err {
+                Error::Usage(e) => Some(e.kind()),
+                _ => None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_with_args_requires_subcommand() {
+        let err = App::new()
+            .run_with_args(vec!["fixme.exe", "-v", "off"])
+            .unwrap_err();
+
+        assert_eq!(
+            Some(ErrorKind::MissingSubcommand),
+            match err {
+                Error::Usage(e) => Some(e.kind()),
+                _ => None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_with_args_rejects_output_for_json_report() {
+        let err = App::new()
+            .run_with_args(vec![
+                "fixme.exe",
+                "--report",
+                "json",
+                "-o",
+                "csv",
+                "fixme2",
+                "a",
+            ])
+            .unwrap_err();
+
+        assert_eq!(
+            Some(ErrorKind::ArgumentConflict),
+            match err {
+                Error::Usage(e) => Some(e.kind()),
+                _ => None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_with_io_writes_help() {
+        let mut stdout = Vec::new();
+
+        App::new()
+            .run_with_io(vec!["fixme.exe", "--help"], &mut stdout, &mut Vec::new())
+            .unwrap();
+
+        assert!(String::from_utf8(stdout).unwrap().contains("Usage:"));
+    }
+}
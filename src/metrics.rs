@@ -0,0 +1,231 @@
+//! Process-wide counters and histograms, rendered in Prometheus' text format
+//! by the daemon's `/metrics` and by `--metrics-out`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Prefix of every metric's name.
+const PREFIX: &str = crate::identity::IDENTITY.ident;
+
+/// What a [`Metric`] measures.
+#[derive(Debug)]
+pub enum Kind {
+    /// A count that only goes up.
+    Counter,
+    /// Observed values, counted into buckets with these upper bounds.
+    Histogram(&'static [f64]),
+}
+
+/// A metric's definition; its samples are kept by a [`Registry`].
+#[derive(Debug)]
+pub struct Metric {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: Kind,
+}
+
+pub const COMMAND_RUNS: Metric = Metric {
+    name: "command_runs_total",
+    help: "Subcommand runs, by subcommand and outcome.",
+    kind: Kind::Counter,
+};
+
+pub const COMMAND_DURATION: Metric = Metric {
+    name: "command_duration_seconds",
+    help: "How long subcommands ran, by subcommand.",
+    kind: Kind::Histogram(&[
+        0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+    ]),
+};
+
+pub const SETTINGS_CACHE_HITS: Metric = Metric {
+    name: "settings_cache_hits_total",
+    help: "Settings lookups answered from an already parsed config file.",
+    kind: Kind::Counter,
+};
+
+pub const SETTINGS_CACHE_MISSES: Metric = Metric {
+    name: "settings_cache_misses_total",
+    help: "Settings lookups that read and parsed a config file.",
+    kind: Kind::Counter,
+};
+
+pub const REMOTE_FETCH_FAILURES: Metric = Metric {
+    name: "remote_fetch_failures_total",
+    help: "Failed requests to remote services, by target.",
+    kind: Kind::Counter,
+};
+
+/// Every metric, in the order they are rendered.
+const METRICS: &[&Metric] = &[
+    &COMMAND_RUNS,
+    &COMMAND_DURATION,
+    &SETTINGS_CACHE_HITS,
+    &SETTINGS_CACHE_MISSES,
+    &REMOTE_FETCH_FAILURES,
+];
+
+/// The samples of one metric with one set of labels.
+#[derive(Debug, Default)]
+struct Series {
+    /// Per bucket of a histogram, the values at or below its bound.
+    buckets: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+/// Samples of [`METRICS`], keyed by metric name and rendered labels.
+#[derive(Debug, Default)]
+pub struct Registry {
+    series: Mutex<BTreeMap<(&'static str, String), Series>>,
+}
+
+/// The registry of the process, used by the free functions of this module.
+static GLOBAL: Registry = Registry::new();
+
+impl Registry {
+    pub const fn new() -> Self {
+        Registry {
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Adds one to the counter `metric`.
+    pub fn increment(&self, metric: &'static Metric, labels: &[(&str, &str)]) {
+        self.observe(metric, labels, 1.0);
+    }
+
+    /// Records `value`: added to a counter, or counted into a histogram's buckets.
+    pub fn observe(&self, metric: &'static Metric, labels: &[(&str, &str)], value: f64) {
+        let mut series = self.series.lock().expect("lock is not poisoned");
+        let series = series
+            .entry((metric.name, render_labels(labels)))
+            .or_default();
+        if let Kind::Histogram(bounds) = metric.kind {
+            series.buckets.resize(bounds.len(), 0);
+            for (bucket, bound) in series.buckets.iter_mut().zip(bounds) {
+                if value <= *bound {
+                    *bucket += 1;
+                }
+            }
+        }
+        series.count += 1;
+        series.sum += value;
+    }
+
+    /// Renders every metric with samples in Prometheus' text exposition format.
+    pub fn render(&self) -> String {
+        let series = self.series.lock().expect("lock is not poisoned");
+        let mut out = String::new();
+        for metric in METRICS {
+            let mut samples = series
+                .range((metric.name, String::new())..)
+                .take_while(|((name, _), _)| *name == metric.name)
+                .peekable();
+            if samples.peek().is_none() {
+                continue;
+            }
+            let name = format!("{}_{}", PREFIX, metric.name);
+            let kind = match metric.kind {
+                Kind::Counter => "counter",
+                Kind::Histogram(_) => "histogram",
+            };
+            let _ = writeln!(out, "# HELP {} {}", name, metric.help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for ((_, labels), series) in samples {
+                let plain = braced(labels, None);
+                match metric.kind {
+                    Kind::Counter => {
+                        let _ = writeln!(out, "{}{} {}", name, plain, series.sum);
+                    }
+                    Kind::Histogram(bounds) => {
+                        let les = bounds
+                            .iter()
+                            .map(f64::to_string)
+                            .chain(["+Inf".to_string()]);
+                        let counts = series.buckets.iter().chain([&series.count]);
+                        for (le, count) in les.zip(counts) {
+                            let labels = braced(labels, Some(&le));
+                            let _ = writeln!(out, "{}_bucket{} {}", name, labels, count);
+                        }
+                        let _ = writeln!(out, "{}_sum{} {}", name, plain, series.sum);
+                        let _ = writeln!(out, "{}_count{} {}", name, plain, series.count);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Adds one to the counter `metric` of the process.
+pub fn increment(metric: &'static Metric, labels: &[(&str, &str)]) {
+    GLOBAL.increment(metric, labels);
+}
+
+/// Records `value` for `metric` of the process.
+pub fn observe(metric: &'static Metric, labels: &[(&str, &str)], value: f64) {
+    GLOBAL.observe(metric, labels, value);
+}
+
+/// Renders the metrics of the process.
+pub fn render() -> String {
+    GLOBAL.render()
+}
+
+/// Formats `labels` as `name="value"` pairs, without the braces.
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    labels
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Wraps rendered `labels` in braces, adding a histogram bucket's `le`.
+fn braced(labels: &str, le: Option<&str>) -> String {
+    let le = le.map(|le| format!("le=\"{}\"", le));
+    let all: Vec<&str> = [Some(labels), le.as_deref()]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect();
+    match all.is_empty() {
+        true => String::new(),
+        false => format!("{{{}}}", all.join(",")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters_and_histograms() {
+        let registry = Registry::new();
+        registry.increment(&SETTINGS_CACHE_HITS, &[]);
+        registry.increment(&SETTINGS_CACHE_HITS, &[]);
+        registry.observe(&COMMAND_DURATION, &[("command", "config")], 0.02);
+
+        let rendered = registry.render();
+
+        assert!(rendered.contains("# TYPE fixme_settings_cache_hits_total counter\n"));
+        assert!(rendered.contains("\nfixme_settings_cache_hits_total 2\n"));
+        assert!(rendered
+            .contains("fixme_command_duration_seconds_bucket{command=\"config\",le=\"0.01\"} 0\n"));
+        assert!(rendered.contains(
+            "fixme_command_duration_seconds_bucket{command=\"config\",le=\"0.025\"} 1\n"
+        ));
+        assert!(rendered
+            .contains("fixme_command_duration_seconds_bucket{command=\"config\",le=\"+Inf\"} 1\n"));
+        assert!(rendered.contains("fixme_command_duration_seconds_count{command=\"config\"} 1\n"));
+        assert!(!rendered.contains("remote_fetch_failures"));
+    }
+}
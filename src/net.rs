@@ -0,0 +1,288 @@
+//! HTTP helpers shared by the subcommands and background checks that talk
+//! to the network, so that they all honour the same `network.*` settings.
+
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use cor_args::Handler;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use sha2::{Digest, Sha256};
+
+use crate::glyphs::Glyphs;
+use crate::progress::Transfer;
+use crate::values::{self, PathArg};
+use crate::{schema, Error, Result};
+
+/// The `network.*` settings of the run, taken by [`configure`].
+#[derive(Debug)]
+struct Settings {
+    timeout: Option<String>,
+    proxy: Option<String>,
+    ca_file: Option<String>,
+}
+
+static SETTINGS: Mutex<Settings> = Mutex::new(Settings {
+    timeout: None,
+    proxy: None,
+    ca_file: None,
+});
+
+/// Takes the `network.*` settings every later [`client`] is built with from
+/// `settings`, e.g. once the run's settings chain is known.
+pub fn configure(settings: &dyn Handler) {
+    let lookup = |name: &str| {
+        settings
+            .handle_request(name)
+            .or_else(|| schema::lookup(name).ok()?.default.map(str::to_string))
+    };
+    *SETTINGS.lock().expect("lock is not poisoned") = Settings {
+        timeout: lookup("network.timeout"),
+        proxy: lookup("network.proxy"),
+        ca_file: lookup("network.ca_file"),
+    };
+}
+
+/// Builds the HTTP client for talking to the network, configured by the
+/// `network.*` settings: a user agent naming the application and its
+/// version, `network.timeout` for connecting and for each read, the proxy
+/// of `network.proxy` or else of `ALL_PROXY`, `HTTPS_PROXY` or `HTTP_PROXY`,
+/// and the certificates of `network.ca_file` trusted besides the built-in
+/// roots.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidValue`] if one of the settings is invalid.
+pub fn client() -> Result<ureq::Agent> {
+    build(&SETTINGS.lock().expect("lock is not poisoned"))
+}
+
+fn build(settings: &Settings) -> Result<ureq::Agent> {
+    let invalid = |key: &str, reason: String| Error::InvalidValue {
+        key: key.to_string(),
+        reason,
+    };
+    let identity = crate::identity::IDENTITY;
+    let mut builder = ureq::AgentBuilder::new()
+        .user_agent(&format!("{}/{}", identity.name, identity.version))
+        .try_proxy_from_env(true);
+    if let Some(timeout) = &settings.timeout {
+        let timeout = values::duration(timeout).map_err(|e| invalid("network.timeout", e))?;
+        builder = builder.timeout_connect(timeout).timeout_read(timeout);
+    }
+    if let Some(proxy) = &settings.proxy {
+        let proxy = ureq::Proxy::new(proxy).map_err(|e| invalid("network.proxy", e.to_string()))?;
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = &settings.ca_file {
+        let path = PathArg::new()
+            .file()
+            .parse(path)
+            .map_err(|e| invalid("network.ca_file", e))?;
+        let config = tls_config(&path).map_err(|e| invalid("network.ca_file", e))?;
+        builder = builder.tls_config(Arc::new(config));
+    }
+    Ok(builder.build())
+}
+
+/// Trusts the certificates in the PEM file at `path` besides the built-in roots.
+fn tls_config(path: &Path) -> std::result::Result<rustls::ClientConfig, String> {
+    let mut roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let certificates = CertificateDer::pem_file_iter(path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    for certificate in certificates {
+        let certificate =
+            certificate.map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        roots.add(certificate).map_err(|e| e.to_string())?;
+    }
+    rustls::ClientConfig::builder_with_provider(rustls::crypto::ring::default_provider().into())
+        .with_protocol_versions(&[&rustls::version::TLS12, &rustls::version::TLS13])
+        .map_err(|e| e.to_string())
+        .map(|builder| builder.with_root_certificates(roots).with_no_client_auth())
+}
+
+/// Downloads `url` to `path`, showing its progress, and returns the size of
+/// the file in bytes.
+///
+/// The content is streamed to a `.part` file next to `path` and only moved
+/// into place once complete and, given `sha256`, once its checksum matches;
+/// a `.part` file left by an interrupted download is resumed if the server
+/// supports it. The [`client`] sets how long a download may stall.
+///
+/// # Errors
+///
+/// Returns an error if the request fails, the file cannot be written, or
+/// the checksum does not match, in which case nothing is kept.
+pub fn download(url: &str, path: &Path, sha256: Option<&str>, glyphs: &Glyphs) -> Result<u64> {
+    let part = part_path(path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(Error::io(parent))?;
+    }
+    let partial = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+    let mut request = client()?.get(url);
+    if partial > 0 {
+        request = request.set("Range", &format!("bytes={}-", partial));
+    }
+    match request.call() {
+        // What was downloaded before is already the whole file.
+        Err(ureq::Error::Status(416, _)) if partial > 0 => {}
+        Err(e) => return Err(failed(url, e.to_string())),
+        Ok(response) => {
+            let resumed = response.status() == 206;
+            let done = if resumed { partial } else { 0 };
+            let len = response
+                .header("Content-Length")
+                .and_then(|len| len.parse::<u64>().ok())
+                .map(|len| len + done);
+            let mut options = OpenOptions::new();
+            match resumed {
+                true => options.append(true),
+                false => options.write(true).create(true).truncate(true),
+            };
+            let mut file = options.open(&part).map_err(Error::io(&part))?;
+            let progress = Transfer::start(url, done, len, glyphs);
+            let mut reader = response.into_reader();
+            let mut buffer = vec![0; 64 * 1024];
+            loop {
+                let read = reader
+                    .read(&mut buffer)
+                    .map_err(|e| failed(url, e.to_string()))?;
+                if read == 0 {
+                    break;
+                }
+                file.write_all(&buffer[..read]).map_err(Error::io(&part))?;
+                progress.inc(read as u64);
+            }
+            file.sync_all().map_err(Error::io(&part))?;
+        }
+    }
+    if let Some(expected) = sha256 {
+        let actual = checksum(&part)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            let _ = std::fs::remove_file(&part);
+            return Err(Error::Subcommand(format!(
+                "the checksum of {} does not match: expected {}, got {}",
+                url, expected, actual
+            )));
+        }
+    }
+    std::fs::rename(&part, path).map_err(Error::io(path))?;
+    std::fs::metadata(path)
+        .map(|m| m.len())
+        .map_err(Error::io(path))
+}
+
+fn failed(url: &str, reason: String) -> Error {
+    crate::metrics::increment(
+        &crate::metrics::REMOTE_FETCH_FAILURES,
+        &[("target", "download")],
+    );
+    Error::Subcommand(format!("cannot download {}: {}", url, reason))
+}
+
+/// Returns where `path` is downloaded to until complete.
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// Returns the SHA-256 checksum of the file at `path`, in hex.
+fn checksum(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path).map_err(Error::io(path))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(Error::io(path))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    use super::*;
+
+    /// Serves `body` to `requests` clients, honouring `Range: bytes=N-`.
+    fn serve(body: &'static [u8], requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut from = 0;
+                for line in BufReader::new(&stream).lines() {
+                    let line = line.unwrap();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(range) = line.strip_prefix("Range: bytes=") {
+                        from = range.trim_end_matches('-').parse().unwrap();
+                    }
+                }
+                let status = if from > 0 {
+                    "206 Partial Content"
+                } else {
+                    "200 OK"
+                };
+                let rest = &body[from..];
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    rest.len()
+                )
+                .unwrap();
+                stream.write_all(rest).unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_build_checks_settings() {
+        let settings = |timeout: &str, proxy: &str| Settings {
+            timeout: Some(timeout.to_string()),
+            proxy: Some(proxy.to_string()),
+            ca_file: None,
+        };
+
+        assert!(build(&settings("5s", "http://proxy:3128")).is_ok());
+        assert!(matches!(
+            build(&settings("soon", "http://proxy:3128")),
+            Err(Error::InvalidValue { key, .. }) if key == "network.timeout"
+        ));
+        let missing = Settings {
+            ca_file: Some("/nonexistent/ca.pem".to_string()),
+            ..settings("5s", "http://proxy:3128")
+        };
+        assert!(build(&missing).is_err());
+    }
+
+    #[test]
+    fn test_download_resumes_and_verifies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(part_path(&path), b"hello ").unwrap();
+        let sha256 = format!("{:x}", Sha256::digest(b"hello world"));
+
+        let url = serve(b"hello world", 2);
+        let size = download(&url, &path, Some(&sha256), &crate::glyphs::ASCII).unwrap();
+
+        assert_eq!(11, size);
+        assert_eq!(b"hello world", std::fs::read(&path).unwrap().as_slice());
+        assert!(!part_path(&path).exists());
+
+        let wrong = download(
+            &url,
+            &path.with_extension("bad"),
+            Some("00"),
+            &crate::glyphs::ASCII,
+        );
+        assert!(wrong.is_err());
+        assert!(!path.with_extension("bad").exists());
+    }
+}
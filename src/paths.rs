@@ -0,0 +1,171 @@
+//! The application's directories, following the XDG base directory
+//! specification on Linux and each platform's conventions elsewhere.
+//!
+//! Everything the application keeps on disk lives under one of these, so
+//! that it is found where users and packagers expect it.
+
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+
+use crate::identity::IDENTITY;
+use crate::{Error, Result};
+
+/// Identifies the application to [`ProjectDirs`] by its reverse-DNS name.
+fn project() -> Option<ProjectDirs> {
+    ProjectDirs::from(IDENTITY.qualifier, IDENTITY.organization, IDENTITY.name)
+}
+
+/// Used when the user has no home directory, e.g. some service accounts.
+fn fallback(kind: &str) -> PathBuf {
+    std::env::temp_dir().join(IDENTITY.name).join(kind)
+}
+
+/// Returns the directory of the configuration files, e.g.
+/// `$XDG_CONFIG_HOME/fixme` or `~/.config/fixme`.
+pub fn config_dir() -> PathBuf {
+    project()
+        .map(|dirs| dirs.config_dir().to_path_buf())
+        .unwrap_or_else(|| fallback("config"))
+}
+
+/// Returns the directory of data that can be recreated at any time, e.g.
+/// `$XDG_CACHE_HOME/fixme` or `~/.cache/fixme`.
+pub fn cache_dir() -> PathBuf {
+    project()
+        .map(|dirs| dirs.cache_dir().to_path_buf())
+        .unwrap_or_else(|| fallback("cache"))
+}
+
+/// Returns the directory of data the user would miss, such as installed
+/// plugins, e.g. `$XDG_DATA_HOME/fixme` or `~/.local/share/fixme`.
+pub fn data_dir() -> PathBuf {
+    project()
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or_else(|| fallback("data"))
+}
+
+/// Returns the directory of state kept between runs, such as logs, e.g.
+/// `$XDG_STATE_HOME/fixme` or `~/.local/state/fixme`. Platforms without
+/// such a directory use the local data directory.
+pub fn state_dir() -> PathBuf {
+    project()
+        .map(|dirs| {
+            dirs.state_dir()
+                .unwrap_or_else(|| dirs.data_local_dir())
+                .to_path_buf()
+        })
+        .unwrap_or_else(|| fallback("state"))
+}
+
+/// Returns the directory of sockets and pidfiles: `$XDG_RUNTIME_DIR/fixme`,
+/// or e.g. `fixme-1000` under the system's temporary directory, named after
+/// the user's id as other users share it.
+///
+/// Create it with [`create_private_dir`] before use.
+pub fn runtime_dir() -> PathBuf {
+    project()
+        .and_then(|dirs| dirs.runtime_dir().map(|dir| dir.to_path_buf()))
+        .unwrap_or_else(|| std::env::temp_dir().join(shared_name()))
+}
+
+#[cfg(unix)]
+fn shared_name() -> String {
+    // SAFETY: getuid has no preconditions and cannot fail.
+    format!("{}-{}", IDENTITY.name, unsafe { libc::getuid() })
+}
+
+#[cfg(not(unix))]
+fn shared_name() -> String {
+    IDENTITY.name.to_string()
+}
+
+/// Creates `dir` accessible by the current user only, or checks with
+/// [`check_private_dir`] that it already is.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if `dir` cannot be created, or
+/// [`Error::Subcommand`] if another user may have put files in it.
+pub fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir).map_err(Error::io(dir))?;
+    check_private_dir(dir)
+}
+
+/// Checks that `dir` is a directory owned by the current user that no one
+/// else may access, so that the sockets and pidfiles in it can be trusted.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if `dir` cannot be examined, or
+/// [`Error::Subcommand`] if it is not private.
+#[cfg(unix)]
+pub fn check_private_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::symlink_metadata(dir).map_err(Error::io(dir))?;
+    // SAFETY: getuid has no preconditions and cannot fail.
+    let uid = unsafe { libc::getuid() };
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(Error::Subcommand(format!(
+            "{} must be a directory of yours that only you can access; remove it or run chmod 700 on it",
+            dir.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Always succeeds: elsewhere the per-user directories are private already.
+#[cfg(not(unix))]
+pub fn check_private_dir(_dir: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Returns `path` for display, with the user's home directory shortened to
+/// `~`, e.g. `~/.config/fixme/config.json`.
+pub fn abbreviate(path: &Path) -> String {
+    let home = directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+    match home.and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) => Path::new("~").join(rest).display().to_string(),
+        None => path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_dir_is_created_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let parent = tempfile::tempdir().unwrap();
+        let dir = parent.path().join("run");
+
+        create_private_dir(&dir).unwrap();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(0o700, mode & 0o777);
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(check_private_dir(&dir).is_err());
+    }
+
+    #[test]
+    fn test_dirs_are_absolute() {
+        for dir in [
+            config_dir(),
+            cache_dir(),
+            data_dir(),
+            state_dir(),
+            runtime_dir(),
+        ] {
+            assert!(dir.is_absolute(), "{}", dir.display());
+        }
+        assert_ne!(config_dir(), cache_dir());
+    }
+}
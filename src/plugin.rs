@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{Error, Result};
+
+/// Returns the prefix of executables providing external subcommands, the
+/// application's name and a dash: `fixme-foo` is run for `fixme foo`.
+pub fn prefix() -> String {
+    format!("{}-", crate::identity::IDENTITY.name)
+}
+
+/// Name of the file in [`plugins_dir`] recording the installed plugins.
+pub const MANIFEST: &str = "plugins.json";
+
+/// A plugin installed with `plugin install`, as recorded in the [`MANIFEST`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Installed {
+    /// Where the plugin was installed from, and is updated from.
+    pub source: PathBuf,
+    /// SHA-256 checksum of the installed executable, in hex.
+    pub sha256: String,
+}
+
+/// Returns the directory plugins are installed into: `plugins` in the
+/// [data directory](crate::paths::data_dir).
+pub fn plugins_dir() -> PathBuf {
+    crate::paths::data_dir().join("plugins")
+}
+
+/// Returns the executable implementing the external subcommand `name`,
+/// preferring installed plugins over those found on `PATH`.
+pub fn locate(name: &str) -> Option<PathBuf> {
+    find(name, Some(plugins_dir().as_os_str()))
+        .or_else(|| find(name, std::env::var_os("PATH").as_deref()))
+}
+
+/// Reads the plugins recorded in the manifest of `dir`, keyed by subcommand name.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if the manifest exists but cannot be read or parsed.
+pub fn manifest(dir: &Path) -> Result<BTreeMap<String, Installed>> {
+    let path = dir.join(MANIFEST);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| Error::io(&path)(std::io::Error::new(std::io::ErrorKind::InvalidData, e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(Error::io(&path)(e)),
+    }
+}
+
+/// Writes `plugins` as the manifest of `dir`.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if the manifest cannot be written.
+pub fn write_manifest(dir: &Path, plugins: &BTreeMap<String, Installed>) -> Result<()> {
+    let path = dir.join(MANIFEST);
+    let content = serde_json::to_string_pretty(plugins).expect("manifest serializes");
+    std::fs::create_dir_all(dir).map_err(Error::io(dir))?;
+    std::fs::write(&path, content + "\n").map_err(Error::io(&path))
+}
+
+/// Returns the hex-encoded SHA-256 checksum of `content`.
+pub fn sha256(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Checks `content` against a minisign `signature` made with the secret key
+/// matching the base64 `public_key`.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if the key or signature are malformed or
+/// the signature does not match.
+pub fn verify_signature(content: &[u8], signature: &str, public_key: &str) -> Result<()> {
+    let invalid =
+        |e: minisign_verify::Error| Error::Subcommand(format!("invalid signature: {}", e));
+    let public_key = minisign_verify::PublicKey::from_base64(public_key).map_err(invalid)?;
+    let signature = minisign_verify::Signature::decode(signature).map_err(invalid)?;
+    public_key
+        .verify(content, &signature, false)
+        .map_err(invalid)
+}
+
+/// Checks that `name` can name a plugin's subcommand: it must not be empty,
+/// `.`, `..` or contain a path separator, which would install the
+/// executable outside the plugins directory.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if it cannot.
+pub fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.chars().any(std::path::is_separator) {
+        return Err(Error::Subcommand(format!(
+            "'{}' cannot name a subcommand",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Copies `content` into `dir` as the executable for the subcommand `name`,
+/// returning its path.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if `name` fails [`check_name`], or
+/// [`Error::Io`] if the file cannot be written.
+pub fn install(dir: &Path, name: &str, content: &[u8]) -> Result<PathBuf> {
+    check_name(name)?;
+    std::fs::create_dir_all(dir).map_err(Error::io(dir))?;
+    let path = dir.join(format!(
+        "{}{}{}",
+        prefix(),
+        name,
+        std::env::consts::EXE_SUFFIX
+    ));
+    std::fs::write(&path, content).map_err(Error::io(&path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .map_err(Error::io(&path))?;
+    }
+    Ok(path)
+}
+
+/// Returns the name of the subcommand provided by the executable at `path`:
+/// its file name without the [`prefix`] and extension, if it has the prefix.
+pub fn subcommand_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    stem.strip_prefix(&prefix())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+/// Lists the external subcommands found in the directories of `path`, a
+/// `PATH`-style list, with their executables; earlier directories win.
+pub fn discover(path: Option<&OsStr>) -> BTreeMap<String, PathBuf> {
+    let mut found = BTreeMap::new();
+    for dir in path.map(std::env::split_paths).into_iter().flatten() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+            if let Some(name) = subcommand_name(&path).filter(|_| is_executable(&path)) {
+                found.entry(name).or_insert(path);
+            }
+        }
+    }
+    found
+}
+
+/// Returns the path of the executable for the subcommand `name` in `dir`.
+pub fn executable(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!(
+        "{}{}{}",
+        prefix(),
+        name,
+        std::env::consts::EXE_SUFFIX
+    ))
+}
+
+/// Searches the directories of `path`, a `PATH`-style list, for the
+/// executable implementing the external subcommand `name`.
+pub fn find(name: &str, path: Option<&OsStr>) -> Option<PathBuf> {
+    std::env::split_paths(path?)
+        .map(|dir| executable(&dir, name))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Runs the external subcommand at `program` with `args`, adding `env` to
+/// its environment.
+///
+/// On Unix the process is replaced, as with `exec`, so this only returns on
+/// failure. Elsewhere the process exits with the subcommand's exit status.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if `program` cannot be run.
+pub fn exec(program: &Path, args: &[OsString], env: &[(String, String)]) -> Result<()> {
+    let mut command = std::process::Command::new(program);
+    command.args(args).envs(env.iter().map(|(k, v)| (k, v)));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        Err(Error::io(program)(command.exec()))
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command.status().map_err(Error::io(program))?;
+        std::process::exit(
+            status
+                .code()
+                .unwrap_or(crate::ExitCode::Runtime.code().into()),
+        )
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_requires_executable() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let plugin = dir.path().join("fixme-foo");
+        std::fs::write(&plugin, "#!/bin/sh\n").unwrap();
+        let path = std::env::join_paths([dir.path()]).unwrap();
+
+        assert_eq!(None, find("foo", Some(&path)));
+
+        std::fs::set_permissions(&plugin, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(Some(plugin), find("foo", Some(&path)));
+        assert_eq!(None, find("bar", Some(&path)));
+        assert_eq!(
+            vec!["foo"],
+            discover(Some(&path)).into_keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_install_records_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = install(dir.path(), "foo", b"#!/bin/sh\n").unwrap();
+        let mut plugins = BTreeMap::new();
+        plugins.insert(
+            "foo".to_string(),
+            Installed {
+                source: "fixme-foo".into(),
+                sha256: sha256(b"#!/bin/sh\n"),
+            },
+        );
+        write_manifest(dir.path(), &plugins).unwrap();
+
+        assert_eq!(Some("foo".to_string()), subcommand_name(&path));
+        assert!(is_executable(&path));
+        assert_eq!(plugins, manifest(dir.path()).unwrap());
+        assert_eq!(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            sha256(b"")
+        );
+    }
+
+    #[test]
+    fn test_install_refuses_names_outside_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for name in ["", ".", "..", "../foo", "foo/bar"] {
+            assert!(
+                install(dir.path(), name, b"#!/bin/sh\n").is_err(),
+                "{:?}",
+                name
+            );
+        }
+        assert!(check_name("foo-bar").is_ok());
+    }
+}
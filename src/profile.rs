@@ -0,0 +1,73 @@
+//! Records the spans of a run for `--profile-out` as a Chrome trace, which
+//! Perfetto, `chrome://tracing` and speedscope show as a flame graph.
+
+use std::path::Path;
+
+use crate::{Error, Result};
+
+/// Records the spans entered on the current thread, and on the threads that
+/// adopt its dispatcher, until dropped, then writes them out.
+pub struct Profile {
+    #[cfg(feature = "profiling")]
+    _default: tracing::dispatcher::DefaultGuard,
+    /// Dropped last, so that the trace is complete when written.
+    #[cfg(feature = "profiling")]
+    _flush: tracing_chrome::FlushGuard,
+}
+
+impl Profile {
+    /// Stops recording and writes the trace, as dropping the profile does.
+    pub fn finish(self) {}
+}
+
+/// Starts recording a profile to `path`.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if `path` cannot be created, or
+/// [`Error::Subcommand`] in a build without the `profiling` feature.
+#[cfg(feature = "profiling")]
+pub fn start(path: &Path) -> Result<Profile> {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let file = std::fs::File::create(path).map_err(Error::io(path))?;
+    let (layer, flush) = tracing_chrome::ChromeLayerBuilder::new()
+        .writer(std::io::BufWriter::new(file))
+        .include_args(true)
+        .build();
+    let subscriber = tracing_subscriber::registry().with(layer);
+    Ok(Profile {
+        _default: tracing::subscriber::set_default(subscriber),
+        _flush: flush,
+    })
+}
+
+/// Always fails: there is no profiler without the `profiling` feature.
+#[cfg(not(feature = "profiling"))]
+pub fn start(_path: &Path) -> Result<Profile> {
+    Err(Error::Subcommand(
+        "cannot record a profile: built without the profiling feature".to_string(),
+    ))
+}
+
+#[cfg(all(test, feature = "profiling"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_records_spans() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trace.json");
+
+        let profile = start(&path).unwrap();
+        tracing::info_span!("run", subcommand = %"fixme1").in_scope(|| {});
+        drop(profile);
+
+        let trace: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let events = trace.as_array().unwrap();
+        assert!(events
+            .iter()
+            .any(|event| event["name"] == "run" && event["args"]["subcommand"] == "fixme1"));
+    }
+}
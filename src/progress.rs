@@ -0,0 +1,230 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "progress")]
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use log::info;
+
+use crate::glyphs::Glyphs;
+
+/// How often a [`Heartbeat`] logs that its task is still running.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Reports that an operation without measurable progress is still running.
+///
+/// While alive, a heartbeat shows a spinner with the elapsed time on stderr,
+/// when stderr is a terminal and the `progress` feature is enabled, and logs
+/// `still working on <task>` at info level every interval. Dropping or
+/// [finishing](Heartbeat::finish) it clears the spinner.
+pub struct Heartbeat {
+    spinner: ProgressBar,
+    stop: Option<Sender<()>>,
+    logger: Option<JoinHandle<()>>,
+}
+
+impl Heartbeat {
+    /// Starts reporting on `task`, logging every [`HEARTBEAT_INTERVAL`] and
+    /// animating the spinner with `glyphs`.
+    pub fn start<S: Into<String>>(task: S, glyphs: &Glyphs) -> Self {
+        Heartbeat::with_interval(task, HEARTBEAT_INTERVAL, glyphs)
+    }
+
+    /// Starts reporting on `task`, logging every `interval`.
+    pub fn with_interval<S: Into<String>>(task: S, interval: Duration, glyphs: &Glyphs) -> Self {
+        let task = task.into();
+        let spinner = spinner(&task, glyphs);
+
+        let (stop, stopped) = mpsc::channel();
+        let started = Instant::now();
+        let logger = std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                info!(
+                    "still working on {} ({}s elapsed)",
+                    task,
+                    started.elapsed().as_secs()
+                );
+            }
+        });
+
+        Heartbeat {
+            spinner,
+            stop: Some(stop),
+            logger: Some(logger),
+        }
+    }
+
+    /// Stops reporting and clears the spinner.
+    pub fn finish(self) {}
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(logger) = self.logger.take() {
+            let _ = logger.join();
+        }
+        self.spinner.finish_and_clear();
+    }
+}
+
+/// Shows how many of a known number of items are done, as a bar on stderr
+/// when stderr is a terminal and the `progress` feature is enabled.
+///
+/// Output written while the bar is shown should go through
+/// [`Tally::suspend`], so it does not garble the bar.
+pub struct Tally {
+    bar: ProgressBar,
+}
+
+impl Tally {
+    /// Starts counting `len` items, describing them as `what`.
+    pub fn start(what: &str, len: usize, glyphs: &Glyphs) -> Self {
+        Tally {
+            bar: bar(what, len, glyphs),
+        }
+    }
+
+    /// Counts one more item as done.
+    pub fn inc(&self) {
+        self.bar.inc(1);
+    }
+
+    /// Hides the bar while `f` writes output, then redraws it.
+    pub fn suspend<F: FnOnce() -> R, R>(&self, f: F) -> R {
+        self.bar.suspend(f)
+    }
+}
+
+impl Drop for Tally {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Shows how many bytes of a transfer are done, as a bar on stderr when
+/// stderr is a terminal and the `progress` feature is enabled.
+pub struct Transfer {
+    bar: ProgressBar,
+}
+
+impl Transfer {
+    /// Starts showing the transfer of `what`, `done` of `len` bytes in; a
+    /// transfer of unknown length shows the bytes so far instead of a bar.
+    pub fn start(what: &str, done: u64, len: Option<u64>, glyphs: &Glyphs) -> Self {
+        Transfer {
+            bar: transfer(what, done, len, glyphs),
+        }
+    }
+
+    /// Counts `bytes` more as transferred.
+    pub fn inc(&self, bytes: u64) {
+        self.bar.inc(bytes);
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Draws on stderr, unless `glyphs` are not [animated](Glyphs::animated).
+#[cfg(feature = "progress")]
+fn target(glyphs: &Glyphs) -> ProgressDrawTarget {
+    match glyphs.animated {
+        true => ProgressDrawTarget::stderr(),
+        false => ProgressDrawTarget::hidden(),
+    }
+}
+
+#[cfg(feature = "progress")]
+fn spinner(task: &str, glyphs: &Glyphs) -> ProgressBar {
+    let spinner = ProgressBar::with_draw_target(None, target(glyphs));
+    spinner.set_style(
+        ProgressStyle::with_template("{spinner} {msg} ({elapsed})")
+            .expect("spinner template is valid")
+            .tick_strings(glyphs.spinner),
+    );
+    spinner.set_message(task.to_string());
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner
+}
+
+#[cfg(feature = "progress")]
+fn bar(what: &str, len: usize, glyphs: &Glyphs) -> ProgressBar {
+    let bar = ProgressBar::with_draw_target(Some(len as u64), target(glyphs));
+    bar.set_style(
+        ProgressStyle::with_template("[{bar:30}] {pos}/{len} {msg}")
+            .expect("progress template is valid")
+            .progress_chars(glyphs.bar),
+    );
+    bar.set_message(what.to_string());
+    bar
+}
+
+#[cfg(feature = "progress")]
+fn transfer(what: &str, done: u64, len: Option<u64>, glyphs: &Glyphs) -> ProgressBar {
+    let bar = ProgressBar::with_draw_target(len, target(glyphs));
+    let template = match len {
+        Some(_) => "[{bar:30}] {bytes}/{total_bytes} {msg} ({bytes_per_sec})",
+        None => "{spinner} {bytes} {msg} ({bytes_per_sec})",
+    };
+    bar.set_style(
+        ProgressStyle::with_template(template)
+            .expect("transfer template is valid")
+            .progress_chars(glyphs.bar)
+            .tick_strings(glyphs.spinner),
+    );
+    bar.set_message(what.to_string());
+    bar.set_position(done);
+    bar
+}
+
+/// Stands in for `indicatif::ProgressBar` without the `progress` feature,
+/// drawing nothing.
+#[cfg(not(feature = "progress"))]
+struct ProgressBar;
+
+#[cfg(not(feature = "progress"))]
+impl ProgressBar {
+    fn inc(&self, _delta: u64) {}
+
+    fn suspend<F: FnOnce() -> R, R>(&self, f: F) -> R {
+        f()
+    }
+
+    fn finish_and_clear(&self) {}
+}
+
+#[cfg(not(feature = "progress"))]
+fn spinner(_task: &str, _glyphs: &Glyphs) -> ProgressBar {
+    ProgressBar
+}
+
+#[cfg(not(feature = "progress"))]
+fn bar(_what: &str, _len: usize, _glyphs: &Glyphs) -> ProgressBar {
+    ProgressBar
+}
+
+#[cfg(not(feature = "progress"))]
+fn transfer(_what: &str, _done: u64, _len: Option<u64>, _glyphs: &Glyphs) -> ProgressBar {
+    ProgressBar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_stops_promptly() {
+        let started = Instant::now();
+
+        let heartbeat =
+            Heartbeat::with_interval("testing", Duration::from_secs(60), &crate::glyphs::ASCII);
+        std::thread::sleep(Duration::from_millis(20));
+        heartbeat.finish();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,194 @@
+use std::io::IsTerminal;
+
+use dialoguer::{Confirm, FuzzySelect, Input, Select};
+
+use crate::{Error, Result};
+
+/// How questions to the user are answered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PromptMode {
+    /// Ask on the terminal.
+    #[default]
+    Interactive,
+    /// Answer yes to confirmations and take the default elsewhere (`--yes`).
+    AssumeYes,
+    /// Never ask; fail with [`Error::NoInput`] instead (`--no-input`).
+    NoInput,
+}
+
+/// Asks the user questions on stderr, honouring `--yes` and `--no-input`.
+///
+/// Prompting also fails with [`Error::NoInput`] when stdin is not a terminal,
+/// so scripts never hang waiting for an answer.
+#[derive(Clone, Debug, Default)]
+pub struct Prompter {
+    mode: PromptMode,
+}
+
+impl Prompter {
+    pub fn new(mode: PromptMode) -> Self {
+        Prompter { mode }
+    }
+
+    pub fn mode(&self) -> PromptMode {
+        self.mode
+    }
+
+    /// Asks a yes/no question, such as before a destructive operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoInput`] if the user cannot be asked.
+    pub fn confirm(&self, prompt: &str, default: bool) -> Result<bool> {
+        if self.mode == PromptMode::AssumeYes {
+            return Ok(true);
+        }
+        self.ensure_interactive(prompt)?;
+        Confirm::new()
+            .with_prompt(prompt)
+            .default(default)
+            .interact()
+            .map_err(into_error)
+    }
+
+    /// Asks the user to pick one of `items`, returning its index.
+    ///
+    /// With `--yes` the `default` item is picked without asking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoInput`] if the user cannot be asked, or with `--yes`
+    /// when there is no default.
+    pub fn select<T: ToString>(
+        &self,
+        prompt: &str,
+        items: &[T],
+        default: Option<usize>,
+    ) -> Result<usize> {
+        if let (PromptMode::AssumeYes, Some(default)) = (self.mode, default) {
+            return Ok(default);
+        }
+        self.ensure_interactive(prompt)?;
+        let mut select = Select::new().with_prompt(prompt).items(items);
+        if let Some(default) = default {
+            select = select.default(default);
+        }
+        select.interact().map_err(into_error)
+    }
+
+    /// Asks the user to pick one of `items`, narrowing the list down as they
+    /// type, returning its index.
+    ///
+    /// With `--yes` the `default` item is picked without asking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoInput`] if the user cannot be asked, or with `--yes`
+    /// when there is no default.
+    pub fn fuzzy_select<T: ToString>(
+        &self,
+        prompt: &str,
+        items: &[T],
+        default: Option<usize>,
+    ) -> Result<usize> {
+        if let (PromptMode::AssumeYes, Some(default)) = (self.mode, default) {
+            return Ok(default);
+        }
+        self.ensure_interactive(prompt)?;
+        let mut select = FuzzySelect::new().with_prompt(prompt).items(items);
+        if let Some(default) = default {
+            select = select.default(default);
+        }
+        select.interact().map_err(into_error)
+    }
+
+    /// Asks for a line of text, which must satisfy `validate`; an empty answer
+    /// is passed to `validate` like any other.
+    ///
+    /// With `--yes` the `default` is taken without asking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoInput`] if the user cannot be asked, or with `--yes`
+    /// when there is no default.
+    pub fn input<F>(&self, prompt: &str, default: Option<&str>, validate: F) -> Result<String>
+    where
+        F: Fn(&String) -> std::result::Result<(), String>,
+    {
+        if let (PromptMode::AssumeYes, Some(default)) = (self.mode, default) {
+            return Ok(default.to_string());
+        }
+        self.ensure_interactive(prompt)?;
+        let mut input = Input::<String>::new()
+            .with_prompt(prompt)
+            .allow_empty(true)
+            .validate_with(|value: &String| validate(value));
+        if let Some(default) = default {
+            input = input.default(default.to_string());
+        }
+        input.interact_text().map_err(into_error)
+    }
+
+    /// Asks for a sensitive value without echoing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoInput`] if the user cannot be asked; `--yes` cannot
+    /// answer for a secret.
+    pub fn password(&self, prompt: &str) -> Result<String> {
+        if self.mode == PromptMode::AssumeYes {
+            return Err(Error::NoInput {
+                prompt: prompt.to_string(),
+            });
+        }
+        self.ensure_interactive(prompt)?;
+        prompt_secret(prompt)
+    }
+
+    fn ensure_interactive(&self, prompt: &str) -> Result<()> {
+        if self.mode == PromptMode::Interactive && std::io::stdin().is_terminal() {
+            Ok(())
+        } else {
+            Err(Error::NoInput {
+                prompt: prompt.to_string(),
+            })
+        }
+    }
+}
+
+/// Reads a line from the terminal without echoing it, after showing `prompt`.
+///
+/// The value never appears on screen nor on the command line, so it cannot end
+/// up in shell history. Prefer [`Prompter::password`], which also honours
+/// `--yes` and `--no-input`.
+pub fn prompt_secret(prompt: &str) -> Result<String> {
+    rpassword::prompt_password(format!("{}: ", prompt)).map_err(Error::Output)
+}
+
+fn into_error(error: dialoguer::Error) -> Error {
+    match error {
+        dialoguer::Error::IO(e) => Error::Output(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompter_answers_without_asking() {
+        let yes = Prompter::new(PromptMode::AssumeYes);
+        assert!(yes.confirm("Overwrite?", false).unwrap());
+        assert_eq!(1, yes.select("Shell", &["bash", "zsh"], Some(1)).unwrap());
+        assert!(matches!(
+            yes.select("Shell", &["bash", "zsh"], None),
+            Err(Error::NoInput { .. })
+        ));
+
+        let no_input = Prompter::new(PromptMode::NoInput);
+        assert!(matches!(
+            no_input.confirm("Overwrite?", true),
+            Err(Error::NoInput { .. })
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use crate::{schema, settings};
+
+/// Re-resolves the settings chain when the process receives SIGHUP, as
+/// administrators expect of long-running tools, logging every setting that
+/// changed.
+///
+/// A changed `verbosity` is applied to logging right away, unless the level
+/// was given on the command line. Dropping the reloader stops handling the
+/// signal. On platforms without Unix signals it does nothing.
+pub struct Reloader {
+    #[cfg(unix)]
+    handle: signal_hook::iterator::Handle,
+    #[cfg(unix)]
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Reloader {
+    /// Starts re-resolving the settings of `layout` on SIGHUP, re-applying the
+    /// log level if `apply_verbosity`, or returns `None`, logging a warning,
+    /// if the handler cannot be installed.
+    #[cfg(unix)]
+    pub fn install(layout: settings::Layout, apply_verbosity: bool) -> Option<Self> {
+        let mut signals = match signal_hook::iterator::Signals::new([signal_hook::consts::SIGHUP]) {
+            Ok(signals) => signals,
+            Err(e) => {
+                log::warn!("cannot handle SIGHUP: {}", e);
+                return None;
+            }
+        };
+        let handle = signals.handle();
+        let mut current = settings::snapshot(&*layout.chain(None));
+        let thread = std::thread::spawn(move || {
+            for _ in signals.forever() {
+                let reloaded = settings::snapshot(&*layout.chain(None));
+                if apply_verbosity && current.get("verbosity") != reloaded.get("verbosity") {
+                    let verbosity = layout.chain(None).handle_request("verbosity");
+                    crate::setup_logging(verbosity.as_deref().unwrap_or("off"));
+                }
+                let changes = changes(&current, &reloaded);
+                if changes.is_empty() {
+                    log::info!("reloaded settings; nothing changed");
+                }
+                for change in &changes {
+                    log::info!("reloaded settings; {}", change);
+                }
+                current = reloaded;
+            }
+        });
+        Some(Reloader {
+            handle,
+            thread: Some(thread),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn install(_layout: settings::Layout, _apply_verbosity: bool) -> Option<Self> {
+        Some(Reloader {})
+    }
+}
+
+impl Drop for Reloader {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            self.handle.close();
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+/// Re-resolves the settings now, as if the process had received SIGHUP.
+#[cfg(unix)]
+pub fn trigger() -> crate::Result<()> {
+    signal_hook::low_level::raise(signal_hook::consts::SIGHUP).map_err(crate::Error::Output)
+}
+
+#[cfg(not(unix))]
+pub fn trigger() -> crate::Result<()> {
+    Err(crate::Error::Subcommand(
+        "reloading the settings is only supported on Unix".to_string(),
+    ))
+}
+
+/// Describes how the settings in `after` differ from those in `before`,
+/// masking the values of [secret](schema::Key::secret) keys.
+pub fn changes(before: &BTreeMap<&str, String>, after: &BTreeMap<&str, String>) -> Vec<String> {
+    let display = |key: &str, value: Option<&String>| match value {
+        None => "unset".to_string(),
+        Some(_) if schema::lookup(key).is_ok_and(|k| k.secret) => "'********'".to_string(),
+        Some(value) => format!("'{}'", value),
+    };
+    let mut keys: Vec<&str> = before.keys().chain(after.keys()).copied().collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter(|key| before.get(key) != after.get(key))
+        .map(|key| {
+            format!(
+                "{} changed from {} to {}",
+                key,
+                display(key, before.get(key)),
+                display(key, after.get(key))
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_masks_secrets() {
+        let before = BTreeMap::from([("verbosity", "info".to_string())]);
+        let after = BTreeMap::from([
+            ("verbosity", "debug".to_string()),
+            ("auth.token", "hunter2".to_string()),
+        ]);
+
+        assert_eq!(
+            vec![
+                "auth.token changed from unset to '********'",
+                "verbosity changed from 'info' to 'debug'",
+            ],
+            changes(&before, &after)
+        );
+    }
+}
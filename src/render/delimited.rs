@@ -0,0 +1,61 @@
+use std::io::Write;
+
+use crate::{Error, Result};
+
+use super::tabulate;
+
+/// Writes `value` as delimiter-separated records with a header row, laid out
+/// by [`tabulate`].
+///
+/// Fields containing the delimiter, quotes or line breaks are quoted, and
+/// quotes doubled, as spreadsheets expect.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if a selected column is not in the result.
+pub fn render(
+    value: &serde_json::Value,
+    selected: &[String],
+    delimiter: u8,
+    w: &mut dyn Write,
+) -> Result<()> {
+    let (columns, cells) = tabulate(value, selected)?;
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(w);
+    for record in std::iter::once(&columns).chain(&cells) {
+        writer
+            .write_record(record)
+            .map_err(|e| Error::Output(e.into()))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_quotes_fields() {
+        let value = serde_json::json!([
+            {"name": "a,b", "about": "say \"hi\""},
+            {"name": "tab\there", "about": "plain"},
+        ]);
+        let mut csv = Vec::new();
+        let mut tsv = Vec::new();
+
+        render(&value, &[], b',', &mut csv).unwrap();
+        render(&value, &[], b'\t', &mut tsv).unwrap();
+
+        assert_eq!(
+            "about,name\n\"say \"\"hi\"\"\",\"a,b\"\nplain,tab\there\n",
+            String::from_utf8(csv).unwrap()
+        );
+        assert_eq!(
+            "about\tname\n\"say \"\"hi\"\"\"\ta,b\nplain\t\"tab\there\"\n",
+            String::from_utf8(tsv).unwrap()
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, Utc};
+use unic_langid::LanguageIdentifier;
+
+/// How numbers and dates are written for people in one locale, e.g.
+/// `1.234,5` and `31.12.2024` in German.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Locale {
+    /// Separates groups of three digits in the whole part of a number.
+    group: &'static str,
+    /// Separates the whole part of a number from its fraction.
+    decimal: char,
+    /// `strftime` format of a date.
+    date: &'static str,
+    /// `strftime` format of a time of day.
+    time: &'static str,
+}
+
+/// Writes numbers without grouping and dates in RFC 3339, in UTC, for output
+/// that programs read and for locales without conventions of their own.
+pub const PLAIN: Locale = Locale {
+    group: "",
+    decimal: '.',
+    date: "%Y-%m-%d",
+    time: "%H:%M:%S",
+};
+
+impl Locale {
+    /// Returns the conventions of the locale `name`, e.g. `de-DE`, or
+    /// [`PLAIN`] for locales without known ones.
+    pub fn new(name: &str) -> Self {
+        let Ok(id) = name.parse::<LanguageIdentifier>() else {
+            return PLAIN;
+        };
+        let locale = |group, decimal, date, time| Locale {
+            group,
+            decimal,
+            date,
+            time,
+        };
+        match (id.language.as_str(), id.region.as_ref().map(|r| r.as_str())) {
+            ("en", None | Some("US")) => locale(",", '.', "%m/%d/%Y", "%-I:%M %p"),
+            ("en", _) => locale(",", '.', "%d/%m/%Y", "%H:%M"),
+            ("de", _) => locale(".", ',', "%d.%m.%Y", "%H:%M"),
+            ("fr", _) => locale("\u{202f}", ',', "%d/%m/%Y", "%H:%M"),
+            ("es" | "it" | "pt" | "nl", _) => locale(".", ',', "%d/%m/%Y", "%H:%M"),
+            ("ja" | "ko" | "zh", _) => locale(",", '.', "%Y/%m/%d", "%H:%M"),
+            _ => PLAIN,
+        }
+    }
+
+    /// Formats `n` with its digits grouped, e.g. `1,234,567`.
+    pub fn integer(&self, n: u64) -> String {
+        group(&n.to_string(), self.group)
+    }
+
+    /// Formats `n` with `places` digits after the decimal separator.
+    pub fn decimal(&self, n: f64, places: usize) -> String {
+        let fixed = format!("{:.*}", places, n.abs());
+        let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+        let sign = if n.is_sign_negative() && n != 0.0 {
+            "-"
+        } else {
+            ""
+        };
+        match fraction.is_empty() {
+            true => format!("{}{}", sign, group(whole, self.group)),
+            false => format!(
+                "{}{}{}{}",
+                sign,
+                group(whole, self.group),
+                self.decimal,
+                fraction
+            ),
+        }
+    }
+
+    /// Formats the day of `at` in the local time zone; [`PLAIN`] uses UTC.
+    pub fn date(&self, at: SystemTime) -> String {
+        match *self == PLAIN {
+            true => DateTime::<Utc>::from(at).format(self.date).to_string(),
+            false => DateTime::<Local>::from(at).format(self.date).to_string(),
+        }
+    }
+
+    /// Formats the day and time of `at` in the local time zone; [`PLAIN`]
+    /// uses UTC.
+    pub fn datetime(&self, at: SystemTime) -> String {
+        match *self == PLAIN {
+            true => DateTime::<Utc>::from(at)
+                .format("%Y-%m-%dT%H:%M:%SZ")
+                .to_string(),
+            false => DateTime::<Local>::from(at)
+                .format(&format!("{} {}", self.date, self.time))
+                .to_string(),
+        }
+    }
+}
+
+/// Puts `separator` between each group of three of `digits`, from the right.
+fn group(digits: &str, separator: &str) -> String {
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_follow_the_locale() {
+        let german = Locale::new("de-DE");
+
+        assert_eq!("1.234.567", german.integer(1_234_567));
+        assert_eq!("-1.234,50", german.decimal(-1234.5, 2));
+        assert_eq!("1,234.5", Locale::new("en-US").decimal(1234.5, 1));
+        assert_eq!("999", Locale::new("en").integer(999));
+        assert_eq!("1234567", PLAIN.integer(1_234_567));
+        assert_eq!(PLAIN, Locale::new("tlh"));
+    }
+
+    #[test]
+    fn test_plain_dates_are_rfc3339() {
+        let at = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+
+        assert_eq!("2023-11-14", PLAIN.date(at));
+        assert_eq!("2023-11-14T22:13:20Z", PLAIN.datetime(at));
+    }
+}
@@ -0,0 +1,452 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::glyphs::{self, Glyphs};
+use crate::{Error, Result};
+
+use locale::Locale;
+
+pub mod delimited;
+pub mod locale;
+pub mod report;
+pub mod table;
+pub mod template;
+
+/// How subcommand results are written to stdout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Readable text, as chosen by the subcommand.
+    #[default]
+    Human,
+    /// Pretty-printed JSON.
+    Json,
+    /// YAML.
+    #[cfg(feature = "yaml")]
+    Yaml,
+    /// Aligned columns, one row per record.
+    Table,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// Tab-separated values with a header row.
+    Tsv,
+    /// One compact JSON document per line, written as records are produced.
+    Ndjson,
+    /// A Markdown report with a heading and a table.
+    Markdown,
+    /// A standalone HTML report with a heading and a table.
+    Html,
+}
+
+/// A result returned by a subcommand, rendered by the [`crate::App`] in the
+/// format the user selected with `--output`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Output {
+    value: serde_json::Value,
+    human: Option<String>,
+    primary: Option<String>,
+}
+
+impl Output {
+    /// Creates an output from any serializable `value`.
+    pub fn new<T: Serialize>(value: &T) -> Self {
+        Output {
+            value: serde_json::to_value(value).expect("results must serialize to JSON"),
+            human: None,
+            primary: None,
+        }
+    }
+
+    /// Overrides the generic human-readable rendering with `text`.
+    pub fn human<S: Into<String>>(mut self, text: S) -> Self {
+        self.human = Some(text.into());
+        self
+    }
+
+    /// Sets the text `--copy` places on the clipboard, such as a generated ID.
+    pub fn primary<S: Into<String>>(mut self, text: S) -> Self {
+        self.primary = Some(text.into());
+        self
+    }
+
+    pub fn value(&self) -> &serde_json::Value {
+        &self.value
+    }
+
+    /// The text set with [`Output::human`], if any.
+    pub fn human_text(&self) -> Option<&str> {
+        self.human.as_deref()
+    }
+
+    /// The text `--copy` places on the clipboard: the text set with
+    /// [`Output::primary`], else a scalar value itself. `None` means the
+    /// rendered output is copied instead.
+    pub fn primary_text(&self) -> Option<String> {
+        if let Some(primary) = &self.primary {
+            return Some(primary.clone());
+        }
+        match &self.value {
+            serde_json::Value::Object(_) | serde_json::Value::Array(_) => None,
+            value => Some(scalar(value)),
+        }
+    }
+}
+
+/// Renders subcommand results according to the user's output options.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Renderer {
+    format: OutputFormat,
+    columns: Vec<String>,
+    width: Option<usize>,
+    template: Option<String>,
+    title: String,
+    glyphs: &'static Glyphs,
+    locale: Locale,
+    accessible: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer {
+            format: OutputFormat::default(),
+            columns: Vec::new(),
+            width: None,
+            template: None,
+            title: String::new(),
+            glyphs: &glyphs::UNICODE,
+            locale: locale::PLAIN,
+            accessible: false,
+        }
+    }
+}
+
+impl Renderer {
+    pub fn new(format: OutputFormat) -> Self {
+        Renderer {
+            format,
+            ..Renderer::default()
+        }
+    }
+
+    /// Restricts tables and reports to `columns`, in that order; empty selects every column.
+    pub fn columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Limits tables to `width` characters per line, and wraps human-readable
+    /// text at it; `None` leaves them unbounded.
+    pub fn width(mut self, width: Option<usize>) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Renders every record through `template` instead of the selected format.
+    pub fn template(mut self, template: Option<String>) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Replaces Unicode symbols with plain ASCII when `ascii` is set.
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.glyphs = glyphs::select(ascii);
+        self
+    }
+
+    /// Suits output to screen readers when `accessible` is set: tables
+    /// become labeled lines, and spinners and progress bars are not drawn.
+    pub fn accessible(mut self, accessible: bool) -> Self {
+        if accessible {
+            self.glyphs = &glyphs::ACCESSIBLE;
+        }
+        self.accessible = accessible;
+        self
+    }
+
+    /// Sets the heading of Markdown and HTML reports.
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Writes numbers and dates in human output as is usual in `locale`.
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// How subcommands write numbers and dates for people: as is usual in
+    /// the user's locale for human output, and [`locale::PLAIN`] for every
+    /// other format so that programs can read them.
+    pub fn numbers(&self) -> &Locale {
+        match self.format {
+            OutputFormat::Human => &self.locale,
+            _ => &locale::PLAIN,
+        }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// The symbols drawn in output, as chosen with `--ascii`.
+    pub fn glyphs(&self) -> &'static Glyphs {
+        self.glyphs
+    }
+
+    /// Whether records are written as soon as they are emitted rather than
+    /// once the subcommand returns.
+    pub fn is_streaming(&self) -> bool {
+        self.template.is_some() || self.format == OutputFormat::Ndjson
+    }
+
+    /// Writes `output` to `w` in the selected format.
+    pub fn render(&self, output: &Output, w: &mut dyn Write) -> Result<()> {
+        if let Some(text) = &self.template {
+            return template::render(&output.value, text, w);
+        }
+        match self.format {
+            OutputFormat::Human => match &output.human {
+                Some(text) => writeln!(w, "{}", wrap(text, self.width))?,
+                None => render_human(&output.value, w)?,
+            },
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut *w, &output.value)
+                    .map_err(|e| Error::Output(e.into()))?;
+                writeln!(w)?;
+            }
+            #[cfg(feature = "yaml")]
+            OutputFormat::Yaml => {
+                let yaml = serde_yaml::to_string(&output.value)
+                    .map_err(|e| Error::Subcommand(format!("failed to render YAML: {}", e)))?;
+                write!(w, "{}", yaml)?;
+            }
+            OutputFormat::Table if self.accessible => {
+                table::render_labeled(&output.value, &self.columns, w)?
+            }
+            OutputFormat::Table => table::render(
+                &output.value,
+                &self.columns,
+                self.width,
+                self.glyphs.ellipsis,
+                w,
+            )?,
+            OutputFormat::Csv => delimited::render(&output.value, &self.columns, b',', w)?,
+            OutputFormat::Tsv => delimited::render(&output.value, &self.columns, b'\t', w)?,
+            OutputFormat::Markdown => {
+                report::markdown(&output.value, &self.columns, &self.title, w)?
+            }
+            OutputFormat::Html => report::html(&output.value, &self.columns, &self.title, w)?,
+            OutputFormat::Ndjson => match &output.value {
+                serde_json::Value::Array(items) => {
+                    for item in items {
+                        write_line(item, w)?;
+                    }
+                }
+                other => write_line(other, w)?,
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Writes `value` as compact JSON on a line of its own and flushes `w`, so
+/// readers of a pipe see each record as soon as it is produced.
+pub fn write_line(value: &serde_json::Value, w: &mut dyn Write) -> Result<()> {
+    serde_json::to_writer(&mut *w, value).map_err(|e| Error::Output(e.into()))?;
+    writeln!(w)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Returns the width output should fit: `COLUMNS` if it is set to a number,
+/// else the width of the terminal stdout is attached to, if it is one.
+pub fn terminal_width() -> Option<usize> {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .or_else(|| {
+            terminal_size::terminal_size_of(std::io::stdout()).map(|(width, _)| width.0 as usize)
+        })
+}
+
+/// Soft-wraps each line of `text` longer than `width` characters at spaces,
+/// indenting the continuation lines like the line itself. Words longer than
+/// `width` are kept whole; `None` leaves the text as it is.
+pub fn wrap(text: &str, width: Option<usize>) -> String {
+    let Some(width) = width else {
+        return text.to_string();
+    };
+    let mut wrapped = Vec::new();
+    for line in text.lines() {
+        if line.chars().count() <= width {
+            wrapped.push(line.to_string());
+            continue;
+        }
+        let content = line.trim_start();
+        let indent = &line[..line.len() - content.len()];
+        let mut current = indent.to_string();
+        for word in content.split(' ') {
+            let length = current.chars().count() + 1 + word.chars().count();
+            if current.len() > indent.len() && length > width {
+                wrapped.push(std::mem::replace(&mut current, indent.to_string()));
+            } else if current.len() > indent.len() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        wrapped.push(current);
+    }
+    wrapped.join("\n")
+}
+
+/// Renders `value` generically: scalars as-is, objects as `key: value` lines
+/// and arrays one item per line.
+fn render_human(value: &serde_json::Value, w: &mut dyn Write) -> Result<()> {
+    match value {
+        serde_json::Value::Null => {}
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                writeln!(w, "{}: {}", key, scalar(value))?;
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                writeln!(w, "{}", scalar(item))?;
+            }
+        }
+        other => writeln!(w, "{}", scalar(other))?,
+    }
+    Ok(())
+}
+
+/// Lays `value` out as rows of cells under named columns.
+///
+/// An array of objects becomes one row per object, with columns in order of
+/// first appearance. A single object becomes one row, and scalars a single cell.
+/// When `selected` is not empty only those columns are kept, in that order.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if a selected column is not in the result.
+pub fn tabulate(
+    value: &serde_json::Value,
+    selected: &[String],
+) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let rows: Vec<&serde_json::Value> = match value {
+        serde_json::Value::Array(items) => items.iter().collect(),
+        serde_json::Value::Null => Vec::new(),
+        other => vec![other],
+    };
+
+    let mut columns: Vec<String> = Vec::new();
+    for row in &rows {
+        match row {
+            serde_json::Value::Object(map) => {
+                for key in map.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            _ if !columns.iter().any(|c| c == "value") => columns.push("value".to_string()),
+            _ => {}
+        }
+    }
+
+    if !selected.is_empty() {
+        if let Some(unknown) = selected.iter().find(|s| !columns.contains(s)) {
+            return Err(Error::Subcommand(format!(
+                "unknown column '{}', expected one of: {}",
+                unknown,
+                columns.join(", ")
+            )));
+        }
+        columns = selected.to_vec();
+    }
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| match row {
+                    serde_json::Value::Object(map) => {
+                        map.get(column).map(scalar).unwrap_or_default()
+                    }
+                    other if column == "value" => scalar(other),
+                    _ => String::new(),
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok((columns, cells))
+}
+
+/// Formats a value for a single cell or line; strings are not quoted.
+pub fn scalar(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(format: OutputFormat, output: &Output) -> String {
+        let mut buffer = Vec::new();
+        Renderer::new(format).render(output, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let output = Output::new(&serde_json::json!({"name": "fixme", "count": 2}));
+
+        assert_eq!(
+            "count: 2\nname: fixme\n",
+            rendered(OutputFormat::Human, &output)
+        );
+        assert_eq!(
+            "{\n  \"count\": 2,\n  \"name\": \"fixme\"\n}\n",
+            rendered(OutputFormat::Json, &output)
+        );
+        #[cfg(feature = "yaml")]
+        assert_eq!(
+            "count: 2\nname: fixme\n",
+            rendered(OutputFormat::Yaml, &output)
+        );
+        assert_eq!(
+            "{\"count\":2,\"name\":\"fixme\"}\n",
+            rendered(OutputFormat::Ndjson, &output)
+        );
+        assert_eq!(None, output.primary_text());
+        assert_eq!(
+            Some("id".to_string()),
+            output.clone().primary("id").primary_text()
+        );
+        assert_eq!(
+            Some("fixme".to_string()),
+            Output::new(&"fixme").primary_text()
+        );
+        assert_eq!(
+            "Hello\n",
+            rendered(OutputFormat::Human, &output.clone().human("Hello"))
+        );
+    }
+
+    #[test]
+    fn test_wrap_breaks_long_lines_at_spaces() {
+        let text = "short\n  a line that is too long for the width\nunbreakable-word-here";
+
+        assert_eq!(
+            "short\n  a line that\n  is too long\n  for the\n  width\nunbreakable-word-here",
+            wrap(text, Some(14))
+        );
+        assert_eq!(text, wrap(text, None));
+    }
+}
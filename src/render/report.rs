@@ -0,0 +1,111 @@
+use std::io::Write;
+
+use crate::Result;
+
+use super::tabulate;
+
+/// Writes `value` as a Markdown document: a `title` heading followed by a
+/// table laid out by [`tabulate`].
+pub fn markdown(
+    value: &serde_json::Value,
+    selected: &[String],
+    title: &str,
+    w: &mut dyn Write,
+) -> Result<()> {
+    let (columns, cells) = tabulate(value, selected)?;
+
+    writeln!(w, "# {}\n", escape_markdown(title))?;
+    write_markdown_row(&columns, w)?;
+    writeln!(w, "|{}", " --- |".repeat(columns.len()))?;
+    for row in &cells {
+        write_markdown_row(row, w)?;
+    }
+    Ok(())
+}
+
+/// Writes `value` as a standalone HTML document: a `title` heading followed by
+/// a table laid out by [`tabulate`].
+pub fn html(
+    value: &serde_json::Value,
+    selected: &[String],
+    title: &str,
+    w: &mut dyn Write,
+) -> Result<()> {
+    let (columns, cells) = tabulate(value, selected)?;
+    let title = escape_html(title);
+
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html>")?;
+    writeln!(w, "<head>")?;
+    writeln!(w, "<meta charset=\"utf-8\">")?;
+    writeln!(w, "<title>{}</title>", title)?;
+    writeln!(w, "</head>")?;
+    writeln!(w, "<body>")?;
+    writeln!(w, "<h1>{}</h1>", title)?;
+    writeln!(w, "<table>")?;
+    writeln!(w, "<thead>")?;
+    write_html_row("th", &columns, w)?;
+    writeln!(w, "</thead>")?;
+    writeln!(w, "<tbody>")?;
+    for row in &cells {
+        write_html_row("td", row, w)?;
+    }
+    writeln!(w, "</tbody>")?;
+    writeln!(w, "</table>")?;
+    writeln!(w, "</body>")?;
+    writeln!(w, "</html>")?;
+    Ok(())
+}
+
+fn write_markdown_row(row: &[String], w: &mut dyn Write) -> Result<()> {
+    let cells: Vec<String> = row.iter().map(|cell| escape_markdown(cell)).collect();
+    writeln!(w, "| {} |", cells.join(" | "))?;
+    Ok(())
+}
+
+fn write_html_row(tag: &str, row: &[String], w: &mut dyn Write) -> Result<()> {
+    let cells: String = row
+        .iter()
+        .map(|cell| format!("<{tag}>{}</{tag}>", escape_html(cell), tag = tag))
+        .collect();
+    writeln!(w, "<tr>{}</tr>", cells)?;
+    Ok(())
+}
+
+/// Escapes characters that would break out of a Markdown table cell.
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('\n', "<br>")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_escape_cells() {
+        let value = serde_json::json!([{"name": "a|b", "about": "<b>&</b>"}]);
+        let mut md = Vec::new();
+        let mut page = Vec::new();
+
+        markdown(&value, &[], "FIXME list", &mut md).unwrap();
+        html(&value, &[], "FIXME list", &mut page).unwrap();
+
+        assert_eq!(
+            "# FIXME list\n\n| about | name |\n| --- | --- |\n| <b>&</b> | a\\|b |\n",
+            String::from_utf8(md).unwrap()
+        );
+        let page = String::from_utf8(page).unwrap();
+        assert!(page.contains("<h1>FIXME list</h1>"));
+        assert!(page.contains("<tr><td>&lt;b&gt;&amp;&lt;/b&gt;</td><td>a|b</td></tr>"));
+    }
+}
@@ -0,0 +1,166 @@
+use std::io::Write;
+
+use crate::Result;
+
+use super::tabulate;
+
+/// Columns are never shrunk below this many characters to fit the width.
+const MIN_COLUMN_WIDTH: usize = 4;
+
+/// Writes `value` as a table with a header row, laid out by [`tabulate`].
+///
+/// When `max_width` is given the widest columns are shrunk, and their cells
+/// truncated with `ellipsis`, until each line fits.
+///
+/// # Errors
+///
+/// Returns [`crate::Error::Subcommand`] if a selected column is not in the result.
+pub fn render(
+    value: &serde_json::Value,
+    selected: &[String],
+    max_width: Option<usize>,
+    ellipsis: &str,
+    w: &mut dyn Write,
+) -> Result<()> {
+    let (columns, cells) = tabulate(value, selected)?;
+
+    let mut widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(column.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    if let Some(max_width) = max_width {
+        shrink(&mut widths, max_width);
+    }
+
+    let header: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+    write_row(&header, &widths, ellipsis, w)?;
+    for row in &cells {
+        write_row(row, &widths, ellipsis, w)?;
+    }
+    Ok(())
+}
+
+/// Writes `value` as labeled lines instead of a table, `column: cell`, with
+/// a blank line between rows, for screen readers that cannot follow columns.
+///
+/// # Errors
+///
+/// Returns [`crate::Error::Subcommand`] if a selected column is not in the result.
+pub fn render_labeled(
+    value: &serde_json::Value,
+    selected: &[String],
+    w: &mut dyn Write,
+) -> Result<()> {
+    let (columns, cells) = tabulate(value, selected)?;
+    for (i, row) in cells.iter().enumerate() {
+        if i > 0 {
+            writeln!(w)?;
+        }
+        for (column, cell) in columns.iter().zip(row) {
+            writeln!(w, "{}: {}", column, cell)?;
+        }
+    }
+    Ok(())
+}
+
+/// Narrows the widest columns one character at a time until the row, with its
+/// separators, fits in `max_width` or every column is at [`MIN_COLUMN_WIDTH`].
+fn shrink(widths: &mut [usize], max_width: usize) {
+    let separators = 2 * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + separators > max_width {
+        match widths.iter_mut().max() {
+            Some(widest) if *widest > MIN_COLUMN_WIDTH => *widest -= 1,
+            _ => break,
+        }
+    }
+}
+
+/// Cuts `cell` down to `width` characters, marking the cut with `ellipsis`.
+fn truncate(cell: &str, width: usize, ellipsis: &str) -> String {
+    if cell.chars().count() <= width {
+        cell.to_string()
+    } else {
+        let kept = width.saturating_sub(ellipsis.chars().count());
+        let mut cut: String = cell.chars().take(kept).collect();
+        cut.push_str(ellipsis);
+        cut
+    }
+}
+
+fn write_row(row: &[String], widths: &[usize], ellipsis: &str, w: &mut dyn Write) -> Result<()> {
+    let line: Vec<String> = row
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:width$}", truncate(cell, *width, ellipsis), width = width))
+        .collect();
+    writeln!(w, "{}", line.join("  ").trim_end())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_aligns_columns() {
+        let value = serde_json::json!([
+            {"name": "fixme1", "hidden": false},
+            {"name": "generate", "hidden": true},
+        ]);
+        let mut buffer = Vec::new();
+
+        render(&value, &[], None, "…", &mut buffer).unwrap();
+
+        assert_eq!(
+            "HIDDEN  NAME\nfalse   fixme1\ntrue    generate\n",
+            String::from_utf8(buffer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_selects_columns_and_fits_width() {
+        let value = serde_json::json!([
+            {"name": "fixme1", "about": "Runs the first example subcommand", "hidden": false},
+        ]);
+        let columns = vec!["name".to_string(), "about".to_string()];
+        let mut buffer = Vec::new();
+
+        render(&value, &columns, Some(24), "…", &mut buffer).unwrap();
+
+        assert_eq!(
+            "NAME    ABOUT\nfixme1  Runs the first …\n",
+            String::from_utf8(buffer).unwrap()
+        );
+
+        let err = render(&value, &["size".to_string()], None, "…", &mut Vec::new()).unwrap_err();
+        assert_eq!(
+            "unknown column 'size', expected one of: about, hidden, name",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_render_labeled_names_each_cell() {
+        let value = serde_json::json!([
+            {"name": "fixme1", "hidden": false},
+            {"name": "generate", "hidden": true},
+        ]);
+        let mut buffer = Vec::new();
+
+        render_labeled(&value, &[], &mut buffer).unwrap();
+
+        assert_eq!(
+            "hidden: false\nname: fixme1\n\nhidden: true\nname: generate\n",
+            String::from_utf8(buffer).unwrap()
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::io::Write;
+
+use minijinja::Environment;
+
+use crate::{Error, Result};
+
+/// Writes `value` through the user's `template`, once per record: each item of
+/// an array, or the value itself otherwise.
+///
+/// An object's fields are available by name, e.g. `{{ name }}`; any other
+/// record is available as `value`. A line break follows every record.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if `template` is not valid or fails to render.
+pub fn render(value: &serde_json::Value, template: &str, w: &mut dyn Write) -> Result<()> {
+    let env = Environment::new();
+    let compiled = env.template_from_str(template).map_err(into_error)?;
+    let records = match value {
+        serde_json::Value::Array(items) => items.iter().collect(),
+        serde_json::Value::Null => Vec::new(),
+        other => vec![other],
+    };
+    for record in records {
+        let line = match record {
+            serde_json::Value::Object(_) => compiled.render(record),
+            other => compiled.render(minijinja::context! { value => other }),
+        }
+        .map_err(into_error)?;
+        writeln!(w, "{}", line)?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+fn into_error(error: minijinja::Error) -> Error {
+    Error::Subcommand(format!("invalid --format template: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_applies_template_per_record() {
+        let value = serde_json::json!([
+            {"name": "fixme1", "hidden": false},
+            {"name": "generate", "hidden": true},
+        ]);
+        let mut buffer = Vec::new();
+
+        render(
+            &value,
+            "{{ name }}{% if hidden %} (hidden){% endif %}",
+            &mut buffer,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "fixme1\ngenerate (hidden)\n",
+            String::from_utf8(buffer).unwrap()
+        );
+        assert!(render(&value, "{{ name", &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! How a subcommand's records, warnings and summary become [events](crate::events)
+//! for the format chosen with `--output`, so that subcommands report the
+//! same way whichever format the user asked for.
+
+use crate::events::Event;
+use crate::render::scalar;
+use crate::{Context, Output, OutputFormat, Renderer, Result};
+
+/// Turns what a subcommand reports into the events published on its bus.
+pub trait Reporter {
+    /// Reports one of the many records a subcommand produces, e.g. a file found.
+    fn item(&mut self, ctx: &Context, item: Output) -> Result<()>;
+
+    /// Reports something that went wrong without failing the subcommand.
+    fn warn(&mut self, ctx: &Context, message: &str) -> Result<()> {
+        ctx.publish(&Event::Warning { message })
+    }
+
+    /// Reports the outcome of the whole subcommand, e.g. totals after its items.
+    fn summary(&mut self, ctx: &Context, summary: Output) -> Result<()>;
+
+    /// Reports whatever is held back once the subcommand has returned.
+    fn finish(&mut self, _ctx: &Context) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns the reporter for the output options of `renderer`: a
+/// [`HumanReporter`] for human output and a [`JsonReporter`] for every
+/// format that programs read.
+pub fn select(renderer: &Renderer) -> Box<dyn Reporter> {
+    match renderer.format() {
+        OutputFormat::Human if !renderer.is_streaming() => Box::new(HumanReporter),
+        _ => Box::new(JsonReporter::new(renderer.is_streaming())),
+    }
+}
+
+/// Reports each item as a line as soon as it is ready, then the summary.
+pub struct HumanReporter;
+
+impl Reporter for HumanReporter {
+    fn item(&mut self, ctx: &Context, item: Output) -> Result<()> {
+        let item = match item.human_text() {
+            Some(_) => item,
+            None => {
+                let line = scalar(item.value());
+                item.human(line)
+            }
+        };
+        ctx.publish(&Event::Result { output: &item })
+    }
+
+    fn summary(&mut self, ctx: &Context, summary: Output) -> Result<()> {
+        ctx.publish(&Event::Result { output: &summary })
+    }
+}
+
+/// Reports the items as one document once the subcommand returns, or each
+/// as it is ready when `streaming`. Programs can count the items
+/// themselves, so the summary is only reported when there are none.
+pub struct JsonReporter {
+    streaming: bool,
+    items: Vec<serde_json::Value>,
+    /// Whether an item has been reported already, when `streaming`.
+    streamed: bool,
+    summary: Option<Output>,
+}
+
+impl JsonReporter {
+    pub fn new(streaming: bool) -> Self {
+        JsonReporter {
+            streaming,
+            items: Vec::new(),
+            streamed: false,
+            summary: None,
+        }
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn item(&mut self, ctx: &Context, item: Output) -> Result<()> {
+        match self.streaming {
+            true => {
+                self.streamed = true;
+                ctx.publish(&Event::Result { output: &item })
+            }
+            false => {
+                self.items.push(item.value().clone());
+                Ok(())
+            }
+        }
+    }
+
+    fn summary(&mut self, _ctx: &Context, summary: Output) -> Result<()> {
+        self.summary = Some(summary);
+        Ok(())
+    }
+
+    fn finish(&mut self, ctx: &Context) -> Result<()> {
+        let items = std::mem::take(&mut self.items);
+        match self.summary.take() {
+            _ if !items.is_empty() => ctx.publish(&Event::Result {
+                output: &Output::new(&items),
+            }),
+            Some(summary) if !self.streamed => ctx.publish(&Event::Result { output: &summary }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::App;
+
+    fn report(format: OutputFormat, items: &[&str]) -> String {
+        let app = App::new();
+        let matches = clap::ArgMatches::default();
+        let mut stdout = Vec::new();
+        let ctx = Context::new(&app, &matches)
+            .stdout(&mut stdout)
+            .renderer(Renderer::new(format));
+        for item in items {
+            ctx.item(Output::new(item)).unwrap();
+        }
+        let total = format!("{} item(s)", items.len());
+        ctx.finish(Some(Output::new(&items.len()).human(total)))
+            .unwrap();
+        drop(ctx);
+        String::from_utf8(stdout).unwrap()
+    }
+
+    #[test]
+    fn test_reporters_suit_the_output_format() {
+        assert_eq!(
+            "a\nb\n2 item(s)\n",
+            report(OutputFormat::Human, &["a", "b"])
+        );
+        assert_eq!(
+            "[\n  \"a\",\n  \"b\"\n]\n",
+            report(OutputFormat::Json, &["a", "b"])
+        );
+        assert_eq!("\"a\"\n\"b\"\n", report(OutputFormat::Ndjson, &["a", "b"]));
+        assert_eq!("0\n", report(OutputFormat::Json, &[]));
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use log::warn;
+
+use crate::clock::Clock;
+use crate::context::CancellationToken;
+use crate::Result;
+
+/// How often, and how patiently, transient failures are retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts made after the first one fails.
+    pub retries: u32,
+    /// Delay before the first retry; it doubles before each further retry.
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            retries: 0,
+            delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Calls `attempt` with the attempt number, starting at 1, until it
+    /// succeeds, fails with an error that is not [transient](crate::Error::is_transient),
+    /// runs out of retries or `cancellation` is cancelled. Delays are waited
+    /// out on `clock`.
+    ///
+    /// Returns the result of the last attempt.
+    pub fn run<T, F>(
+        &self,
+        cancellation: &CancellationToken,
+        clock: &dyn Clock,
+        mut attempt: F,
+    ) -> Result<T>
+    where
+        F: FnMut(u32) -> Result<T>,
+    {
+        let mut delay = self.delay;
+        let mut number = 1;
+        loop {
+            match attempt(number) {
+                Err(e)
+                    if e.is_transient()
+                        && number <= self.retries
+                        && !cancellation.is_cancelled() =>
+                {
+                    warn!(
+                        "attempt {} of {} failed: {}; retrying in {}",
+                        number,
+                        self.retries + 1,
+                        e,
+                        humantime::format_duration(delay)
+                    );
+                    clock.sleep(delay);
+                    delay = delay.saturating_mul(2);
+                    number += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FrozenClock;
+    use crate::Error;
+
+    #[test]
+    fn test_run_retries_only_transient_errors() {
+        let policy = RetryPolicy {
+            retries: 2,
+            delay: Duration::from_secs(1),
+        };
+        let token = CancellationToken::new();
+        let start = std::time::SystemTime::UNIX_EPOCH;
+        let clock = FrozenClock::new(start);
+
+        let mut attempts = 0;
+        let result = policy.run(&token, &clock, |number| {
+            attempts = number;
+            match number {
+                3 => Ok(number),
+                _ => Err(Error::Subcommand("flaky".to_string()).transient()),
+            }
+        });
+        assert_eq!(3, result.unwrap());
+        assert_eq!(3, attempts);
+        assert_eq!(start + Duration::from_secs(3), clock.now());
+
+        let result: Result<()> = policy.run(&token, &clock, |number| {
+            attempts = number;
+            Err(Error::Subcommand("broken".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(1, attempts);
+    }
+}
@@ -0,0 +1,319 @@
+//! A JSON-RPC 2.0 front end to the subcommands, for `serve --stdio`.
+//!
+//! Each line is one request; the method names a subcommand, with nested
+//! subcommands joined by dots (e.g. `config.get`), and the optional params
+//! are the command-line arguments that follow it. The result is the
+//! subcommand's JSON output, or `null` if it produced none. Subcommands that
+//! run until interrupted cannot be called, nor can the params name files
+//! for the application itself to read or write; see [`check_call`], which
+//! the daemon's gRPC interface shares.
+
+use std::fmt;
+
+use clap::error::ErrorKind;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{App, Error};
+
+/// The request was not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// The JSON was not a valid request object.
+pub const INVALID_REQUEST: i64 = -32600;
+/// No subcommand has the requested name.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The subcommand rejected its arguments.
+pub const INVALID_PARAMS: i64 = -32602;
+/// The subcommand ran and failed.
+pub const COMMAND_FAILED: i64 = -32000;
+
+/// The subcommands, joined by dots, that run until interrupted, which would
+/// hold up the server for good.
+const NEVER_RETURN: [&str; 3] = ["serve", "schedule", "daemon.start"];
+
+/// The global options that never return or read or write files, which a
+/// remote caller must not have the application do on its behalf.
+const REFUSED_OPTIONS: [&str; 4] = ["--watch", "--batch", "--metrics-out", "--profile-out"];
+
+/// Why a remote call of a subcommand is refused.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Refusal {
+    /// No subcommand has the name.
+    NotFound(String),
+    /// The subcommand runs until interrupted.
+    NeverReturns(String),
+    /// An argument is an argument file or a [refused option](REFUSED_OPTIONS).
+    Argument(String),
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::NotFound(name) => write!(f, "no subcommand is named '{}'", name),
+            Refusal::NeverReturns(name) => {
+                write!(f, "'{}' runs until interrupted, so cannot be called", name)
+            }
+            Refusal::Argument(arg) => write!(f, "'{}' cannot be used in a remote call", arg),
+        }
+    }
+}
+
+/// Checks that a remote caller may run the nested subcommands of `command`
+/// named by `path`, e.g. `["config", "get"]`, with `args`, and returns
+/// their names.
+///
+/// # Errors
+///
+/// Returns a [`Refusal`] if `path` names no subcommand, or one that runs
+/// until interrupted, or if `args` holds an `@path` argument file or a
+/// [refused option](REFUSED_OPTIONS).
+pub fn check_call(
+    command: &clap::Command,
+    path: &[&str],
+    args: &[String],
+) -> Result<Vec<String>, Refusal> {
+    let method = path.join(".");
+    let names = match path.is_empty() {
+        true => None,
+        false => subcommands(command, path),
+    };
+    let names = names.ok_or_else(|| Refusal::NotFound(method.clone()))?;
+    if NEVER_RETURN.contains(&names.join(".").as_str()) {
+        return Err(Refusal::NeverReturns(method));
+    }
+    let refused = args.iter().find(|arg| {
+        arg.starts_with('@')
+            || REFUSED_OPTIONS.iter().any(|option| {
+                arg.strip_prefix(option)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+            })
+    });
+    match refused {
+        Some(arg) => Err(Refusal::Argument(arg.clone())),
+        None => Ok(names),
+    }
+}
+
+/// Like [`check_call`] for a command line that starts with the subcommands,
+/// e.g. `["config", "get", "verbosity"]`.
+///
+/// # Errors
+///
+/// As [`check_call`].
+pub fn check_command_line(command: &clap::Command, args: &[String]) -> Result<(), Refusal> {
+    let mut current = command;
+    let depth = args
+        .iter()
+        .take_while(|arg| match current.find_subcommand(arg) {
+            Some(subcommand) => {
+                current = subcommand;
+                true
+            }
+            None => false,
+        })
+        .count();
+    let path: Vec<&str> = args[..depth].iter().map(String::as_str).collect();
+    match path.is_empty() {
+        true => Err(Refusal::NotFound(args.first().cloned().unwrap_or_default())),
+        false => check_call(command, &path, &args[depth..]).map(|_| ()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Absent for notifications, which get no response.
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Result(Value),
+    Error {
+        code: i64,
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        data: Option<Value>,
+    },
+}
+
+impl Outcome {
+    fn error(code: i64, message: impl Into<String>) -> Self {
+        Outcome::Error {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+/// Answers the request on `line` by running the subcommand it names in
+/// `app`. Returns the response line, or `None` for a notification.
+pub fn respond(app: &App, line: &str) -> Option<String> {
+    let (id, outcome) = match serde_json::from_str::<Value>(line) {
+        Err(e) => (Value::Null, Outcome::error(PARSE_ERROR, e.to_string())),
+        Ok(value) => {
+            let id = value.get("id").cloned().unwrap_or_default();
+            match serde_json::from_value::<Request>(value) {
+                Err(e) => (id, Outcome::error(INVALID_REQUEST, e.to_string())),
+                Ok(request) if request.jsonrpc != "2.0" => (
+                    id,
+                    Outcome::error(INVALID_REQUEST, "only JSON-RPC 2.0 is supported"),
+                ),
+                Ok(request) => {
+                    let outcome = invoke(app, &request);
+                    (request.id?, outcome)
+                }
+            }
+        }
+    };
+    let response = Response {
+        jsonrpc: "2.0",
+        id,
+        outcome,
+    };
+    Some(serde_json::to_string(&response).expect("responses serialize"))
+}
+
+/// Runs the subcommand named by `request` with JSON output, capturing what
+/// it writes.
+fn invoke(app: &App, request: &Request) -> Outcome {
+    let method: Vec<&str> = request.method.split('.').collect();
+    let path = match check_call(app.command(), &method, &request.params) {
+        Ok(path) => path,
+        Err(refusal @ Refusal::Argument(_)) => {
+            return Outcome::error(INVALID_PARAMS, refusal.to_string())
+        }
+        Err(refusal) => return Outcome::error(METHOD_NOT_FOUND, refusal.to_string()),
+    };
+    let args = [app.command().get_name(), "--output", "json"]
+        .into_iter()
+        .map(str::to_string)
+        .chain(path)
+        .chain(request.params.iter().cloned());
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    match app.dispatch(args, &mut stdout, &mut stderr, false) {
+        Ok(()) => Outcome::Result(result(&stdout)),
+        Err(Error::Usage(e)) => {
+            let code = match e.kind() {
+                ErrorKind::InvalidSubcommand | ErrorKind::MissingSubcommand => METHOD_NOT_FOUND,
+                _ => INVALID_PARAMS,
+            };
+            Outcome::error(code, e.to_string().trim_end())
+        }
+        Err(e) => Outcome::Error {
+            code: COMMAND_FAILED,
+            message: e.to_string(),
+            data: Some(json!({
+                "code": e.code(),
+                "stderr": String::from_utf8_lossy(&stderr),
+            })),
+        },
+    }
+}
+
+/// Returns the names of the nested subcommands of `command` that `path`
+/// names, e.g. `["config", "get"]`, or `None` if one of its segments names
+/// none, such as an option.
+fn subcommands(command: &clap::Command, path: &[&str]) -> Option<Vec<String>> {
+    let mut command = command;
+    path.iter()
+        .map(|segment| {
+            command = command.find_subcommand(segment)?;
+            Some(command.get_name().to_string())
+        })
+        .collect()
+}
+
+/// Parses what a subcommand wrote with `--output json`: one document, an
+/// array of the documents if it emitted several, or plain text, such as
+/// help, as a string.
+fn result(stdout: &[u8]) -> Value {
+    let documents: Result<Vec<Value>, _> = serde_json::Deserializer::from_slice(stdout)
+        .into_iter()
+        .collect();
+    match documents {
+        Ok(mut documents) if documents.len() <= 1 => documents.pop().unwrap_or_default(),
+        Ok(documents) => Value::Array(documents),
+        Err(_) => Value::String(String::from_utf8_lossy(stdout).into_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn respond_to(request: Value) -> Value {
+        let line = respond(&App::new(), &request.to_string()).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn test_respond_runs_subcommand() {
+        let response = respond_to(json!({
+            "jsonrpc": "2.0", "id": 7, "method": "explain", "params": ["E007"]
+        }));
+        assert_eq!(json!(7), response["id"]);
+        assert_eq!(json!("E007"), response["result"]["code"]);
+
+        let response = respond_to(json!({"jsonrpc": "2.0", "id": "a", "method": "nope"}));
+        assert_eq!(json!(METHOD_NOT_FOUND), response["error"]["code"]);
+        for method in ["--config=/x.fixme1", "schedule", "daemon.start"] {
+            let response = respond_to(json!({"jsonrpc": "2.0", "id": 1, "method": method}));
+            assert_eq!(json!(METHOD_NOT_FOUND), response["error"]["code"]);
+        }
+        let response = respond_to(json!({
+            "jsonrpc": "2.0", "id": 2, "method": "fixme1", "params": ["--watch", "."]
+        }));
+        assert_eq!(json!(INVALID_PARAMS), response["error"]["code"]);
+        let response = respond_to(json!({
+            "jsonrpc": "2.0", "id": 3, "method": "fixme1", "params": ["@/etc/passwd"]
+        }));
+        assert_eq!(json!(INVALID_PARAMS), response["error"]["code"]);
+
+        let line = respond(&App::new(), "{not json").unwrap();
+        assert!(line.contains(&PARSE_ERROR.to_string()));
+
+        let notification = json!({"jsonrpc": "2.0", "method": "explain", "params": ["E007"]});
+        assert_eq!(None, respond(&App::new(), &notification.to_string()));
+    }
+
+    #[test]
+    fn test_check_command_line_refuses_what_would_hang_or_touch_files() {
+        let app = App::new();
+        let check = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            check_command_line(app.command(), &args)
+        };
+
+        assert_eq!(Ok(()), check(&["config", "get", "verbosity"]));
+        assert!(matches!(
+            check(&["--verbose", "debug"]),
+            Err(Refusal::NotFound(_))
+        ));
+        assert!(matches!(
+            check(&["daemon", "start"]),
+            Err(Refusal::NeverReturns(_))
+        ));
+        assert!(matches!(
+            check(&["fixme1", "--watch=."]),
+            Err(Refusal::Argument(_))
+        ));
+        assert!(matches!(
+            check(&["fixme1", "@secrets"]),
+            Err(Refusal::Argument(_))
+        ));
+    }
+}
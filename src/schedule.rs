@@ -0,0 +1,231 @@
+//! Runs subcommands on a cron expression or a fixed interval, for the
+//! `schedule` subcommand and for the daemon's `schedule.<name>` settings.
+//!
+//! Each run is a child process running the executable again, so a failing
+//! or hanging run cannot take the scheduler down with it. A run that is due
+//! while the previous one is still going is skipped.
+
+use std::fmt;
+use std::process::{Child, Stdio};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Local};
+use log::{info, warn};
+
+use crate::{Error, Result};
+
+/// When a job runs.
+#[derive(Clone, Debug)]
+pub enum When {
+    /// At a fixed interval, the first run one interval from now.
+    Every(Duration),
+    /// Whenever the cron expression matches, in local time.
+    Cron(Box<croner::Cron>),
+}
+
+impl When {
+    /// Parses a five-field cron expression such as `*/5 * * * *`, or a
+    /// nickname such as `@hourly`.
+    pub fn cron(expression: &str) -> Result<Self> {
+        croner::Cron::new(expression)
+            .parse()
+            .map(|cron| When::Cron(Box::new(cron)))
+            .map_err(|e| {
+                Error::Subcommand(format!("invalid cron expression '{}': {}", expression, e))
+            })
+    }
+
+    /// Returns the first time after `after` the job is due.
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        match self {
+            When::Every(interval) => Some(after + *interval),
+            When::Cron(cron) => cron
+                .find_next_occurrence(&DateTime::<Local>::from(after), false)
+                .ok()
+                .map(SystemTime::from),
+        }
+    }
+}
+
+impl fmt::Display for When {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            When::Every(interval) => write!(f, "@every {}", humantime::format_duration(*interval)),
+            When::Cron(cron) => write!(f, "{}", cron.as_str()),
+        }
+    }
+}
+
+/// A subcommand to run on a schedule.
+#[derive(Debug)]
+pub struct Job {
+    name: String,
+    when: When,
+    args: Vec<String>,
+    jitter: Duration,
+    next: Option<SystemTime>,
+    runs: u64,
+    running: Option<Run>,
+}
+
+#[derive(Debug)]
+struct Run {
+    number: u64,
+    child: Child,
+    started: SystemTime,
+}
+
+impl Job {
+    /// Schedules `args`, a subcommand and its arguments, first due after `now`.
+    /// Each run is delayed by up to `jitter`, so that many instances do not
+    /// all start at the same moment.
+    pub fn new(
+        name: &str,
+        when: When,
+        args: Vec<String>,
+        jitter: Duration,
+        now: SystemTime,
+    ) -> Self {
+        let mut job = Job {
+            name: name.to_string(),
+            when,
+            args,
+            jitter,
+            next: None,
+            runs: 0,
+            running: None,
+        };
+        job.next = job.next_after(now);
+        job
+    }
+
+    /// Parses a job from a `schedule.<name>` setting: `@every <duration>` or
+    /// a cron expression, followed by the command line, e.g.
+    /// `*/5 * * * * fixme1 foo`.
+    pub fn parse(name: &str, spec: &str, now: SystemTime) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidValue {
+            key: format!("schedule.{}", name),
+            reason: reason.to_string(),
+        };
+        let words = shell_words::split(spec).map_err(|e| invalid(&e.to_string()))?;
+        let (when, args) = match words.first().map(String::as_str) {
+            Some("@every") => {
+                let interval = words
+                    .get(1)
+                    .ok_or_else(|| invalid("@every needs a duration, e.g. @every 5m"))?;
+                let interval =
+                    humantime::parse_duration(interval).map_err(|e| invalid(&e.to_string()))?;
+                (When::Every(interval), &words[2..])
+            }
+            Some(nickname) if nickname.starts_with('@') => (When::cron(nickname)?, &words[1..]),
+            _ if words.len() > 5 => (When::cron(&words[..5].join(" "))?, &words[5..]),
+            _ => {
+                return Err(invalid(
+                    "must be @every <duration> or a cron expression, then the command",
+                ))
+            }
+        };
+        if args.is_empty() {
+            return Err(invalid("names no command to run"));
+        }
+        Ok(Job::new(name, when, args.to_vec(), Duration::ZERO, now))
+    }
+
+    /// Returns when the next run is due, if ever.
+    pub fn next(&self) -> Option<SystemTime> {
+        self.next
+    }
+
+    /// Reaps a finished run and starts the next if it is due at `now`,
+    /// logging each.
+    pub fn poll(&mut self, now: SystemTime) -> Result<()> {
+        if let Some(run) = &mut self.running {
+            if let Some(status) = run.child.try_wait().map_err(Error::Output)? {
+                let took = now.duration_since(run.started).unwrap_or_default();
+                let took =
+                    humantime::format_duration(Duration::from_millis(took.as_millis() as u64));
+                match status.success() {
+                    true => info!("{} run {} succeeded after {}", self.name, run.number, took),
+                    false => warn!(
+                        "{} run {} failed after {}: {}",
+                        self.name, run.number, took, status
+                    ),
+                }
+                self.running = None;
+            }
+        }
+        let Some(next) = self.next.filter(|next| *next <= now) else {
+            return Ok(());
+        };
+        self.next = self.next_after(now);
+        self.runs += 1;
+        if let Some(run) = &self.running {
+            warn!(
+                "{} run {} skipped: run {} is still running",
+                self.name, self.runs, run.number
+            );
+            return Ok(());
+        }
+        let exe = std::env::current_exe().map_err(Error::Output)?;
+        let mut command = std::process::Command::new(&exe);
+        #[cfg(unix)]
+        crate::systemd::without_sockets(&mut command);
+        let child = command
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .spawn()
+            .map_err(Error::io(&exe))?;
+        info!(
+            "{} run {} started (pid {}), due {}: {}",
+            self.name,
+            self.runs,
+            child.id(),
+            humantime::format_rfc3339_seconds(next),
+            shell_words::join(&self.args)
+        );
+        self.running = Some(Run {
+            number: self.runs,
+            child,
+            started: now,
+        });
+        Ok(())
+    }
+
+    /// Waits for a run in progress to finish, e.g. before the scheduler exits.
+    pub fn finish(&mut self) {
+        if let Some(mut run) = self.running.take() {
+            info!("{} waiting for run {} to finish", self.name, run.number);
+            let _ = run.child.wait();
+        }
+    }
+
+    fn next_after(&self, now: SystemTime) -> Option<SystemTime> {
+        let jitter = self.jitter.mul_f64(fastrand::f64());
+        self.when.next_after(now).map(|next| next + jitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_schedules_command() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let job = Job::parse("backup", "@every 5m fixme1 'a b'", now).unwrap();
+        assert_eq!(vec!["fixme1", "a b"], job.args);
+        assert_eq!(Some(now + Duration::from_secs(300)), job.next());
+
+        let job = Job::parse("report", "0 * * * * fixme2", now).unwrap();
+        assert_eq!(vec!["fixme2"], job.args);
+        let next = job.next().unwrap().duration_since(UNIX_EPOCH).unwrap();
+        assert_eq!(0, next.as_secs() % 60);
+        assert!(next.as_secs() - 1_700_000_000 <= 3600);
+
+        assert!(Job::parse("empty", "@every 5m", now).is_err());
+        assert!(Job::parse("short", "* * * fixme1", now).is_err());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::{values, Error, Result};
+
+/// A setting the application understands, resolvable through the settings chain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Key {
+    /// Dotted name of the key, e.g. `theme.header`. A final `*` stands for
+    /// any one name segment, e.g. `hooks.pre.*` covers `hooks.pre.fixme1`.
+    pub name: &'static str,
+    /// One-line description shown in help and listings.
+    pub description: &'static str,
+    /// Value used when no source provides one.
+    pub default: Option<&'static str>,
+    /// Values the key accepts.
+    pub kind: ValueKind,
+    /// Whether the value is sensitive and must never be echoed.
+    pub secret: bool,
+}
+
+/// The values a [`Key`] accepts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueKind {
+    /// Any text.
+    Text,
+    /// `true` or `false`.
+    Bool,
+    /// One of the listed values.
+    OneOf(&'static [&'static str]),
+    /// A style specification such as `bold underline yellow`.
+    Style,
+    /// A locale identifier such as `de-DE`.
+    Locale,
+    /// An absolute URL with one of the listed schemes, or any if none are listed.
+    Url(&'static [&'static str]),
+    /// An IPv4 or IPv6 address.
+    IpAddr,
+    /// An IP address and port, e.g. `127.0.0.1:8080`.
+    SocketAddr,
+    /// An email address.
+    Email,
+    /// A whole number, optionally with a suffix such as `k`; see [`values::count`].
+    Count,
+    /// A number of bytes, optionally with a unit such as `MiB`; see [`values::size`].
+    Size,
+    /// A duration such as `30s`; see [`values::duration`].
+    Duration,
+    /// A path, checked as the [`values::PathArg`] says.
+    Path(values::PathArg),
+}
+
+impl Key {
+    /// Whether the name ends with `*`, standing for a family of keys.
+    pub fn is_pattern(&self) -> bool {
+        self.name.ends_with('*')
+    }
+
+    /// Whether `name` is this key, or one of the family it stands for.
+    pub fn matches(&self, name: &str) -> bool {
+        match self.name.strip_suffix('*') {
+            Some(prefix) => name
+                .strip_prefix(prefix)
+                .is_some_and(|rest| !rest.is_empty() && !rest.contains('.')),
+            None => self.name == name,
+        }
+    }
+
+    /// Checks that `value` is acceptable for this key, explaining why not.
+    pub fn validate(&self, value: &str) -> std::result::Result<(), String> {
+        let valid = match self.kind {
+            ValueKind::Url(schemes) => return values::url(schemes)(value).map(drop),
+            ValueKind::IpAddr => return values::ip_addr(value).map(drop),
+            ValueKind::SocketAddr => return values::socket_addr(value).map(drop),
+            ValueKind::Email => return values::email(value).map(drop),
+            ValueKind::Count => return values::count(value).map(drop),
+            ValueKind::Bool => return values::boolean(value).map(drop),
+            ValueKind::Size => return values::size(value).map(drop),
+            ValueKind::Duration => return values::duration(value).map(drop),
+            ValueKind::Path(path) => return path.parse(value).map(drop),
+            ValueKind::Text => true,
+            ValueKind::OneOf(values) => values.contains(&value),
+            ValueKind::Style => crate::theme::parse_style(value).is_some(),
+            ValueKind::Locale => value.parse::<unic_langid::LanguageIdentifier>().is_ok(),
+        };
+        if valid {
+            return Ok(());
+        }
+        Err(match self.kind {
+            ValueKind::OneOf(values) => format!("must be one of: {}", values.join(", ")),
+            ValueKind::Style => {
+                "must be effects and a color, e.g. \"bold underline yellow\"".to_string()
+            }
+            ValueKind::Locale => "must be a locale such as de-DE".to_string(),
+            ValueKind::Text => unreachable!("any text is valid"),
+            ValueKind::Bool
+            | ValueKind::Url(_)
+            | ValueKind::IpAddr
+            | ValueKind::SocketAddr
+            | ValueKind::Email
+            | ValueKind::Count
+            | ValueKind::Size
+            | ValueKind::Duration
+            | ValueKind::Path(_) => {
+                unreachable!("checked by their parsers")
+            }
+        })
+    }
+}
+
+/// Every key the application reads from its settings chain.
+pub const KEYS: &[Key] = &[
+    Key {
+        name: "verbosity",
+        description: "Logging verbosity level: off, error, warn, info, debug or trace",
+        default: Some("info"),
+        kind: ValueKind::OneOf(&["off", "error", "warn", "info", "debug", "trace"]),
+        secret: false,
+    },
+    Key {
+        name: "ascii",
+        description: "Draw only plain ASCII instead of Unicode symbols: true or false",
+        default: Some("false"),
+        kind: ValueKind::Bool,
+        secret: false,
+    },
+    Key {
+        name: "accessible",
+        description: "Suit output to screen readers: no spinners or progress bars, labeled lines instead of tables and no colors: true or false",
+        default: Some("false"),
+        kind: ValueKind::Bool,
+        secret: false,
+    },
+    Key {
+        name: "update.check",
+        description: "Look for a newer release once a day and mention it: true or false",
+        default: Some("true"),
+        kind: ValueKind::Bool,
+        secret: false,
+    },
+    Key {
+        name: "telemetry",
+        description: "Record anonymous usage statistics and upload them in batches: true or false",
+        default: Some("false"),
+        kind: ValueKind::Bool,
+        secret: false,
+    },
+    Key {
+        name: "seed",
+        description: "Seed of the random numbers subcommands draw, to reproduce a run; defaults to a fresh one each run",
+        default: None,
+        kind: ValueKind::Count,
+        secret: false,
+    },
+    Key {
+        name: "locale",
+        description: "Language of messages, and of numbers and dates in human output, e.g. de-DE; defaults to LC_ALL, LC_MESSAGES or LANG",
+        default: None,
+        kind: ValueKind::Locale,
+        secret: false,
+    },
+    Key {
+        name: "daemon.grpc",
+        description: "Address the daemon serves gRPC control on, e.g. 127.0.0.1:50051; needs the grpc feature",
+        default: None,
+        kind: ValueKind::SocketAddr,
+        secret: false,
+    },
+    Key {
+        name: "daemon.grpc_token",
+        description: "Token gRPC clients must send as 'authorization: Bearer <token>'; needed to serve gRPC beyond loopback",
+        default: None,
+        kind: ValueKind::Text,
+        secret: true,
+    },
+    Key {
+        name: "daemon.http",
+        description: "Address the daemon serves /healthz and /metrics on over HTTP, e.g. 127.0.0.1:9090",
+        default: None,
+        kind: ValueKind::SocketAddr,
+        secret: false,
+    },
+    Key {
+        name: "network.timeout",
+        description: "How long to wait for a connection, and for data once connected, e.g. 30s",
+        default: Some("30s"),
+        kind: ValueKind::Duration,
+        secret: false,
+    },
+    Key {
+        name: "network.proxy",
+        description: "Proxy for every request, e.g. http://proxy:3128; defaults to $HTTPS_PROXY",
+        default: None,
+        kind: ValueKind::Url(&["http", "socks", "socks4", "socks4a", "socks5"]),
+        secret: false,
+    },
+    Key {
+        name: "network.ca_file",
+        description: "PEM file of certificate authorities to trust besides the built-in ones",
+        default: None,
+        kind: ValueKind::Path(values::PathArg::new().file()),
+        secret: false,
+    },
+    Key {
+        name: "auth.token",
+        description: "Token used to authenticate with the service",
+        default: None,
+        kind: ValueKind::Text,
+        secret: true,
+    },
+    Key {
+        name: "plugins.public_key",
+        description:
+            "Minisign public key plugins must be signed with; unset accepts unsigned plugins",
+        default: None,
+        kind: ValueKind::Text,
+        secret: false,
+    },
+    Key {
+        name: "theme.header",
+        description: "Style of section headings in help output",
+        default: None,
+        kind: ValueKind::Style,
+        secret: false,
+    },
+    Key {
+        name: "theme.usage",
+        description: "Style of the usage line in help output",
+        default: None,
+        kind: ValueKind::Style,
+        secret: false,
+    },
+    Key {
+        name: "theme.literal",
+        description: "Style of literal flags and commands in help output",
+        default: None,
+        kind: ValueKind::Style,
+        secret: false,
+    },
+    Key {
+        name: "theme.placeholder",
+        description: "Style of value placeholders in help output",
+        default: None,
+        kind: ValueKind::Style,
+        secret: false,
+    },
+    Key {
+        name: "theme.error",
+        description: "Style of the error label in error output",
+        default: None,
+        kind: ValueKind::Style,
+        secret: false,
+    },
+    Key {
+        name: "theme.valid",
+        description: "Style of suggested valid values in error output",
+        default: None,
+        kind: ValueKind::Style,
+        secret: false,
+    },
+    Key {
+        name: "theme.invalid",
+        description: "Style of rejected values in error output",
+        default: None,
+        kind: ValueKind::Style,
+        secret: false,
+    },
+    Key {
+        name: "alias.*",
+        description: "Command line the named alias stands for, e.g. \"fixme1 --verbose debug\"",
+        default: None,
+        kind: ValueKind::Text,
+        secret: false,
+    },
+    Key {
+        name: "hooks.pre.*",
+        description: "Shell command run before the named subcommand; it is not run if this fails",
+        default: None,
+        kind: ValueKind::Text,
+        secret: false,
+    },
+    Key {
+        name: "hooks.post.*",
+        description: "Shell command run after the named subcommand, whether or not it succeeded",
+        default: None,
+        kind: ValueKind::Text,
+        secret: false,
+    },
+    Key {
+        name: "schedule.*",
+        description: "Subcommand the daemon runs on a schedule: \"@every <duration>\" or a cron expression, then the command line, e.g. \"@every 1h fixme1 foo\"",
+        default: None,
+        kind: ValueKind::Text,
+        secret: false,
+    },
+];
+
+/// A constraint between keys of the [`KEYS`], enforced on the command line
+/// through the clap groups and argument relations that [`crate::AppBuilder`]
+/// derives from it (see [`crate::flags::add_relations`]), and in the
+/// settings by [`check_relations`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Relation {
+    /// At most one of the keys may be set.
+    Exclusive(&'static [&'static str]),
+    /// The first key may only be set along with the second.
+    Requires(&'static str, &'static str),
+    /// The two keys may not both be set.
+    Conflicts(&'static str, &'static str),
+}
+
+/// Every [`Relation`] between the keys the application reads.
+// FIXME: declare how the application's settings depend on each other, e.g.
+// `Relation::Exclusive(&["color", "plain"])`.
+pub const RELATIONS: &[Relation] = &[];
+
+/// Checks `relations` given which keys `is_set`.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidValue`] for the first key that breaks one of them.
+pub fn check_relations<F: Fn(&str) -> bool>(relations: &[Relation], is_set: F) -> Result<()> {
+    let broken = |key: &str, reason: String| {
+        Err(Error::InvalidValue {
+            key: key.to_string(),
+            reason,
+        })
+    };
+    for relation in relations {
+        match *relation {
+            Relation::Exclusive(keys) => {
+                let set: Vec<&str> = keys.iter().copied().filter(|key| is_set(key)).collect();
+                if let [first, second, ..] = set[..] {
+                    return broken(second, format!("cannot be set along with '{}'", first));
+                }
+            }
+            Relation::Requires(key, required) if is_set(key) && !is_set(required) => {
+                return broken(key, format!("requires '{}' to be set too", required));
+            }
+            Relation::Conflicts(key, other) if is_set(key) && is_set(other) => {
+                return broken(key, format!("cannot be set along with '{}'", other));
+            }
+            Relation::Requires(..) | Relation::Conflicts(..) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns the schema entry for `name`.
+///
+/// # Errors
+///
+/// Returns [`Error::UnknownKey`], with suggestions for similarly named keys,
+/// if `name` is not in the schema.
+pub fn lookup(name: &str) -> Result<&'static Key> {
+    KEYS.iter()
+        .find(|k| k.matches(name))
+        .ok_or_else(|| Error::UnknownKey {
+            key: name.to_string(),
+            suggestions: suggestions(name),
+        })
+}
+
+/// Returns the names of schema keys within a small edit distance of `name`,
+/// closest first.
+pub fn suggestions(name: &str) -> Vec<String> {
+    let max_distance = (name.len() / 3).max(2);
+    let mut candidates: Vec<(usize, &str)> = KEYS
+        .iter()
+        .map(|k| (strsim::damerau_levenshtein(name, k.name), k.name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort();
+    candidates
+        .into_iter()
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_suggests_similar_keys() {
+        assert!(lookup("verbosity").is_ok());
+
+        let err = lookup("verbostiy").unwrap_err();
+        assert_eq!(
+            "unknown key 'verbostiy', did you mean 'verbosity'?",
+            err.to_string()
+        );
+
+        let err = lookup("color").unwrap_err();
+        assert_eq!("unknown key 'color'", err.to_string());
+    }
+
+    #[test]
+    fn test_validate_checks_kind() {
+        let verbosity = lookup("verbosity").unwrap();
+        assert!(verbosity.validate("debug").is_ok());
+        assert_eq!(
+            Err("must be one of: off, error, warn, info, debug, trace".to_string()),
+            verbosity.validate("loud")
+        );
+        assert!(lookup("theme.header").unwrap().validate("bold red").is_ok());
+        assert!(lookup("ascii").unwrap().validate("maybe").is_err());
+        assert!(lookup("ascii").unwrap().validate("on").is_ok());
+    }
+
+    #[test]
+    fn test_check_relations_names_the_key_at_fault() {
+        let relations = [
+            Relation::Exclusive(&["ascii", "theme.header"]),
+            Relation::Requires("network.ca_file", "network.proxy"),
+        ];
+        let check = |set: &[&str]| {
+            check_relations(&relations, |key| set.contains(&key)).map_err(|e| e.to_string())
+        };
+
+        assert!(check(&["ascii", "network.proxy"]).is_ok());
+        assert_eq!(
+            Err("invalid value for 'theme.header': cannot be set along with 'ascii'".to_string()),
+            check(&["ascii", "theme.header"])
+        );
+        assert_eq!(
+            Err(
+                "invalid value for 'network.ca_file': requires 'network.proxy' to be set too"
+                    .to_string()
+            ),
+            check(&["network.ca_file"])
+        );
+    }
+
+    #[test]
+    fn test_lookup_matches_patterns() {
+        assert_eq!("hooks.pre.*", lookup("hooks.pre.fixme1").unwrap().name);
+        assert!(lookup("hooks.pre").is_err());
+        assert!(lookup("hooks.pre.fixme1.extra").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! Running the daemon as a native Windows service.
+
+use std::ffi::OsString;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_dispatcher;
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+use crate::context::CancellationToken;
+use crate::identity::IDENTITY;
+use crate::{Error, Result};
+
+/// Name the service is registered under.
+pub const NAME: &str = IDENTITY.name;
+
+/// Registers the current executable as a service started at boot, running
+/// `service run`.
+pub fn install() -> Result<()> {
+    let manager = manager(ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE)?;
+    let info = ServiceInfo {
+        name: OsString::from(NAME),
+        // Shown in the Services console.
+        display_name: OsString::from(format!("{} daemon", IDENTITY.display_name)),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe().map_err(Error::Output)?,
+        launch_arguments: vec![OsString::from("service"), OsString::from("run")],
+        dependencies: Vec::new(),
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager
+        .create_service(&info, ServiceAccess::CHANGE_CONFIG)
+        .map_err(failed)?;
+    service
+        .set_description(IDENTITY.description)
+        .map_err(failed)
+}
+
+/// Stops the service if it is running, and removes it.
+pub fn uninstall() -> Result<()> {
+    let manager = manager(ServiceManagerAccess::CONNECT)?;
+    let service = manager
+        .open_service(
+            NAME,
+            ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+        )
+        .map_err(failed)?;
+    if service.query_status().map_err(failed)?.current_state != ServiceState::Stopped {
+        service.stop().map_err(failed)?;
+    }
+    service.delete().map_err(failed)
+}
+
+/// Connects to the service control manager as the service, then calls
+/// `serve` until the manager asks the service to stop, which cancels
+/// `cancellation`.
+///
+/// # Errors
+///
+/// Returns [`Error::Subcommand`] if the process was not started by the
+/// service control manager, or `serve`'s error.
+pub fn run<F>(cancellation: CancellationToken, serve: F) -> Result<()>
+where
+    F: FnOnce() -> Result<()>,
+{
+    let (started, running) = mpsc::channel();
+    let (stopped, finished) = mpsc::channel();
+    SERVICE
+        .set(Shared {
+            cancellation,
+            started: Mutex::new(started.clone()),
+            finished: Mutex::new(finished),
+        })
+        .map_err(|_| Error::Subcommand("the service is already running".to_string()))?;
+
+    let dispatcher = std::thread::spawn(move || {
+        // Returns once the service has stopped, or at once if not run as a service.
+        if let Err(e) = service_dispatcher::start(NAME, ffi_service_main) {
+            let _ = started.send(Err(failed(e)));
+        }
+    });
+    let result = running
+        .recv()
+        .unwrap_or_else(|_| Err(Error::Subcommand("the service did not start".to_string())))
+        .and_then(|()| serve());
+    let _ = stopped.send(());
+    let _ = dispatcher.join();
+    result
+}
+
+/// What [`run`] shares with the service's entry point, which the service
+/// control manager calls on a thread of its own.
+struct Shared {
+    cancellation: CancellationToken,
+    started: Mutex<Sender<Result<()>>>,
+    finished: Mutex<Receiver<()>>,
+}
+
+static SERVICE: OnceLock<Shared> = OnceLock::new();
+
+windows_service::define_windows_service!(ffi_service_main, service_main);
+
+fn service_main(_arguments: Vec<OsString>) {
+    let shared = SERVICE.get().expect("run sets up the service first");
+    let started = shared.started.lock().expect("lock is not poisoned").clone();
+    if let Err(e) = handle_controls(shared, &started) {
+        let _ = started.send(Err(e));
+    }
+}
+
+/// Reports the service running, waits for [`run`] to finish serving, then
+/// reports it stopped.
+fn handle_controls(shared: &Shared, started: &Sender<Result<()>>) -> Result<()> {
+    let cancellation = shared.cancellation.clone();
+    let status = service_control_handler::register(NAME, move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            cancellation.cancel();
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    })
+    .map_err(failed)?;
+    let report = |current_state, controls_accepted| {
+        status
+            .set_service_status(ServiceStatus {
+                service_type: ServiceType::OWN_PROCESS,
+                current_state,
+                controls_accepted,
+                exit_code: ServiceExitCode::NO_ERROR,
+                checkpoint: 0,
+                wait_hint: Duration::default(),
+                process_id: None,
+            })
+            .map_err(failed)
+    };
+    report(
+        ServiceState::Running,
+        ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+    )?;
+    let _ = started.send(Ok(()));
+    let _ = shared.finished.lock().expect("lock is not poisoned").recv();
+    report(ServiceState::Stopped, ServiceControlAccept::empty())
+}
+
+fn manager(access: ServiceManagerAccess) -> Result<ServiceManager> {
+    ServiceManager::local_computer(None::<&str>, access).map_err(failed)
+}
+
+fn failed(error: windows_service::Error) -> Error {
+    Error::Subcommand(format!("service control manager: {}", error))
+}
@@ -0,0 +1,634 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use clap::ArgMatches;
+use cor_args::{ArgHandler, EnvHandler, Handler};
+use serde::Serialize;
+
+use crate::{metrics, schema, Error, Result};
+
+/// Prefix of environment variables consulted by the settings chain.
+pub const ENV_PREFIX: &str = crate::identity::IDENTITY.env_prefix;
+
+/// Shown in place of the value of a secret setting.
+pub const MASK: &str = "********";
+
+/// A [`Handler`] that looks keys up in a JSON configuration file.
+///
+/// Keys may address nested objects using dots, e.g. `theme.header` resolves
+/// `{"theme": {"header": "..."}}`. String values are returned verbatim; other
+/// scalar values are returned in their JSON representation. The file is read
+/// on the first lookup only, so a handler sees the file as it was then.
+pub struct JsonFileHandler<'a> {
+    file_path: PathBuf,
+    root: OnceLock<Option<serde_json::Value>>,
+    next: Option<Box<dyn Handler + 'a>>,
+}
+
+impl<'a> JsonFileHandler<'a> {
+    pub fn new<P: Into<PathBuf>>(file_path: P) -> Self {
+        JsonFileHandler {
+            file_path: file_path.into(),
+            root: OnceLock::new(),
+            next: None,
+        }
+    }
+
+    pub fn next(mut self, handler: Box<dyn Handler + 'a>) -> Self {
+        self.next = Some(handler);
+        self
+    }
+
+    /// Reads and parses the file, returning `None` if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ConfigParse`] if the file is not valid JSON, or
+    /// [`Error::Io`] if it exists but cannot be read.
+    pub fn load(&self) -> Result<Option<serde_json::Value>> {
+        let content = match std::fs::read_to_string(&self.file_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::io(&self.file_path)(e)),
+        };
+        match serde_json::from_str(&content) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => Err(Error::config_parse(&self.file_path, content, e)),
+        }
+    }
+
+    fn lookup(&self, key: &str) -> Option<String> {
+        let cache = match self.root.get() {
+            Some(_) => &metrics::SETTINGS_CACHE_HITS,
+            None => &metrics::SETTINGS_CACHE_MISSES,
+        };
+        metrics::increment(cache, &[]);
+        let root = self
+            .root
+            .get_or_init(|| self.load().ok().flatten())
+            .as_ref()?;
+        let value = key
+            .split('.')
+            .try_fold(root, |value, part| value.get(part))?;
+        match value {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Null
+            | serde_json::Value::Array(_)
+            | serde_json::Value::Object(_) => None,
+            other => Some(other.to_string()),
+        }
+    }
+}
+
+impl<'a> Handler for JsonFileHandler<'a> {
+    fn handle_request(&self, key: &str) -> Option<String> {
+        self.lookup(key)
+            .or_else(|| self.next.as_ref().and_then(|n| n.handle_request(key)))
+    }
+}
+
+/// A [`Handler`] that looks keys up in environment variables named the way
+/// shells can set them; see [`shell_variable`].
+pub struct ShellEnvHandler<'a> {
+    prefix: String,
+    next: Option<Box<dyn Handler + 'a>>,
+}
+
+impl<'a> ShellEnvHandler<'a> {
+    pub fn new<S: Into<String>>(prefix: S) -> Self {
+        ShellEnvHandler {
+            prefix: prefix.into(),
+            next: None,
+        }
+    }
+
+    pub fn next(mut self, handler: Box<dyn Handler + 'a>) -> Self {
+        self.next = Some(handler);
+        self
+    }
+}
+
+impl<'a> Handler for ShellEnvHandler<'a> {
+    fn handle_request(&self, key: &str) -> Option<String> {
+        std::env::var(shell_variable(&self.prefix, key))
+            .ok()
+            .or_else(|| self.next.as_ref().and_then(|n| n.handle_request(key)))
+    }
+}
+
+/// Returns the environment variable for `key` that shells can set: `prefix`
+/// followed by the key with dots and dashes replaced by underscores, e.g.
+/// `FIXME_update_check` for `update.check`.
+pub fn shell_variable(prefix: &str, key: &str) -> String {
+    format!("{}{}", prefix, key.replace(['.', '-'], "_"))
+}
+
+/// Where the settings chain looks for settings, beyond the command line.
+///
+/// The default reads `FIXME_` environment variables and the user's JSON
+/// configuration file; see [`crate::AppBuilder`] for changing either.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    /// Prefix of environment variables consulted by the chain.
+    pub env_prefix: String,
+    /// Configuration files consulted after the environment, in order of
+    /// precedence. The first is the one settings are written to.
+    pub config_paths: Vec<PathBuf>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout {
+            env_prefix: ENV_PREFIX.to_string(),
+            config_paths: vec![default_config_path()],
+        }
+    }
+}
+
+impl Layout {
+    /// Returns the configuration file settings are written to.
+    pub fn config_path(&self) -> PathBuf {
+        self.config_paths
+            .first()
+            .cloned()
+            .unwrap_or_else(default_config_path)
+    }
+
+    /// Checks that the configuration files that exist can be parsed and only
+    /// contain keys from the [`schema`], and that the environment and files
+    /// together respect the [schema's relations](schema::RELATIONS).
+    ///
+    /// Handlers skip unparseable files, so this is run up front to report the
+    /// problem instead of silently ignoring the file.
+    pub fn validate(&self) -> Result<()> {
+        for path in &self.config_paths {
+            if let Some(root) = JsonFileHandler::new(path).load()? {
+                for key in flatten_keys(&root) {
+                    schema::lookup(&key)?;
+                }
+            }
+        }
+        let chain = self.chain(None);
+        schema::check_relations(schema::RELATIONS, |key| chain.handle_request(key).is_some())
+    }
+
+    /// Describes every handler of the [`Layout::chain`], in order of precedence.
+    pub fn sources(&self) -> Vec<Source> {
+        let variables = std::env::vars_os()
+            .filter(|(name, _)| name.to_string_lossy().starts_with(&self.env_prefix))
+            .count();
+        let mut sources = vec![
+            Source {
+                kind: "arguments",
+                target: "command line".to_string(),
+                readable: true,
+                detail: "options given to this run".to_string(),
+            },
+            Source {
+                kind: "environment",
+                target: format!("{}*", self.env_prefix),
+                readable: true,
+                detail: format!("{} variable(s) set", variables),
+            },
+        ];
+        for path in &self.config_paths {
+            let (readable, detail) = match JsonFileHandler::new(path).load() {
+                Ok(Some(root)) => (true, format!("{} setting(s)", flatten_keys(&root).len())),
+                Ok(None) => (false, "does not exist".to_string()),
+                Err(e) => (false, e.to_string()),
+            };
+            sources.push(Source {
+                kind: "file",
+                target: path.display().to_string(),
+                readable,
+                detail,
+            });
+        }
+        sources
+    }
+
+    /// Returns the handler of the [`Layout::chain`] that provides `key`, or
+    /// `None` if none does.
+    pub fn origin(&self, matches: Option<&ArgMatches>, key: &str) -> Option<Origin> {
+        if matches.is_some_and(|matches| ArgHandler::new(matches).handle_request(key).is_some()) {
+            return Some(Origin::CommandLine);
+        }
+        let verbatim = format!("{}{}", self.env_prefix, key);
+        for name in [verbatim, shell_variable(&self.env_prefix, key)] {
+            if std::env::var(&name).is_ok() {
+                return Some(Origin::Environment(name));
+            }
+        }
+        self.config_paths
+            .iter()
+            .find(|path| JsonFileHandler::new(path).handle_request(key).is_some())
+            .map(|path| Origin::File(path.clone()))
+    }
+
+    /// Resolves every setting through the [`Layout::chain`]: each key of the
+    /// [`schema`] that has a value or default, and each member of a family
+    /// such as `hooks.pre.*` that a configuration file sets.
+    pub fn resolve(&self, matches: Option<&ArgMatches>) -> Vec<Resolved> {
+        let chain = self.chain(matches);
+        let mut names: BTreeSet<String> = schema::KEYS
+            .iter()
+            .filter(|key| !key.is_pattern())
+            .map(|key| key.name.to_string())
+            .collect();
+        for path in &self.config_paths {
+            if let Ok(Some(root)) = JsonFileHandler::new(path).load() {
+                names.extend(
+                    flatten_keys(&root)
+                        .into_iter()
+                        .filter(|name| schema::lookup(name).is_ok_and(|key| key.is_pattern())),
+                );
+            }
+        }
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let (value, source) = match chain.handle_request(&name) {
+                    Some(value) => {
+                        let source = self.origin(matches, &name);
+                        let source = source.map(|origin| origin.to_string());
+                        (value, source.unwrap_or_else(|| "unknown".to_string()))
+                    }
+                    None => {
+                        let default = schema::lookup(&name).ok()?.default?;
+                        (default.to_string(), "default".to_string())
+                    }
+                };
+                Some(Resolved {
+                    key: name,
+                    value,
+                    source,
+                })
+            })
+            .collect()
+    }
+
+    /// Lists every key of the [`schema`] for `--debug-config`: the settings
+    /// [`Layout::resolve`] finds, with secrets masked, and the keys nothing
+    /// sets, with an empty value and the source `unset`.
+    pub fn debug(&self, matches: Option<&ArgMatches>) -> Vec<Resolved> {
+        let mut resolved = self.resolve(matches);
+        for key in schema::KEYS.iter().filter(|key| !key.is_pattern()) {
+            if !resolved.iter().any(|setting| setting.key == key.name) {
+                resolved.push(Resolved {
+                    key: key.name.to_string(),
+                    value: String::new(),
+                    source: "unset".to_string(),
+                });
+            }
+        }
+        for setting in &mut resolved {
+            let secret = schema::lookup(&setting.key).is_ok_and(|key| key.secret);
+            if secret && setting.source != "unset" {
+                setting.value = MASK.to_string();
+            }
+        }
+        resolved.sort_by(|a, b| a.key.cmp(&b.key));
+        resolved
+    }
+
+    /// Builds the settings chain: command-line arguments, then environment,
+    /// then each config file.
+    ///
+    /// A key's environment variable is the prefix followed by the key, e.g.
+    /// `FIXME_update.check`, or its [shell-friendly](shell_variable) form
+    /// `FIXME_update_check`; the former wins if both are set. When `matches`
+    /// is `None` (e.g. before arguments are parsed) the chain starts at the
+    /// environment.
+    pub fn chain<'a>(&self, matches: Option<&'a ArgMatches>) -> Box<dyn Handler + 'a> {
+        let mut shell = ShellEnvHandler::new(self.env_prefix.as_str());
+        let mut files = self.config_paths.iter().rev();
+        if let Some(last) = files.next() {
+            let mut file = JsonFileHandler::new(last);
+            for path in files {
+                file = JsonFileHandler::new(path).next(Box::new(file));
+            }
+            shell = shell.next(Box::new(file));
+        }
+        let env = EnvHandler::new()
+            .prefix(self.env_prefix.as_str())
+            .next(Box::new(shell));
+        match matches {
+            Some(matches) => Box::new(ArgHandler::new(matches).next(env.into())),
+            None => Box::new(env),
+        }
+    }
+}
+
+/// Returns the path of the user's JSON configuration file, in the
+/// [configuration directory](crate::paths::config_dir).
+fn default_config_path() -> PathBuf {
+    crate::paths::config_dir().join("config.json")
+}
+
+/// Returns the configuration file settings are written to, with the default [`Layout`].
+pub fn config_path() -> PathBuf {
+    Layout::default().config_path()
+}
+
+/// Checks the configuration files of the default [`Layout`]; see [`Layout::validate`].
+pub fn validate() -> Result<()> {
+    Layout::default().validate()
+}
+
+/// Returns the dotted names of every non-object value under `value`.
+fn flatten_keys(value: &serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .flat_map(|(name, child)| match child {
+                serde_json::Value::Object(_) => flatten_keys(child)
+                    .into_iter()
+                    .map(|sub| format!("{}.{}", name, sub))
+                    .collect(),
+                _ => vec![name.clone()],
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Stores `value` under the dotted `key` in the JSON configuration file at `path`,
+/// creating the file and any intermediate objects as needed.
+///
+/// The file is replaced atomically and keeps its permissions, except that
+/// once it holds a [secret](schema::Key::secret) it is created readable by
+/// its owner only.
+///
+/// # Errors
+///
+/// Returns an error if the existing file cannot be parsed, a parent of `key`
+/// holds a non-object value, or the file cannot be written.
+pub fn set<P: Into<PathBuf>>(path: P, key: &str, value: &str) -> Result<()> {
+    let path = path.into();
+    let mut root = JsonFileHandler::new(&path)
+        .load()?
+        .unwrap_or_else(|| serde_json::json!({}));
+    let mut node = &mut root;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        let map = node.as_object_mut().ok_or_else(|| {
+            Error::Subcommand(format!("cannot set '{}': a parent is not an object", key))
+        })?;
+        if parts.peek().is_none() {
+            map.insert(
+                part.to_string(),
+                serde_json::Value::String(value.to_string()),
+            );
+            break;
+        }
+        node = map.entry(part).or_insert_with(|| serde_json::json!({}));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(Error::io(parent))?;
+    }
+    let content = serde_json::to_string_pretty(&root).expect("JSON values always serialize");
+    let secret = schema::lookup(key).is_ok_and(|k| k.secret);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temporary = path.with_file_name(name);
+    write_replacement(&temporary, &path, secret, (content + "\n").as_bytes())
+        .map_err(Error::io(&temporary))?;
+    std::fs::rename(&temporary, &path).map_err(Error::io(&path))
+}
+
+/// Writes `content` to the new file `temporary` that is to replace `path`,
+/// created with the permissions of `path`, or readable by its owner only
+/// when `private`, so that it is never readable by others.
+fn write_replacement(
+    temporary: &Path,
+    path: &Path,
+    private: bool,
+    content: &[u8],
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    // A leftover of an earlier run would keep its own permissions.
+    let _ = std::fs::remove_file(temporary);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        let mode = std::fs::metadata(path).map_or(0o666, |m| m.permissions().mode() & 0o777);
+        options.mode(if private { mode & 0o600 } else { mode });
+    }
+    #[cfg(not(unix))]
+    let _ = (path, private);
+    let mut file = options.open(temporary)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// One handler of the settings chain, as described by [`Layout::sources`].
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Source {
+    /// What kind of handler it is: `arguments`, `environment` or `file`.
+    pub kind: &'static str,
+    /// What it reads: the variable prefix, or the file's path.
+    pub target: String,
+    /// Whether it can currently provide settings.
+    pub readable: bool,
+    /// What it currently provides, or why it cannot.
+    pub detail: String,
+}
+
+/// The handler of the [`Layout::chain`] a setting comes from, as found by
+/// [`Layout::origin`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Origin {
+    CommandLine,
+    /// The named environment variable.
+    Environment(String),
+    /// The configuration file at the path.
+    File(PathBuf),
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::CommandLine => write!(f, "command line"),
+            Origin::Environment(name) => write!(f, "environment {}", name),
+            Origin::File(path) => write!(f, "file {}", path.display()),
+        }
+    }
+}
+
+/// A setting in effect, as listed by [`Layout::resolve`].
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Resolved {
+    /// Dotted name of the setting.
+    pub key: String,
+    /// The value in effect.
+    pub value: String,
+    /// Where the value comes from, as described by [`Layout::origin`], or
+    /// `default`.
+    pub source: String,
+}
+
+/// Builds the settings chain of the default [`Layout`]; see [`Layout::chain`].
+pub fn chain<'a>(matches: Option<&'a ArgMatches>) -> Box<dyn Handler + 'a> {
+    Layout::default().chain(matches)
+}
+
+/// A settings source that has to be queried asynchronously, such as a remote
+/// configuration service.
+#[cfg(feature = "async")]
+pub trait AsyncHandler: Send + Sync {
+    /// Returns the value of `key`, or `None` if the source does not provide it.
+    fn handle_request<'a>(&'a self, key: &'a str)
+        -> crate::commands::BoxFuture<'a, Option<String>>;
+}
+
+/// Resolves `key` through the `local` chain, then each of the `remote`
+/// handlers in turn, so that local sources override remote ones.
+#[cfg(feature = "async")]
+pub async fn resolve_async(
+    key: &str,
+    local: &dyn Handler,
+    remote: &[Box<dyn AsyncHandler>],
+) -> Option<String> {
+    if let Some(value) = local.handle_request(key) {
+        return Some(value);
+    }
+    for handler in remote {
+        if let Some(value) = handler.handle_request(key).await {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Resolves every [`schema`] key through `handler`, falling back to its
+/// default; keys without any value, and pattern keys, are left out.
+pub fn snapshot(handler: &dyn Handler) -> BTreeMap<&'static str, String> {
+    schema::KEYS
+        .iter()
+        .filter(|key| !key.is_pattern())
+        .filter_map(|key| {
+            handler
+                .handle_request(key.name)
+                .or_else(|| key.default.map(str::to_string))
+                .map(|value| (key.name, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_file_handler_resolves_nested_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"verbosity": "debug", "theme": {"header": "red"}, "n": 3}"#,
+        )
+        .unwrap();
+        let handler = JsonFileHandler::new(&path);
+
+        assert_eq!(
+            Some("debug".to_string()),
+            handler.handle_request("verbosity")
+        );
+        assert_eq!(
+            Some("red".to_string()),
+            handler.handle_request("theme.header")
+        );
+        assert_eq!(Some("3".to_string()), handler.handle_request("n"));
+        assert_eq!(None, handler.handle_request("theme.missing"));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_resolve_async_prefers_local_sources() {
+        struct Remote;
+        impl AsyncHandler for Remote {
+            fn handle_request<'a>(
+                &'a self,
+                key: &'a str,
+            ) -> crate::commands::BoxFuture<'a, Option<String>> {
+                Box::pin(async move { Some(format!("remote {}", key)) })
+            }
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"verbosity": "debug"}"#).unwrap();
+        let local = JsonFileHandler::new(&path);
+        let remote: Vec<Box<dyn AsyncHandler>> = vec![Box::new(Remote)];
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let resolve = |key| runtime.block_on(resolve_async(key, &local, &remote));
+
+        assert_eq!(Some("debug".to_string()), resolve("verbosity"));
+        assert_eq!(Some("remote locale".to_string()), resolve("locale"));
+    }
+
+    #[test]
+    fn test_debug_lists_every_key_and_masks_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{"auth": {"token": "hunter2"}, "locale": "de-DE"}"#,
+        )
+        .unwrap();
+        let layout = Layout {
+            env_prefix: "FIXME_TEST_DEBUG_".to_string(),
+            config_paths: vec![path.clone()],
+        };
+
+        let settings = layout.debug(None);
+        let find = |key: &str| settings.iter().find(|setting| setting.key == key).unwrap();
+
+        assert_eq!(MASK, find("auth.token").value);
+        assert_eq!(format!("file {}", path.display()), find("locale").source);
+        assert_eq!("default", find("verbosity").source);
+        assert_eq!("unset", find("network.proxy").source);
+        assert!(find("network.proxy").value.is_empty());
+    }
+
+    #[test]
+    fn test_set_creates_nested_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixme").join("config.json");
+
+        set(&path, "verbosity", "debug").unwrap();
+        set(&path, "theme.header", "bold red").unwrap();
+
+        let handler = JsonFileHandler::new(&path);
+        assert_eq!(
+            Some("debug".to_string()),
+            handler.handle_request("verbosity")
+        );
+        assert_eq!(
+            Some("bold red".to_string()),
+            handler.handle_request("theme.header")
+        );
+        let root = handler.load().unwrap().unwrap();
+        assert_eq!(vec!["theme.header", "verbosity"], flatten_keys(&root));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_keeps_secrets_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        set(&path, "auth.token", "s3cr3t").unwrap();
+        set(&path, "verbosity", "debug").unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+/// Times the phases of startup, for the hidden `--startup-report` flag.
+pub struct StartupReport {
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl StartupReport {
+    /// Starts timing the first phase at `started`.
+    pub fn new(started: Instant) -> Self {
+        StartupReport {
+            last: started,
+            phases: Vec::new(),
+        }
+    }
+
+    /// Ends the current phase, naming it `phase`, and starts the next.
+    pub fn mark(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, duration)| *duration).sum()
+    }
+
+    /// Formats the phases as aligned lines, followed by the total.
+    pub fn render(&self) -> String {
+        let width = self
+            .phases
+            .iter()
+            .map(|(phase, _)| phase.len())
+            .max()
+            .unwrap_or_default()
+            .max("total".len());
+        self.phases
+            .iter()
+            .copied()
+            .chain([("total", self.total())])
+            .map(|(phase, duration)| format!("{:width$}  {:>8.3}ms", phase, millis(duration)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_lists_phases_and_total() {
+        let mut report = StartupReport::new(Instant::now());
+        report.mark("parsing");
+        report.mark("logging");
+
+        let rendered = report.render();
+
+        let phases: Vec<&str> = rendered
+            .lines()
+            .map(|line| line.split_whitespace().next().unwrap())
+            .collect();
+        assert_eq!(vec!["parsing", "logging", "total"], phases);
+        assert!(rendered.ends_with("ms"));
+    }
+}
@@ -0,0 +1,172 @@
+//! A small persistent key-value store for what the application remembers
+//! between runs, such as when it last ran or which notices the user has
+//! seen, and cursors into paged remote listings.
+//!
+//! The store is one JSON file in the [state directory](crate::paths::state_dir),
+//! replaced atomically on every change so that a crash never leaves it half
+//! written. The file records its format version; older files are migrated
+//! when opened, and newer ones are refused rather than overwritten.
+
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{Error, Result};
+
+/// Version of the file format written by this build.
+pub const VERSION: u64 = 1;
+
+/// Upgrades the file's top-level object from one version to the next.
+type Migration = fn(Map<String, Value>) -> Map<String, Value>;
+
+/// Migrations from each older version to the next: the first upgrades
+/// version 0 to 1, and so on.
+const MIGRATIONS: &[Migration] = &[
+    // Version 0 was a flat object of values, without a version.
+    |values| Map::from_iter([("values".to_string(), Value::Object(values))]),
+];
+
+/// Returns the file of the application's [`Store`].
+pub fn path() -> PathBuf {
+    crate::paths::state_dir().join("state.json")
+}
+
+/// The key-value store, read when opened and written on every change.
+#[derive(Debug)]
+pub struct Store {
+    path: PathBuf,
+    values: Map<String, Value>,
+}
+
+impl Store {
+    /// Opens the store in `path`, empty if the file does not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or was written
+    /// by a newer version of the application.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Store {
+                    path,
+                    values: Map::new(),
+                })
+            }
+            Err(e) => return Err(Error::io(&path)(e)),
+        };
+        let invalid = |reason: String| {
+            Error::Subcommand(format!(
+                "cannot read state from {}: {}",
+                path.display(),
+                reason
+            ))
+        };
+        let Value::Object(mut root) =
+            serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?
+        else {
+            return Err(invalid("not a JSON object".to_string()));
+        };
+        let version = match root.remove("version") {
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| invalid("the version is not a number".to_string()))?,
+            None => 0,
+        };
+        if version > VERSION {
+            return Err(invalid(format!(
+                "written by a newer version (format {}, this build reads up to {})",
+                version, VERSION
+            )));
+        }
+        for migrate in &MIGRATIONS[version as usize..] {
+            root = migrate(root);
+        }
+        let values = match root.remove("values") {
+            Some(Value::Object(values)) => values,
+            _ => Map::new(),
+        };
+        Ok(Store { path, values })
+    }
+
+    /// Returns the value stored under `key`, if there is one of type `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.values
+            .get(key)
+            .and_then(|value| T::deserialize(value).ok())
+    }
+
+    /// Stores `value` under `key` and saves the store.
+    pub fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
+        let value = serde_json::to_value(value)
+            .map_err(|e| Error::Subcommand(format!("cannot store '{}': {}", key, e)))?;
+        self.values.insert(key.to_string(), value);
+        self.save()
+    }
+
+    /// Removes the value under `key`, saving the store if there was one.
+    pub fn remove(&mut self, key: &str) -> Result<Option<Value>> {
+        let removed = self.values.remove(key);
+        if removed.is_some() {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Writes the store to a temporary file next to it, then moves that over
+    /// the store, so readers see either the old or the new content.
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(Error::io(parent))?;
+        }
+        let root = json!({ "version": VERSION, "values": self.values });
+        let content = serde_json::to_string_pretty(&root).expect("JSON values always serialize");
+        let temporary = temporary_path(&self.path);
+        write_synced(&temporary, (content + "\n").as_bytes()).map_err(Error::io(&temporary))?;
+        std::fs::rename(&temporary, &self.path).map_err(Error::io(&self.path))
+    }
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+fn write_synced(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_persists_and_migrates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        std::fs::write(&path, r#"{"last_run": 1700000000}"#).unwrap();
+
+        let mut store = Store::open(&path).unwrap();
+        assert_eq!(Some(1_700_000_000u64), store.get("last_run"));
+        store.set("seen_notice", "1.2.0").unwrap();
+        assert!(store.remove("last_run").unwrap().is_some());
+
+        let store = Store::open(&path).unwrap();
+        assert_eq!(None, store.get::<u64>("last_run"));
+        assert_eq!(Some("1.2.0".to_string()), store.get("seen_notice"));
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json!(VERSION), saved["version"]);
+
+        std::fs::write(&path, r#"{"version": 99, "values": {}}"#).unwrap();
+        assert!(Store::open(&path).is_err());
+    }
+}
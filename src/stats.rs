@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+/// The resources a run used, for the `--stats` flag. What the platform does
+/// not report is left out.
+#[derive(Debug, PartialEq)]
+pub struct Usage {
+    /// Time since the run started.
+    pub wall: Duration,
+    /// Most memory the process held at once, in bytes.
+    pub peak_rss: Option<u64>,
+    /// CPU time spent running the application's own code.
+    pub user: Option<Duration>,
+    /// CPU time the kernel spent on the application's behalf.
+    pub system: Option<Duration>,
+}
+
+impl Usage {
+    /// Measures the resources used by the process since `started`.
+    pub fn since(started: Instant) -> Self {
+        let mut usage = Usage {
+            wall: started.elapsed(),
+            peak_rss: None,
+            user: None,
+            system: None,
+        };
+        #[cfg(unix)]
+        {
+            // SAFETY: getrusage only writes to the zeroed struct it is given.
+            let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+            if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut rusage) } == 0 {
+                let time =
+                    |t: libc::timeval| Duration::new(t.tv_sec as u64, t.tv_usec as u32 * 1000);
+                // macOS reports the peak in bytes, other systems in KiB.
+                let unit = if cfg!(target_os = "macos") { 1 } else { 1024 };
+                usage.peak_rss = Some(rusage.ru_maxrss as u64 * unit);
+                usage.user = Some(time(rusage.ru_utime));
+                usage.system = Some(time(rusage.ru_stime));
+            }
+        }
+        usage
+    }
+
+    /// Formats the measurements as aligned lines.
+    pub fn render(&self) -> String {
+        let seconds = |duration: Duration| format!("{:.3}s", duration.as_secs_f64());
+        let mut lines = vec![("wall time", seconds(self.wall))];
+        if let Some(bytes) = self.peak_rss {
+            let mib = bytes as f64 / (1024.0 * 1024.0);
+            lines.push(("peak memory", format!("{:.1} MiB", mib)));
+        }
+        if let Some(user) = self.user {
+            lines.push(("user time", seconds(user)));
+        }
+        if let Some(system) = self.system {
+            lines.push(("system time", seconds(system)));
+        }
+        let width = lines.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        lines
+            .iter()
+            .map(|(name, value)| format!("{:width$}  {}", name, value))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_leaves_out_what_is_unknown() {
+        let usage = Usage {
+            wall: Duration::from_millis(1500),
+            peak_rss: Some(12 * 1024 * 1024),
+            user: None,
+            system: None,
+        };
+
+        assert_eq!("wall time    1.500s\npeak memory  12.0 MiB", usage.render());
+        #[cfg(unix)]
+        assert!(Usage::since(Instant::now())
+            .peak_rss
+            .is_some_and(|rss| rss > 0));
+    }
+}
@@ -0,0 +1,122 @@
+//! The parts of systemd's service protocol a daemon needs, without linking
+//! libsystemd: readiness and watchdog notifications (`Type=notify`) and
+//! sockets passed by socket activation.
+
+use std::ffi::OsStr;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::unix::net::{UnixDatagram, UnixListener};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::{Error, Result};
+
+/// The first file descriptor systemd passes with socket activation.
+const LISTEN_FDS_START: i32 = 3;
+
+/// The variables of socket activation.
+const LISTEN_VARS: [&str; 3] = ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"];
+
+/// Sends `state`, e.g. `READY=1`, to the service manager.
+///
+/// Returns whether it was sent: without `$NOTIFY_SOCKET` the process is not
+/// run by systemd as `Type=notify` and there is no one to tell.
+pub fn notify(state: &str) -> Result<bool> {
+    match std::env::var_os("NOTIFY_SOCKET") {
+        Some(socket) => notify_to(&socket, state).map(|()| true),
+        None => Ok(false),
+    }
+}
+
+fn notify_to(socket: &OsStr, state: &str) -> Result<()> {
+    let sender = UnixDatagram::unbound().map_err(Error::Output)?;
+    let bytes = socket.as_encoded_bytes();
+    match bytes.strip_prefix(b"@") {
+        // Linux's abstract namespace, where systemd usually listens.
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let address =
+                std::os::unix::net::SocketAddr::from_abstract_name(name).map_err(Error::Output)?;
+            sender.send_to_addr(state.as_bytes(), &address)
+        }
+        _ => sender.send_to(state.as_bytes(), socket),
+    }
+    .map(|_| ())
+    .map_err(Error::Output)
+}
+
+/// Returns how often to send `WATCHDOG=1`, half the interval systemd
+/// expects, or `None` if the service has no watchdog.
+pub fn watchdog_interval() -> Option<Duration> {
+    if std::env::var("WATCHDOG_PID").is_ok_and(|pid| !is_this_process(&pid)) {
+        return None;
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    Some(Duration::from_micros(usec) / 2)
+}
+
+/// Takes the sockets systemd passed with socket activation, in the order of
+/// the socket unit's `Listen*=` lines. Returns none when not socket activated
+/// or when they were taken already.
+///
+/// The environment is left alone, as other threads may be reading it; spawn
+/// children with [`without_sockets`] instead.
+pub fn listen_fds() -> Vec<OwnedFd> {
+    static TAKEN: OnceLock<()> = OnceLock::new();
+
+    if !std::env::var("LISTEN_PID").is_ok_and(|pid| is_this_process(&pid)) {
+        return Vec::new();
+    }
+    let count: i32 = match std::env::var("LISTEN_FDS").map(|n| n.parse()) {
+        Ok(Ok(count)) => count,
+        _ => return Vec::new(),
+    };
+    if TAKEN.set(()).is_err() {
+        return Vec::new();
+    }
+    (LISTEN_FDS_START..LISTEN_FDS_START + count)
+        // SAFETY: systemd passes these descriptors open and owned by this
+        // process, and `TAKEN` makes sure they are taken once.
+        .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+        .collect()
+}
+
+/// Removes the variables of socket activation from the environment of
+/// `command`, whose process must not take this one's sockets.
+pub fn without_sockets(command: &mut Command) -> &mut Command {
+    for name in LISTEN_VARS {
+        command.env_remove(name);
+    }
+    command
+}
+
+/// Returns the first socket passed by socket activation as the control
+/// socket's listener, if any.
+pub fn control_listener() -> Option<UnixListener> {
+    listen_fds().into_iter().next().map(UnixListener::from)
+}
+
+/// Whether `pid`, from one of systemd's `*_PID` variables, is this process;
+/// a child inheriting the variables must not act on them.
+fn is_this_process(pid: &str) -> bool {
+    pid.parse() == Ok(std::process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notify_sends_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify.sock");
+        let receiver = UnixDatagram::bind(&path).unwrap();
+
+        notify_to(path.as_os_str(), "READY=1").unwrap();
+
+        let mut buffer = [0; 16];
+        let len = receiver.recv(&mut buffer).unwrap();
+        assert_eq!(b"READY=1", &buffer[..len]);
+    }
+}
@@ -0,0 +1,218 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Where batches of events are sent.
+// FIXME: point at the project's collector.
+pub const ENDPOINT: &str = "https://telemetry.example.com/v1/events";
+
+/// How many events are recorded locally before they are uploaded together.
+pub const BATCH_SIZE: usize = 20;
+
+/// How long an upload waits for the collector.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How large the pending events may grow, in bytes, while uploads fail;
+/// further events are dropped.
+pub const MAX_PENDING_BYTES: u64 = 1024 * 1024;
+
+/// One run of a subcommand. Nothing identifying the user, their arguments or
+/// their files is recorded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    /// Name of the subcommand that ran, e.g. `config`.
+    pub command: String,
+    /// How long the run took, in milliseconds.
+    pub duration_ms: u64,
+    /// Whether the run succeeded.
+    pub success: bool,
+    /// When the run started, in seconds since the epoch.
+    pub timestamp: u64,
+    /// Version of the application.
+    pub version: String,
+    /// Operating system, e.g. `linux`.
+    pub os: String,
+}
+
+impl Event {
+    pub fn new(command: &str, started: SystemTime, duration: Duration, success: bool) -> Self {
+        Event {
+            command: command.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            success,
+            timestamp: started
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            version: crate::identity::IDENTITY.version.to_string(),
+            os: std::env::consts::OS.to_string(),
+        }
+    }
+}
+
+/// Returns the file events wait in until uploaded: `telemetry.jsonl` in the
+/// [state directory](crate::paths::state_dir).
+pub fn events_path() -> PathBuf {
+    crate::paths::state_dir().join("telemetry.jsonl")
+}
+
+/// Returns the file a batch of the events in `path` is moved to while it is
+/// uploaded, so that events recorded meanwhile are kept apart.
+pub fn spool_path(path: &Path) -> PathBuf {
+    path.with_extension("jsonl.sending")
+}
+
+/// Records events locally and uploads them once a [`BATCH_SIZE`] has
+/// accumulated, without delaying the run: the upload happens on a
+/// background thread started before the subcommand runs.
+///
+/// A batch is uploaded at least once: one left behind by a run that exited
+/// before its upload finished is uploaded by the next.
+pub struct Telemetry {
+    path: PathBuf,
+}
+
+impl Telemetry {
+    /// Starts uploading the events pending in `path` to `url` in the
+    /// background if there are enough of them.
+    pub fn start(path: PathBuf, url: &'static str) -> Self {
+        match spool(&path) {
+            Ok(Some(spool)) => {
+                std::thread::spawn(move || {
+                    if let Err(e) = upload(&spool, url) {
+                        debug!("telemetry upload failed: {}", e);
+                    }
+                });
+            }
+            Ok(None) => {}
+            Err(e) => debug!("cannot read telemetry events: {}", e),
+        }
+        Telemetry { path }
+    }
+
+    /// Appends `event` to the pending events.
+    pub fn record(&self, event: &Event) {
+        if let Err(e) = append(&self.path, event) {
+            debug!("cannot record telemetry event: {}", e);
+        }
+    }
+}
+
+/// Returns the events recorded in `path` and not yet uploaded.
+pub fn pending(path: &Path) -> Result<Vec<Event>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::io(path)(e)),
+    };
+    // Skip lines a concurrent run was still writing, rather than lose every event.
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn append(path: &Path, event: &Event) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(Error::io(dir))?;
+    }
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.len() >= MAX_PENDING_BYTES => {
+            debug!("dropping telemetry event: {} is full", path.display());
+            return Ok(());
+        }
+        _ => {}
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(Error::io(path))?;
+    let line = serde_json::to_string(event).expect("event serializes");
+    writeln!(file, "{}", line).map_err(Error::io(path))
+}
+
+/// Returns the [spool](spool_path) of `path` if a batch is waiting there,
+/// first moving the pending events into it if there are enough of them.
+fn spool(path: &Path) -> Result<Option<PathBuf>> {
+    let spool = spool_path(path);
+    if pending(path)?.len() >= BATCH_SIZE {
+        // Linking fails rather than replace a batch still waiting, and
+        // events appended until the original is removed land in the batch.
+        match std::fs::hard_link(path, &spool) {
+            Ok(()) => std::fs::remove_file(path).map_err(Error::io(path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(Error::io(&spool)(e)),
+        }
+    }
+    Ok(spool.exists().then_some(spool))
+}
+
+/// Sends the events in `spool` to `url`, then removes it.
+fn upload(spool: &Path, url: &str) -> Result<()> {
+    send(url, &pending(spool)?)?;
+    std::fs::remove_file(spool).map_err(Error::io(spool))
+}
+
+#[cfg(feature = "network")]
+fn send(url: &str, batch: &[Event]) -> Result<()> {
+    crate::net::client()?
+        .post(url)
+        .timeout(TIMEOUT)
+        .send_json(batch)
+        .inspect_err(|_| {
+            crate::metrics::increment(
+                &crate::metrics::REMOTE_FETCH_FAILURES,
+                &[("target", "telemetry")],
+            )
+        })
+        .map_err(|e| Error::Subcommand(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "network"))]
+fn send(_url: &str, _batch: &[Event]) -> Result<()> {
+    Err(Error::Subcommand(
+        "built without the network feature".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_appends_pending_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("telemetry.jsonl");
+        let telemetry = Telemetry::start(path.clone(), ENDPOINT);
+        let event = Event::new("config", UNIX_EPOCH, Duration::from_millis(12), true);
+
+        telemetry.record(&event);
+        telemetry.record(&event);
+
+        assert_eq!(vec![event.clone(), event], pending(&path).unwrap());
+    }
+
+    #[test]
+    fn test_spool_keeps_later_events_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("telemetry.jsonl");
+        let event = Event::new("config", UNIX_EPOCH, Duration::from_millis(12), true);
+        for _ in 0..BATCH_SIZE {
+            append(&path, &event).unwrap();
+        }
+
+        let spooled = spool(&path).unwrap().unwrap();
+        append(&path, &event).unwrap();
+
+        assert_eq!(BATCH_SIZE, pending(&spooled).unwrap().len());
+        assert_eq!(vec![event], pending(&path).unwrap());
+        assert_eq!(Some(spooled), spool(&path).unwrap());
+    }
+}
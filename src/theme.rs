@@ -0,0 +1,112 @@
+use std::io::IsTerminal;
+
+use clap::builder::styling::{AnsiColor, Color, Effects, Style, Styles};
+use cor_args::Handler;
+
+/// Resolves the help output styles from the settings chain.
+///
+/// Each element can be overridden with a `theme.<element>` key whose value is a
+/// space-separated list of effects and at most one color, e.g. `"bold underline yellow"`.
+/// Supported elements are `header`, `usage`, `literal`, `placeholder`, `error`,
+/// `valid` and `invalid`. Unset or unparseable keys keep clap's default style.
+///
+/// When stdout is not a terminal, plain (uncolored) styles are returned regardless
+/// of configuration.
+pub fn styles(settings: &dyn Handler) -> Styles {
+    if !std::io::stdout().is_terminal() {
+        return Styles::plain();
+    }
+    themed(settings)
+}
+
+fn themed(settings: &dyn Handler) -> Styles {
+    let mut styles = Styles::styled();
+    let lookup = |element: &str| {
+        settings
+            .handle_request(&format!("theme.{}", element))
+            .and_then(|spec| parse_style(&spec))
+    };
+    if let Some(style) = lookup("header") {
+        styles = styles.header(style);
+    }
+    if let Some(style) = lookup("usage") {
+        styles = styles.usage(style);
+    }
+    if let Some(style) = lookup("literal") {
+        styles = styles.literal(style);
+    }
+    if let Some(style) = lookup("placeholder") {
+        styles = styles.placeholder(style);
+    }
+    if let Some(style) = lookup("error") {
+        styles = styles.error(style);
+    }
+    if let Some(style) = lookup("valid") {
+        styles = styles.valid(style);
+    }
+    if let Some(style) = lookup("invalid") {
+        styles = styles.invalid(style);
+    }
+    styles
+}
+
+/// Parses a style specification such as `"bold bright-green"`.
+///
+/// Returns `None` if any word is not a known effect or color.
+pub fn parse_style(spec: &str) -> Option<Style> {
+    let mut style = Style::new();
+    for word in spec.split_whitespace() {
+        let word = word.to_ascii_lowercase();
+        style = match word.as_str() {
+            "bold" => style.effects(style.get_effects() | Effects::BOLD),
+            "dimmed" => style.effects(style.get_effects() | Effects::DIMMED),
+            "italic" => style.effects(style.get_effects() | Effects::ITALIC),
+            "underline" => style.effects(style.get_effects() | Effects::UNDERLINE),
+            color => style.fg_color(Some(Color::Ansi(parse_color(color)?))),
+        };
+    }
+    Some(style)
+}
+
+fn parse_color(name: &str) -> Option<AnsiColor> {
+    let color = match name {
+        "black" => AnsiColor::Black,
+        "red" => AnsiColor::Red,
+        "green" => AnsiColor::Green,
+        "yellow" => AnsiColor::Yellow,
+        "blue" => AnsiColor::Blue,
+        "magenta" => AnsiColor::Magenta,
+        "cyan" => AnsiColor::Cyan,
+        "white" => AnsiColor::White,
+        "bright-black" => AnsiColor::BrightBlack,
+        "bright-red" => AnsiColor::BrightRed,
+        "bright-green" => AnsiColor::BrightGreen,
+        "bright-yellow" => AnsiColor::BrightYellow,
+        "bright-blue" => AnsiColor::BrightBlue,
+        "bright-magenta" => AnsiColor::BrightMagenta,
+        "bright-cyan" => AnsiColor::BrightCyan,
+        "bright-white" => AnsiColor::BrightWhite,
+        _ => return None,
+    };
+    Some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(
+            Some(
+                Style::new()
+                    .bold()
+                    .underline()
+                    .fg_color(Some(Color::Ansi(AnsiColor::Yellow)))
+            ),
+            parse_style("bold underline yellow")
+        );
+        assert_eq!(Some(Style::new()), parse_style(""));
+        assert_eq!(None, parse_style("bold chartreuse"));
+    }
+}
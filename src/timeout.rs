@@ -0,0 +1,73 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use log::warn;
+
+use crate::context::CancellationToken;
+use crate::ExitCode;
+
+/// How long a subcommand has to stop after being cancelled for exceeding its
+/// time limit, before the process exits regardless.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Enforces a `--timeout` on a running subcommand.
+///
+/// When the limit passes, the subcommand's [`CancellationToken`] is cancelled
+/// so it can clean up and return. If it is still running after
+/// [`GRACE_PERIOD`], the process exits with [`ExitCode::Timeout`].
+pub struct Watchdog {
+    done: Sender<()>,
+    thread: JoinHandle<bool>,
+}
+
+impl Watchdog {
+    pub fn start(limit: Duration, token: CancellationToken) -> Self {
+        let (done, finished) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            if finished.recv_timeout(limit) != Err(RecvTimeoutError::Timeout) {
+                return false;
+            }
+            warn!(
+                "timed out after {}, cancelling",
+                humantime::format_duration(limit)
+            );
+            token.cancel();
+            if finished.recv_timeout(GRACE_PERIOD) == Err(RecvTimeoutError::Timeout) {
+                eprintln!(
+                    "Error: timed out after {} and did not stop within {}",
+                    humantime::format_duration(limit),
+                    humantime::format_duration(GRACE_PERIOD)
+                );
+                std::process::exit(ExitCode::Timeout.code().into());
+            }
+            true
+        });
+        Watchdog { done, thread }
+    }
+
+    /// Stops watching, returning whether the limit was exceeded.
+    pub fn finish(self) -> bool {
+        let _ = self.done.send(());
+        self.thread.join().unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_cancels_after_limit() {
+        let token = CancellationToken::new();
+
+        let watchdog = Watchdog::start(Duration::from_millis(10), token.clone());
+        while !token.is_cancelled() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(watchdog.finish());
+
+        let watchdog = Watchdog::start(Duration::from_secs(60), CancellationToken::new());
+        assert!(!watchdog.finish());
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Where the latest release is looked up.
+pub const RELEASES_URL: &str =
+    "https://api.github.com/repos/erichschroeter/template-rust-cli/releases/latest";
+
+/// How long a check, and a notice, stay valid before being repeated.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long the background check waits for the release server.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What is remembered between runs about the latest release.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    /// When the latest release was last looked up, in seconds since the epoch.
+    pub checked_at: u64,
+    /// Version of the latest release, as of `checked_at`.
+    pub latest: Option<String>,
+    /// When the user was last told about an update, in seconds since the epoch.
+    pub notified_at: u64,
+}
+
+/// Returns the file the [`State`] is cached in: `update-check.json` in the
+/// [cache directory](crate::paths::cache_dir).
+pub fn state_path() -> PathBuf {
+    crate::paths::cache_dir().join("update-check.json")
+}
+
+/// Checks for a newer release at most once per [`CHECK_INTERVAL`], without
+/// delaying the run: the lookup happens on a background thread and its
+/// result is cached for the next run to report.
+pub struct UpdateCheck {
+    path: PathBuf,
+    state: State,
+}
+
+impl UpdateCheck {
+    /// Loads the cached state from `path` and, if it is stale, starts
+    /// refreshing it from `url` in the background. Without `cached`, the
+    /// release last looked up is ignored and looked up again.
+    pub fn start(path: PathBuf, url: &'static str, now: SystemTime, cached: bool) -> Self {
+        let mut state = load(&path).unwrap_or_default();
+        if !cached {
+            state.checked_at = 0;
+            state.latest = None;
+        }
+        let now = seconds(now);
+        if now.saturating_sub(state.checked_at) >= CHECK_INTERVAL.as_secs() {
+            let (path, mut refreshed) = (path.clone(), state.clone());
+            std::thread::spawn(move || match latest_release(url) {
+                Ok(latest) => {
+                    refreshed.checked_at = now;
+                    refreshed.latest = Some(latest);
+                    if let Err(e) = save(&path, &refreshed) {
+                        debug!("cannot cache update check: {}", e);
+                    }
+                }
+                Err(e) => debug!("update check failed: {}", e),
+            });
+        }
+        UpdateCheck { path, state }
+    }
+
+    /// Returns a notice if the cached latest release is newer than
+    /// `current`, unless one was already given within [`CHECK_INTERVAL`].
+    pub fn notice(&mut self, current: &str, now: SystemTime) -> Option<String> {
+        let latest = self.state.latest.as_deref()?;
+        let now = seconds(now);
+        if !is_newer(latest, current)
+            || now.saturating_sub(self.state.notified_at) < CHECK_INTERVAL.as_secs()
+        {
+            return None;
+        }
+        let notice = format!(
+            "A new version is available: {} -> {}",
+            current,
+            latest.trim_start_matches('v')
+        );
+        // Re-read so a refresh finished meanwhile is kept.
+        let mut state = load(&self.path).unwrap_or_else(|_| self.state.clone());
+        state.notified_at = now;
+        if let Err(e) = save(&self.path, &state) {
+            debug!("cannot cache update check: {}", e);
+        }
+        self.state = state;
+        Some(notice)
+    }
+}
+
+/// Returns whether the version `latest` is greater than `current`, comparing
+/// dot-separated numbers and ignoring a leading `v` and any suffix such as `-rc.1`.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(latest) > parse(current)
+}
+
+/// Looks up the tag of the latest release at `url`, a GitHub releases API endpoint.
+#[cfg(feature = "network")]
+fn latest_release(url: &str) -> Result<String> {
+    #[derive(Deserialize)]
+    struct Release {
+        tag_name: String,
+    }
+    let release: Release = crate::net::client()?
+        .get(url)
+        .timeout(TIMEOUT)
+        .call()
+        .inspect_err(|_| {
+            crate::metrics::increment(
+                &crate::metrics::REMOTE_FETCH_FAILURES,
+                &[("target", "releases")],
+            )
+        })
+        .map_err(|e| Error::Subcommand(e.to_string()))?
+        .into_json()
+        .map_err(Error::Output)?;
+    Ok(release.tag_name)
+}
+
+#[cfg(not(feature = "network"))]
+fn latest_release(_url: &str) -> Result<String> {
+    Err(Error::Subcommand(
+        "built without the network feature".to_string(),
+    ))
+}
+
+fn load(path: &Path) -> Result<State> {
+    let content = std::fs::read_to_string(path).map_err(Error::io(path))?;
+    serde_json::from_str(&content)
+        .map_err(|e| Error::io(path)(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+}
+
+fn save(path: &Path, state: &State) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(Error::io(dir))?;
+    }
+    let content = serde_json::to_string(state).expect("state serializes");
+    std::fs::write(path, content).map_err(Error::io(path))
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer_compares_numerically() {
+        assert!(is_newer("v0.10.0", "0.9.1"));
+        assert!(!is_newer("1.0.0-rc.1", "1.0.0"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+    }
+
+    #[test]
+    fn test_notice_is_given_once_per_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("update-check.json");
+        let now = SystemTime::now();
+        let state = State {
+            checked_at: seconds(now),
+            latest: Some("v9.0.0".to_string()),
+            notified_at: 0,
+        };
+        save(&path, &state).unwrap();
+
+        let mut check = UpdateCheck::start(path.clone(), RELEASES_URL, now, true);
+
+        assert_eq!(
+            Some("A new version is available: 0.1.0 -> 9.0.0".to_string()),
+            check.notice("0.1.0", now)
+        );
+        assert_eq!(None, check.notice("0.1.0", now));
+        assert_eq!(seconds(now), load(&path).unwrap().notified_at);
+    }
+}
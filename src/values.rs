@@ -0,0 +1,369 @@
+//! Parsers for common kinds of values, shared by command-line arguments and
+//! the [`schema`](crate::schema) so that both accept the same input and
+//! explain a rejection the same way.
+//!
+//! Each parser turns a `&str` into a typed value, or a reason it is invalid,
+//! and can be given to clap as the `value_parser` of an argument:
+//!
+//! ```
+//! use clap::Parser;
+//! use template_rust_cli::values::{self, Url};
+//!
+//! #[derive(Parser)]
+//! struct Args {
+//!     /// Where to send reports
+//!     #[arg(long, value_parser = values::url(&["https"]))]
+//!     endpoint: Url,
+//!     /// Address to listen on
+//!     #[arg(long, value_parser = values::socket_addr)]
+//!     listen: std::net::SocketAddr,
+//! }
+//!
+//! let args = Args::try_parse_from(["app", "--endpoint", "http://example.com", "--listen", "0.0.0.0:80"]);
+//! assert!(args.is_err());
+//! ```
+
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub use email_address::EmailAddress;
+pub use url::Url;
+
+/// Returns a parser of absolute URLs whose scheme is one of `schemes`, e.g.
+/// `&["http", "https"]`; an empty list allows any scheme.
+pub fn url(
+    schemes: &'static [&'static str],
+) -> impl Fn(&str) -> Result<Url, String> + Clone + Send + Sync + 'static {
+    move |value| {
+        let url = Url::parse(value).map_err(|e| format!("must be a URL: {}", e))?;
+        if !schemes.is_empty() && !schemes.contains(&url.scheme()) {
+            return Err(format!(
+                "must be a URL with one of the schemes: {}",
+                schemes.join(", ")
+            ));
+        }
+        Ok(url)
+    }
+}
+
+/// Parses a boolean: `true`, `1`, `yes` or `on`, or `false`, `0`, `no` or
+/// `off`, as environment variables often spell them.
+pub fn boolean(value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        _ => Err("must be true or false".to_string()),
+    }
+}
+
+/// Parses an IPv4 or IPv6 address, e.g. `127.0.0.1` or `::1`.
+pub fn ip_addr(value: &str) -> Result<IpAddr, String> {
+    value
+        .parse()
+        .map_err(|_| "must be an IP address such as 127.0.0.1 or ::1".to_string())
+}
+
+/// Parses an IP address and port, e.g. `127.0.0.1:8080` or `[::1]:8080`.
+pub fn socket_addr(value: &str) -> Result<SocketAddr, String> {
+    value
+        .parse()
+        .map_err(|_| "must be an IP address and port such as 127.0.0.1:8080".to_string())
+}
+
+/// Parses an email address, e.g. `someone@example.com`.
+pub fn email(value: &str) -> Result<EmailAddress, String> {
+    value
+        .parse()
+        .map_err(|_| "must be an email address such as someone@example.com".to_string())
+}
+
+/// Parses a count with an optional decimal suffix, e.g. `250`, `2k` or
+/// `1.5M`; `k`, `M`, `G` and `T` multiply by powers of 1000.
+pub fn count(value: &str) -> Result<u64, String> {
+    let reason = || "must be a whole number, optionally with k, M, G or T, e.g. 2k".to_string();
+    let (number, suffix) = split_number(value).ok_or_else(reason)?;
+    let multiplier = match suffix {
+        "" => 1,
+        "k" | "K" => 1_000,
+        "M" => 1_000_000,
+        "G" => 1_000_000_000,
+        "T" => 1_000_000_000_000,
+        _ => return Err(reason()),
+    };
+    scale(number, multiplier).ok_or_else(reason)
+}
+
+/// Parses a size in bytes with an optional unit, e.g. `512`, `64kB` or
+/// `1.5GiB`: `kB`, `MB`, `GB` and `TB` are powers of 1000, `KiB`, `MiB`,
+/// `GiB` and `TiB` powers of 1024. Units ignore case, and the `B` may be left
+/// out.
+pub fn size(value: &str) -> Result<u64, String> {
+    let reason = || "must be a number of bytes, optionally with a unit, e.g. 1.5GiB".to_string();
+    let (number, unit) = split_number(value).ok_or_else(reason)?;
+    let unit = unit.to_ascii_lowercase();
+    let unit = unit.strip_suffix('b').unwrap_or(&unit);
+    let multiplier: u64 = match unit {
+        "" => 1,
+        "k" => 1_000,
+        "m" => 1_000_000,
+        "g" => 1_000_000_000,
+        "t" => 1_000_000_000_000,
+        "ki" => 1 << 10,
+        "mi" => 1 << 20,
+        "gi" => 1 << 30,
+        "ti" => 1 << 40,
+        _ => return Err(reason()),
+    };
+    scale(number, multiplier).ok_or_else(reason)
+}
+
+/// Parses a duration such as `250ms`, `30s` or `1h 30m`.
+pub fn duration(value: &str) -> Result<Duration, String> {
+    humantime::parse_duration(value)
+        .map_err(|e| format!("must be a duration such as 250ms, 30s or 5m: {}", e))
+}
+
+/// Splits `value` into its leading number and the unit after it, if the
+/// number uses only digits and a `.`, whatever the locale.
+fn split_number(value: &str) -> Option<(&str, &str)> {
+    let value = value.trim();
+    let end = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(end);
+    match number.is_empty() || number == "." {
+        true => None,
+        false => Some((number, unit.trim_start())),
+    }
+}
+
+/// Returns `number` times `multiplier`, if that is a whole number that fits.
+///
+/// The whole and fractional digits are scaled separately in integers, so
+/// that e.g. `4.1` times a million is exact.
+fn scale(number: &str, multiplier: u64) -> Option<u64> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let whole: u128 = match whole {
+        "" => 0,
+        whole => whole.parse().ok()?,
+    };
+    let mut scaled = whole.checked_mul(multiplier.into())?;
+    let fraction = fraction.trim_end_matches('0');
+    if !fraction.is_empty() {
+        let digits: u128 = fraction.parse().ok()?;
+        let denominator = 10u128.checked_pow(fraction.len().try_into().ok()?)?;
+        let part = digits.checked_mul(multiplier.into())?;
+        if part % denominator != 0 {
+            return None;
+        }
+        scaled = scaled.checked_add(part / denominator)?;
+    }
+    scaled.try_into().ok()
+}
+
+/// What a [`PathArg`] must name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathKind {
+    File,
+    Dir,
+}
+
+/// A parser of paths, given on the command line or in a setting, that
+/// expands a leading `~` to the home directory and, as configured, checks
+/// that the path exists and what it names, and makes it absolute and free of
+/// `.`, `..` and symbolic links:
+///
+/// ```
+/// # use clap::Parser;
+/// # use template_rust_cli::values::PathArg;
+/// #[derive(Parser)]
+/// struct Args {
+///     #[arg(value_parser = PathArg::new().file().parser())]
+///     input: std::path::PathBuf,
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PathArg {
+    exists: bool,
+    kind: Option<PathKind>,
+    canonicalize: bool,
+}
+
+impl PathArg {
+    /// Accepts any path, only expanding `~`.
+    pub const fn new() -> Self {
+        PathArg {
+            exists: false,
+            kind: None,
+            canonicalize: false,
+        }
+    }
+
+    /// Requires the path to exist.
+    pub const fn exists(mut self) -> Self {
+        self.exists = true;
+        self
+    }
+
+    /// Requires the path to be an existing file.
+    pub const fn file(mut self) -> Self {
+        self.exists = true;
+        self.kind = Some(PathKind::File);
+        self
+    }
+
+    /// Requires the path to be an existing directory.
+    pub const fn dir(mut self) -> Self {
+        self.exists = true;
+        self.kind = Some(PathKind::Dir);
+        self
+    }
+
+    /// Resolves the path to its canonical, absolute form, which requires it
+    /// to exist. On Windows, the result avoids `\\?\` prefixes where possible.
+    pub const fn canonicalize(mut self) -> Self {
+        self.exists = true;
+        self.canonicalize = true;
+        self
+    }
+
+    /// Checks `value` and returns the path it names.
+    pub fn parse(&self, value: &str) -> Result<PathBuf, String> {
+        let path = expand_home(value)?;
+        if self.exists {
+            let metadata = std::fs::metadata(&path).map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => format!("{} does not exist", path.display()),
+                _ => format!("cannot access {}: {}", path.display(), e),
+            })?;
+            match self.kind {
+                Some(PathKind::File) if metadata.is_dir() => {
+                    return Err(format!("{} is a directory, not a file", path.display()))
+                }
+                Some(PathKind::Dir) if !metadata.is_dir() => {
+                    return Err(format!("{} is not a directory", path.display()))
+                }
+                _ => {}
+            }
+        }
+        if self.canonicalize {
+            return canonical(&path)
+                .map_err(|e| format!("cannot resolve {}: {}", path.display(), e));
+        }
+        Ok(path)
+    }
+
+    /// Returns [`PathArg::parse`] as a clap value parser.
+    pub fn parser(
+        self,
+    ) -> impl Fn(&str) -> Result<PathBuf, String> + Clone + Send + Sync + 'static {
+        move |value| self.parse(value)
+    }
+}
+
+/// Replaces a leading `~` in `value` with the user's home directory.
+fn expand_home(value: &str) -> Result<PathBuf, String> {
+    let rest = match value.strip_prefix('~') {
+        Some("") => "",
+        Some(rest) if rest.starts_with(['/', std::path::MAIN_SEPARATOR]) => &rest[1..],
+        _ => return Ok(PathBuf::from(value)),
+    };
+    let dirs = directories::BaseDirs::new()
+        .ok_or_else(|| format!("cannot expand {}: no home directory", value))?;
+    Ok(dirs.home_dir().join(rest))
+}
+
+#[cfg(windows)]
+fn canonical(path: &Path) -> std::io::Result<PathBuf> {
+    dunce::canonicalize(path)
+}
+
+#[cfg(not(windows))]
+fn canonical(path: &Path) -> std::io::Result<PathBuf> {
+    std::fs::canonicalize(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsers_accept_and_explain() {
+        let web = url(&["http", "https"]);
+
+        assert_eq!(
+            "example.com",
+            web("https://example.com/a").unwrap().host_str().unwrap()
+        );
+        assert_eq!(
+            "must be a URL with one of the schemes: http, https",
+            web("ftp://example.com").unwrap_err()
+        );
+        assert!(web("example.com").is_err());
+        assert!(url(&[])("ftp://example.com").is_ok());
+        assert!(ip_addr("::1").unwrap().is_loopback());
+        assert!(ip_addr("localhost").is_err());
+        assert_eq!(8080, socket_addr("[::1]:8080").unwrap().port());
+        assert!(socket_addr("127.0.0.1").is_err());
+        assert_eq!(
+            "example.com",
+            email("someone@example.com").unwrap().domain()
+        );
+        assert!(email("someone").is_err());
+        assert_eq!(Ok(true), boolean("yes"));
+        assert_eq!(Ok(false), boolean("off"));
+        assert!(boolean("maybe").is_err());
+    }
+
+    #[test]
+    fn test_numbers_take_unit_suffixes() {
+        assert_eq!(Ok(2_000), count("2k"));
+        assert_eq!(Ok(1_500_000), count("1.5M"));
+        assert_eq!(Ok(4_100_000), count("4.1M"));
+        assert_eq!(Ok(16_100), size("16.1k"));
+        assert!(count("1.5.0k").is_err());
+        assert!(count("1.5").is_err());
+        assert!(count("2,5k").is_err());
+        assert_eq!(Ok(1_610_612_736), size("1.5GiB"));
+        assert_eq!(Ok(64_000), size("64 kB"));
+        assert_eq!(Ok(512), size("512"));
+        assert!(size("12 parsecs").is_err());
+        assert_eq!(Ok(Duration::from_millis(250)), duration("250ms"));
+    }
+
+    #[test]
+    fn test_path_arg_checks_kind_and_expands_home() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let dir_name = dir.path().to_str().unwrap();
+
+        assert!(PathArg::new().file().parse(file.to_str().unwrap()).is_ok());
+        assert_eq!(
+            format!("{} is a directory, not a file", dir_name),
+            PathArg::new().file().parse(dir_name).unwrap_err()
+        );
+        assert!(PathArg::new().dir().parse(file.to_str().unwrap()).is_err());
+        assert!(PathArg::new()
+            .exists()
+            .parse(&format!("{}/missing", dir_name))
+            .unwrap_err()
+            .ends_with("does not exist"));
+        assert_eq!(
+            PathArg::new().parse("~/notes").unwrap(),
+            directories::BaseDirs::new()
+                .unwrap()
+                .home_dir()
+                .join("notes")
+        );
+        let canonical = PathArg::new()
+            .canonicalize()
+            .parse(&format!(
+                "{}/../{}",
+                dir_name,
+                dir.path().file_name().unwrap().to_str().unwrap()
+            ))
+            .unwrap();
+        assert_eq!(std::fs::canonicalize(dir.path()).unwrap(), canonical);
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Serialize, Serializer};
+
+/// Provenance of this build, embedded by the build script.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct BuildInfo {
+    /// Name of the package, e.g. `template-rust-cli`.
+    pub name: &'static str,
+    /// Version of the package, e.g. `0.1.0`.
+    pub version: &'static str,
+    /// Abbreviated hash of the git commit built, if built from a checkout.
+    pub commit: Option<&'static str>,
+    /// Git tag pointing at the commit, if any.
+    pub tag: Option<&'static str>,
+    /// Whether tracked files had uncommitted changes, if built from a checkout.
+    pub dirty: Option<bool>,
+    /// When the binary was built, in RFC 3339 format.
+    pub built: &'static str,
+    /// Version of the compiler, e.g. `rustc 1.80.0 (051478957 2024-07-21)`.
+    pub rustc: Option<&'static str>,
+    /// Target triple, e.g. `x86_64-unknown-linux-gnu`.
+    pub target: &'static str,
+    /// Cargo features enabled, comma-separated; listed when serialized.
+    #[serde(serialize_with = "comma_separated")]
+    pub features: &'static str,
+}
+
+/// The [`BuildInfo`] of the running binary.
+pub const BUILD: BuildInfo = BuildInfo {
+    name: crate::identity::IDENTITY.binary,
+    version: crate::identity::IDENTITY.version,
+    commit: non_empty(env!("FIXME_BUILD_COMMIT")),
+    tag: non_empty(env!("FIXME_BUILD_TAG")),
+    dirty: match env!("FIXME_BUILD_DIRTY").as_bytes() {
+        b"true" => Some(true),
+        b"false" => Some(false),
+        _ => None,
+    },
+    built: env!("FIXME_BUILD_TIMESTAMP"),
+    rustc: non_empty(env!("FIXME_BUILD_RUSTC")),
+    target: env!("FIXME_BUILD_TARGET"),
+    features: env!("FIXME_BUILD_FEATURES"),
+};
+
+const fn non_empty(value: &'static str) -> Option<&'static str> {
+    match value.is_empty() {
+        true => None,
+        false => Some(value),
+    }
+}
+
+fn comma_separated<S: Serializer>(list: &&'static str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(list.split(',').filter(|item| !item.is_empty()))
+}
+
+impl BuildInfo {
+    /// One-line version, shown by `-V`: the version and, when known, the
+    /// commit with a `-dirty` mark.
+    pub fn short(&self) -> String {
+        match self.commit {
+            Some(commit) => format!(
+                "{} ({}{})",
+                self.version,
+                commit,
+                if self.dirty == Some(true) {
+                    "-dirty"
+                } else {
+                    ""
+                }
+            ),
+            None => self.version.to_string(),
+        }
+    }
+
+    /// Detailed version, shown by `--version` and the `version` subcommand.
+    pub fn long(&self) -> String {
+        let unknown = "unknown";
+        format!(
+            "{}\ncommit: {}\ntag: {}\nbuilt: {}\nrustc: {}\ntarget: {}\nfeatures: {}",
+            self.short(),
+            self.commit.unwrap_or(unknown),
+            self.tag.unwrap_or("none"),
+            self.built,
+            self.rustc.unwrap_or(unknown),
+            self.target,
+            match self.features {
+                "" => "none".to_string(),
+                features => features.replace(',', ", "),
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_marks_dirty_builds() {
+        let info = BuildInfo {
+            commit: Some("0123456789ab"),
+            dirty: Some(true),
+            ..BUILD
+        };
+
+        assert_eq!(
+            format!("{} (0123456789ab-dirty)", BUILD.version),
+            info.short()
+        );
+        assert!(info.long().contains("\ntarget: "));
+
+        let features = BuildInfo {
+            features: "progress,yaml",
+            ..BUILD
+        };
+        let json = serde_json::to_value(features).unwrap();
+        assert_eq!(serde_json::json!(["progress", "yaml"]), json["features"]);
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+
+use crate::context::CancellationToken;
+use crate::{Error, Result};
+
+/// How long changes must settle before `--watch` runs the subcommand again,
+/// so that saving several files at once triggers a single run.
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How often [`Watcher::wait`] checks whether it was cancelled.
+const CANCELLATION_POLL: Duration = Duration::from_millis(100);
+
+/// ANSI sequence clearing the terminal and moving the cursor home.
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Watches files and directories, recursively, for changes.
+pub struct Watcher {
+    _debouncer: Debouncer<RecommendedWatcher>,
+    events: Receiver<DebounceEventResult>,
+}
+
+impl Watcher {
+    /// Starts watching `paths`, grouping changes that happen within `debounce`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if a path does not exist or cannot be watched.
+    pub fn new(paths: &[PathBuf], debounce: Duration) -> Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let mut debouncer =
+            new_debouncer(debounce, sender).map_err(|e| into_error(e, Path::new(".")))?;
+        for path in paths {
+            debouncer
+                .watcher()
+                .watch(path, RecursiveMode::Recursive)
+                .map_err(|e| into_error(e, path))?;
+        }
+        Ok(Watcher {
+            _debouncer: debouncer,
+            events,
+        })
+    }
+
+    /// Blocks until something changes, returning the changed paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if watching failed, or [`Error::Interrupted`]
+    /// once `cancellation` is cancelled.
+    pub fn wait(&self, cancellation: &CancellationToken) -> Result<Vec<PathBuf>> {
+        let events = loop {
+            if cancellation.is_cancelled() {
+                return Err(Error::Interrupted);
+            }
+            match self.events.recv_timeout(CANCELLATION_POLL) {
+                Ok(events) => break events,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(Error::Subcommand("file watcher stopped".to_string()))
+                }
+            }
+        }
+        .map_err(|e| into_error(e, Path::new(".")))?;
+        Ok(events.into_iter().map(|event| event.path).collect())
+    }
+}
+
+fn into_error(error: notify_debouncer_mini::notify::Error, path: &Path) -> Error {
+    let path = error
+        .paths
+        .first()
+        .map_or(path, PathBuf::as_path)
+        .to_path_buf();
+    let source = match error.kind {
+        notify_debouncer_mini::notify::ErrorKind::Io(e) => e,
+        kind => std::io::Error::other(format!("{:?}", kind)),
+    };
+    Error::io(path)(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_reports_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let watcher = Watcher::new(&[dir.path().to_path_buf()], Duration::from_millis(50)).unwrap();
+
+        std::fs::write(dir.path().join("data.txt"), "changed").unwrap();
+
+        assert!(!watcher.wait(&CancellationToken::new()).unwrap().is_empty());
+
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        assert!(matches!(watcher.wait(&cancelled), Err(Error::Interrupted)));
+        assert!(Watcher::new(&[dir.path().join("missing")], DEBOUNCE).is_err());
+    }
+}
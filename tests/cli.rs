@@ -0,0 +1,152 @@
+// The harness isolates runs from the user's directories on Unix only.
+#![cfg(unix)]
+
+mod common;
+
+use common::cmd;
+use predicates::prelude::*;
+use serde_json::json;
+
+#[test]
+fn test_subcommand_output_is_json_when_piped() {
+    cmd()
+        .args(["fixme1", "foo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""input": "foo""#));
+}
+
+#[test]
+fn test_config_set_is_read_back() {
+    let mut fixme = cmd();
+    fixme
+        .args(["config", "set", "verbosity", "debug"])
+        .assert()
+        .success();
+
+    fixme
+        .args(["config", "get", "verbosity"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("debug"));
+}
+
+#[test]
+fn test_config_file_is_used() {
+    cmd()
+        .with_config(json!({ "verbosity": "warn" }))
+        .args(["config", "get", "verbosity"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("warn"));
+}
+
+#[test]
+fn test_unknown_subcommand_is_usage_error() {
+    cmd()
+        .arg("fixme3")
+        .assert()
+        .code(64)
+        .stderr(predicate::str::contains("fixme3"));
+}
+
+#[test]
+fn test_environment_overrides_config_file() {
+    cmd()
+        .with_config(json!({ "verbosity": "warn" }))
+        .env("FIXME_verbosity", "trace")
+        .args(["config", "get", "verbosity"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("trace"));
+}
+
+#[test]
+fn test_batch_reads_items_from_stdin() {
+    cmd()
+        .args(["--batch", "-", "fixme1"])
+        .stdin("one\ntwo\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("one").and(predicate::str::contains("two")));
+}
+
+#[test]
+fn test_help_wraps_at_columns() {
+    let output = cmd()
+        .env("COLUMNS", "60")
+        .args(["fixme1", "--help"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let help = String::from_utf8(output).unwrap();
+    assert!(help
+        .lines()
+        .filter(|line| !line.starts_with("Usage:"))
+        .all(|line| line.chars().count() <= 60));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_closed_output_ends_the_run_quietly() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = cmd()
+        .args(["--batch", "-", "fixme1"])
+        .command()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stdout.take());
+    let items = "foo\n".repeat(10_000);
+    // The run may end before it has read every item.
+    let _ = child.stdin.take().unwrap().write_all(items.as_bytes());
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(Some(141), output.status.code());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("Error") && !stderr.contains("panicked"));
+}
+
+#[test]
+fn test_detached_daemon_reloads_its_level() {
+    /// Stops the daemon even if the test fails.
+    struct Stop<'a>(&'a mut common::Harness);
+    impl Drop for Stop<'_> {
+        fn drop(&mut self) {
+            let _ = self.0.args(["daemon", "stop"]).command().output();
+        }
+    }
+
+    let mut fixme = cmd();
+    fixme.with_config(json!({ "verbosity": "warn" }));
+    let started = fixme.args(["daemon", "start"]).command().output().unwrap();
+    let fixme = Stop(&mut fixme);
+    assert!(started.status.success(), "{:?}", started);
+    let stderr = String::from_utf8_lossy(&started.stderr);
+    let log = stderr
+        .split_once("logging to ")
+        .map(|(_, log)| std::path::PathBuf::from(log.trim()))
+        .expect("the log file is reported");
+
+    fixme.0.with_config(json!({ "verbosity": "debug" }));
+    fixme.0.args(["ctl", "reload-config"]).assert().success();
+
+    // The settings are reloaded by another thread of the daemon.
+    let reloaded = "reloaded settings; verbosity changed from 'warn' to 'debug'";
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while !std::fs::read_to_string(&log).unwrap().contains(reloaded) {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "{} is not logged",
+            reloaded
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
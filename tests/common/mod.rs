@@ -0,0 +1,162 @@
+//! Helpers for running the binary end to end, isolated from the user's
+//! home directory, config file, runtime directory and `FIXME_` environment
+//! variables.
+//!
+//! The isolation relies on the binary honoring `$HOME` and the `$XDG_*`
+//! variables, which it does on Unix only, so the tests using these helpers
+//! only run there.
+
+// Each test crate includes this module and uses only some of the helpers.
+#![allow(dead_code)]
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+use assert_cmd::assert::Assert;
+use tempfile::TempDir;
+
+/// Returns a harness running the binary in a fresh, empty home directory.
+pub fn cmd() -> Harness {
+    Harness::new()
+}
+
+/// Runs the binary in a temporary home directory, removed when the harness
+/// is dropped, so that runs can share config files and state.
+pub struct Harness {
+    home: TempDir,
+    args: Vec<OsString>,
+    env: Vec<(OsString, OsString)>,
+    stdin: Option<Vec<u8>>,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        Harness {
+            home: tempfile::tempdir().expect("temporary home is created"),
+            args: Vec::new(),
+            env: Vec::new(),
+            stdin: None,
+        }
+    }
+
+    /// The temporary home directory the binary runs in.
+    pub fn home(&self) -> &Path {
+        self.home.path()
+    }
+
+    /// The config file the binary reads, within [`Harness::home`], as the
+    /// binary itself reports it.
+    pub fn config_path(&self) -> PathBuf {
+        let output = Harness::isolate(std::process::Command::new(bin()), self.home())
+            .args(["--output", "json", "config", "sources"])
+            .output()
+            .expect("the binary runs");
+        let sources: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("sources are JSON");
+        // The first config file is the one settings are written to.
+        let mut files = sources.as_array().expect("sources are a list").iter();
+        let file = files.find(|source| source["kind"] == "file");
+        let path = PathBuf::from(
+            file.expect("a config file is read")["target"]
+                .as_str()
+                .unwrap(),
+        );
+        assert!(
+            path.starts_with(self.home()),
+            "{} is outside the home",
+            path.display()
+        );
+        path
+    }
+
+    /// Writes `config` to the config file.
+    pub fn with_config(&mut self, config: serde_json::Value) -> &mut Self {
+        let path = self.config_path();
+        std::fs::create_dir_all(path.parent().unwrap()).expect("config directory is created");
+        std::fs::write(&path, config.to_string()).expect("config file is written");
+        self
+    }
+
+    /// Adds an argument for the next run.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Adds arguments for the next run.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
+    /// Sets an environment variable for this and every later run.
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, value: V) -> &mut Self {
+        self.env
+            .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    /// Feeds `input` to the next run's standard input.
+    pub fn stdin<S: Into<Vec<u8>>>(&mut self, input: S) -> &mut Self {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    /// Runs the binary with the arguments given since the last run,
+    /// returning its outcome for assertions.
+    pub fn assert(&mut self) -> Assert {
+        let stdin = self.stdin.take();
+        let mut command = assert_cmd::Command::from_std(self.command());
+        if let Some(input) = stdin {
+            command.write_stdin(input);
+        }
+        command.assert()
+    }
+
+    /// Builds the process for the next run, for tests that drive it
+    /// themselves; input given with [`Harness::stdin`] is not fed to it.
+    pub fn command(&mut self) -> std::process::Command {
+        let mut command = Harness::isolate(std::process::Command::new(bin()), self.home());
+        command
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .args(self.args.drain(..));
+        command
+    }
+
+    /// Points `command` at `home` and its directories instead of the user's.
+    fn isolate(mut command: std::process::Command, home: &Path) -> std::process::Command {
+        for (name, _) in std::env::vars_os() {
+            if name.to_string_lossy().starts_with("FIXME_") {
+                command.env_remove(name);
+            }
+        }
+        command
+            .env("HOME", home)
+            .env("USERPROFILE", home)
+            .env("XDG_CONFIG_HOME", home.join(".config"))
+            .env("XDG_CACHE_HOME", home.join(".cache"))
+            .env("XDG_STATE_HOME", home.join(".local").join("state"))
+            .env("XDG_DATA_HOME", home.join(".local").join("share"))
+            .env("XDG_RUNTIME_DIR", home.join("run"))
+            .env("LC_ALL", "en_US.UTF-8")
+            .env_remove("LC_MESSAGES")
+            .env_remove("LANG")
+            .env_remove("COLUMNS");
+        command
+    }
+}
+
+fn bin() -> PathBuf {
+    assert_cmd::cargo::cargo_bin(env!("CARGO_PKG_NAME"))
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Harness::new()
+    }
+}
@@ -0,0 +1,61 @@
+//! Snapshots of help and error output, so that changes to what users see are
+//! reviewed deliberately. Run `INSTA_UPDATE=always cargo test` to accept changes.
+//!
+//! The snapshots are of the default features, which add subcommands and options.
+#![cfg(all(unix, feature = "completions", feature = "yaml"))]
+
+mod common;
+
+use common::cmd;
+
+/// Returns the names of `command`'s subcommands and theirs, as argument lists.
+fn subcommand_paths(command: &clap::Command, parent: &[String]) -> Vec<Vec<String>> {
+    let mut paths = Vec::new();
+    for sub in command
+        .get_subcommands()
+        .filter(|sub| sub.get_name() != "help")
+    {
+        let mut path = parent.to_vec();
+        path.push(sub.get_name().to_string());
+        paths.extend(subcommand_paths(sub, &path));
+        paths.push(path);
+    }
+    paths
+}
+
+fn output(args: &[&str]) -> String {
+    let output = cmd().args(args).assert().get_output().clone();
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+#[test]
+fn test_help() {
+    insta::assert_snapshot!("help", output(&["--help"]));
+    insta::assert_snapshot!("help-all", output(&["--help-all"]));
+
+    for command in template_rust_cli::commands::registry() {
+        for path in subcommand_paths(&command.clap(), &[command.clap().get_name().to_string()])
+            .into_iter()
+            .chain([vec![command.clap().get_name().to_string()]])
+        {
+            let mut args: Vec<&str> = path.iter().map(String::as_str).collect();
+            args.push("--help");
+            insta::assert_snapshot!(format!("help-{}", path.join("-")), output(&args));
+        }
+    }
+}
+
+#[test]
+fn test_errors() {
+    insta::assert_snapshot!("error-unknown-subcommand", output(&["fixme3"]));
+    insta::assert_snapshot!("error-unknown-argument", output(&["fixme1", "--bogus"]));
+    insta::assert_snapshot!("error-unknown-key", output(&["config", "get", "verbostiy"]));
+    insta::assert_snapshot!(
+        "error-invalid-value",
+        output(&["config", "set", "verbosity", "loud"])
+    );
+}